@@ -0,0 +1,422 @@
+// ホスティングの REST API クライアント（Bearer token、rate limit の待ちと再試行、Link のページング）
+use std::{
+    cell::Cell,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::ActionError;
+
+// rate limit に当たったとき、reset までこれ以内なら待って再試行する
+const RATE_LIMIT_MAX_WAIT_SECS: u64 = 60;
+const RATE_LIMIT_RETRIES: u32 = 2;
+// 全ページ取得の上限（100件 × 50 ページ）
+const DEFAULT_MAX_PAGES: u32 = 50;
+
+const JSON_HEADERS: &[(&str, &str)] = &[("Accept", "application/json")];
+
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RateLimit {
+    limit: u64,
+    remaining: u64,
+    // epoch 秒
+    reset: u64,
+}
+
+pub(crate) struct ApiError {
+    status: Option<u16>,
+    message: String,
+    rate_limited: bool,
+    reset: Option<u64>,
+}
+
+impl ApiError {
+    pub(crate) fn status(&self) -> Option<u16> {
+        self.status
+    }
+
+    pub(crate) fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// ActionError に変換（rate limit / 401 / 404 / それ以外で番号を分ける）。family は GH / HOST など
+    pub(crate) fn into_action(self, family: &str, message: &str) -> ActionError {
+        let (n, message) = if self.rate_limited {
+            ("0102", "API rate limit exceeded".to_string())
+        } else if matches!(self.status, Some(401)) {
+            ("0103", "token rejected (401)".to_string())
+        } else if matches!(self.status, Some(404)) {
+            ("0104", format!("{} (not found or no access)", message))
+        } else {
+            ("0100", message.to_string())
+        };
+        let detail = match self.reset {
+            Some(r) if self.rate_limited => format!("{} (reset at epoch {})", self.message, r),
+            _ => self.message,
+        };
+        ActionError {
+            code: format!("{}-{}", family, n),
+            severity: "ERROR".into(),
+            message,
+            detail: Some(detail),
+        }
+    }
+}
+
+pub(crate) struct ApiClient {
+    base_url: String,
+    token: String,
+    agent: ureq::Agent,
+    // 最後に見た X-RateLimit-* / RateLimit-*
+    rate_limit: Cell<Option<RateLimit>>,
+    // Accept など provider 固有のヘッダ
+    headers: &'static [(&'static str, &'static str)],
+}
+
+impl ApiClient {
+    /// token は Bearer で送る（空なら Authorization を付けない）
+    pub(crate) fn new(api_base_url: &str, token: &str) -> Self {
+        ApiClient {
+            base_url: api_base_url.trim().trim_end_matches('/').to_string(),
+            token: token.trim().to_string(),
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(20))
+                .build(),
+            rate_limit: Cell::new(None),
+            headers: JSON_HEADERS,
+        }
+    }
+
+    pub(crate) fn with_headers(self, headers: &'static [(&'static str, &'static str)]) -> Self {
+        ApiClient { headers, ..self }
+    }
+
+    pub(crate) fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    pub(crate) fn has_token(&self) -> bool {
+        !self.token.is_empty()
+    }
+
+    pub(crate) fn rate_limit(&self) -> Option<RateLimit> {
+        self.rate_limit.get()
+    }
+
+    fn request(&self, method: &str, url: &str) -> ureq::Request {
+        let mut r = self.agent.request(method, url).set("User-Agent", "gitshlc");
+        for (k, v) in self.headers {
+            r = r.set(k, v);
+        }
+        if !self.token.is_empty() {
+            r = r.set("Authorization", &format!("Bearer {}", self.token));
+        }
+        r
+    }
+
+    fn note_rate_limit(&self, r: &ureq::Response) {
+        let num = |h: &str| rate_header(r, h).and_then(|v| v.trim().parse::<u64>().ok());
+        if let (Some(limit), Some(remaining), Some(reset)) =
+            (num("limit"), num("remaining"), num("reset"))
+        {
+            self.rate_limit.set(Some(RateLimit {
+                limit,
+                remaining,
+                reset,
+            }));
+        }
+    }
+
+    // rate limit に当たったら待って再試行する（待ち時間が長ければそのままエラー）
+    fn send(
+        &self,
+        method: &str,
+        url: &str,
+        body: Option<&serde_json::Value>,
+    ) -> Result<ureq::Response, ApiError> {
+        let mut attempt = 0;
+        loop {
+            let req = self.request(method, url);
+            let res = match body {
+                Some(b) => req.send_json(b.clone()),
+                None => req.call(),
+            };
+            match res {
+                Ok(r) => {
+                    self.note_rate_limit(&r);
+                    return Ok(r);
+                }
+                Err(ureq::Error::Status(code, r)) => {
+                    self.note_rate_limit(&r);
+                    let wait = rate_limit_wait(code, &r);
+                    let text = r.into_string().unwrap_or_default();
+                    // {"message": "..."}（GitHub / GitLab / Gitea 共通）、Bitbucket は {"error": {"message": "..."}}
+                    let msg = serde_json::from_str::<serde_json::Value>(&text)
+                        .ok()
+                        .and_then(|v| {
+                            v.get("message")
+                                .or_else(|| v.pointer("/error/message"))
+                                .and_then(|m| m.as_str())
+                                .map(String::from)
+                        })
+                        .unwrap_or(text);
+                    if let Some(w) = wait {
+                        if w <= RATE_LIMIT_MAX_WAIT_SECS && attempt < RATE_LIMIT_RETRIES {
+                            attempt += 1;
+                            std::thread::sleep(Duration::from_secs(w.max(1)));
+                            continue;
+                        }
+                    }
+                    return Err(ApiError {
+                        status: Some(code),
+                        message: format!("HTTP {}: {}", code, msg.trim()),
+                        rate_limited: wait.is_some(),
+                        reset: self.rate_limit.get().map(|r| r.reset),
+                    });
+                }
+                Err(e) => {
+                    return Err(ApiError {
+                        status: None,
+                        message: e.to_string(),
+                        rate_limited: false,
+                        reset: None,
+                    })
+                }
+            }
+        }
+    }
+
+    fn read(r: ureq::Response) -> Result<serde_json::Value, ApiError> {
+        r.into_json::<serde_json::Value>().map_err(|e| ApiError {
+            status: None,
+            message: e.to_string(),
+            rate_limited: false,
+            reset: None,
+        })
+    }
+
+    pub(crate) fn get_json(&self, path: &str) -> Result<serde_json::Value, ApiError> {
+        Self::read(self.send("GET", &self.url(path), None)?)
+    }
+
+    /// POST / PUT / PATCH など（ApiError のまま返す）
+    pub(crate) fn send_json(
+        &self,
+        method: &str,
+        path: &str,
+        body: serde_json::Value,
+    ) -> Result<serde_json::Value, ApiError> {
+        Self::read(self.send(method, &self.url(path), Some(&body))?)
+    }
+
+    /// Link: rel="next" を辿って配列を全部集める。max_pages で打ち切ったら true を返す
+    pub(crate) fn get_all(
+        &self,
+        path: &str,
+        max_pages: Option<u32>,
+    ) -> Result<(Vec<serde_json::Value>, bool), ApiError> {
+        let max_pages = max_pages.unwrap_or(DEFAULT_MAX_PAGES).max(1);
+        let mut url = self.url(path);
+        let mut items = Vec::new();
+        for _ in 0..max_pages {
+            let r = self.send("GET", &url, None)?;
+            let mut next = r.header("link").and_then(next_link);
+            match Self::read(r)? {
+                serde_json::Value::Array(a) => items.extend(a),
+                // check-runs / installation の repo 一覧は {"total_count": n, "<key>": [...]} で返る
+                // Bitbucket は {"values": [...], "next": "<url>"}（次のページは Link ではなく本文）
+                serde_json::Value::Object(mut o) => {
+                    if next.is_none() {
+                        next = o.get("next").and_then(|n| n.as_str()).map(String::from);
+                    }
+                    if let Some(serde_json::Value::Array(a)) =
+                        ["check_runs", "repositories", "values"]
+                            .iter()
+                            .find_map(|k| o.remove(*k))
+                    {
+                        items.extend(a);
+                    }
+                }
+                _ => {}
+            }
+            match next {
+                // token を別ホストに送らない
+                Some(n) if n.starts_with(&self.base_url) => url = n,
+                _ => return Ok((items, false)),
+            }
+        }
+        Ok((items, true))
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// GitHub / Gitea は X-RateLimit-*、GitLab は RateLimit-*
+fn rate_header<'r>(r: &'r ureq::Response, name: &str) -> Option<&'r str> {
+    r.header(&format!("x-ratelimit-{}", name))
+        .or_else(|| r.header(&format!("ratelimit-{}", name)))
+}
+
+// 429、または 403 で残り 0 / Retry-After 付きなら rate limit。待つ秒数を返す
+fn rate_limit_wait(code: u16, r: &ureq::Response) -> Option<u64> {
+    let retry_after = r
+        .header("retry-after")
+        .and_then(|v| v.trim().parse::<u64>().ok());
+    let exhausted = rate_header(r, "remaining").map(str::trim) == Some("0");
+    if code != 429 && !(code == 403 && (exhausted || retry_after.is_some())) {
+        return None;
+    }
+    if let Some(s) = retry_after {
+        return Some(s);
+    }
+    let reset = rate_header(r, "reset").and_then(|v| v.trim().parse::<u64>().ok());
+    Some(match reset {
+        Some(t) => t.saturating_sub(now_secs()) + 1,
+        None => RATE_LIMIT_MAX_WAIT_SECS,
+    })
+}
+
+// <https://api.github.com/user/repos?page=2>; rel="next", <...>; rel="last"
+fn next_link(header: &str) -> Option<String> {
+    header.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        let is_next = params
+            .split(';')
+            .any(|p| p.trim().replace(' ', "") == "rel=\"next\"");
+        if !is_next {
+            return None;
+        }
+        let url = url.trim().strip_prefix('<')?.strip_suffix('>')?;
+        Some(url.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_http::{MockServer, Reply};
+
+    #[test]
+    fn get_all_follows_link_and_unwraps_known_keys() {
+        let server = MockServer::start(|req, base| {
+            if req.path.contains("page=2") {
+                Reply::json(
+                    200,
+                    serde_json::json!({"total_count": 3, "check_runs": [{"id": 3}]}),
+                )
+            } else {
+                // 先頭の配列ではなく check_runs を取る
+                Reply::json(
+                    200,
+                    serde_json::json!({
+                        "total_count": 3,
+                        "annotations": [{"id": 0}],
+                        "check_runs": [{"id": 1}, {"id": 2}],
+                    }),
+                )
+                .header(
+                    "Link",
+                    &format!(
+                        "<{}/repos/o/r/commits/x/check-runs?page=2>; rel=\"next\"",
+                        base
+                    ),
+                )
+            }
+        });
+        let client = ApiClient::new(&server.base, "tok");
+        let (items, truncated) = client
+            .get_all("/repos/o/r/commits/x/check-runs", None)
+            .unwrap_or_else(|e| panic!("{}", e.message));
+        let ids: Vec<u64> = items.iter().filter_map(|v| v["id"].as_u64()).collect();
+        assert_eq!(ids, [1, 2, 3]);
+        assert!(!truncated);
+        assert_eq!(server.requests().len(), 2);
+
+        let (_, truncated) = client
+            .get_all("/repos/o/r/commits/x/check-runs", Some(1))
+            .unwrap_or_else(|e| panic!("{}", e.message));
+        assert!(truncated);
+
+        let server =
+            MockServer::start(|_, _| Reply::json(200, serde_json::json!({"other": [{"id": 1}]})));
+        let client = ApiClient::new(&server.base, "tok");
+        let (items, _) = client
+            .get_all("/x", None)
+            .unwrap_or_else(|e| panic!("{}", e.message));
+        assert!(items.is_empty());
+    }
+
+    fn response(status: &str, headers: &[&str]) -> ureq::Response {
+        let mut raw = format!("HTTP/1.1 {}\r\n", status);
+        for h in headers {
+            raw.push_str(h);
+            raw.push_str("\r\n");
+        }
+        raw.push_str("\r\n");
+        raw.parse().unwrap()
+    }
+
+    #[test]
+    fn next_link_picks_rel_next() {
+        assert_eq!(
+            next_link(r#"<https://api.github.com/x?page=1>; rel="prev", <https://api.github.com/x?page=3>; rel="next""#)
+                .as_deref(),
+            Some("https://api.github.com/x?page=3")
+        );
+        // GitLab は rel = "next" のように空白が入ることがある
+        assert_eq!(
+            next_link(r#"<https://gl/x?page=2>; rel = "next"; foo="bar""#).as_deref(),
+            Some("https://gl/x?page=2")
+        );
+        assert_eq!(next_link(r#"<https://a/x?page=9>; rel="last""#), None);
+        assert_eq!(next_link(r#"https://a/x; rel="next""#), None);
+        assert_eq!(next_link(""), None);
+    }
+
+    #[test]
+    fn rate_limit_wait_only_for_429_or_exhausted_403() {
+        assert_eq!(
+            rate_limit_wait(429, &response("429 Too Many Requests", &["Retry-After: 7"])),
+            Some(7)
+        );
+        assert_eq!(
+            rate_limit_wait(429, &response("429 Too Many Requests", &[])),
+            Some(RATE_LIMIT_MAX_WAIT_SECS)
+        );
+        let reset = format!("X-RateLimit-Reset: {}", now_secs() + 30);
+        let w = rate_limit_wait(
+            403,
+            &response("403 Forbidden", &["X-RateLimit-Remaining: 0", &reset]),
+        )
+        .unwrap();
+        assert!((30..=31).contains(&w), "{}", w);
+        // GitLab の RateLimit-*
+        assert_eq!(
+            rate_limit_wait(
+                403,
+                &response(
+                    "403 Forbidden",
+                    &["RateLimit-Remaining: 0", "RateLimit-Reset: 0"]
+                )
+            ),
+            Some(1)
+        );
+        // 権限不足の 403 や他のステータスは rate limit ではない
+        assert_eq!(
+            rate_limit_wait(
+                403,
+                &response("403 Forbidden", &["X-RateLimit-Remaining: 12"])
+            ),
+            None
+        );
+        assert_eq!(
+            rate_limit_wait(500, &response("500 Server Error", &["Retry-After: 1"])),
+            None
+        );
+    }
+}
@@ -0,0 +1,528 @@
+// ブランチ操作（create / delete / rename / set_upstream / switch）。local_path と remote_path の両方で使える
+use crate::repo_target::RepoTarget;
+use crate::{remote_auth_plan, ActionError, ActionOutcome, RunActionRequest, StepResult};
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct BranchOp {
+    // create | delete | rename | set_upstream | switch
+    kind: String,
+    // 省略時は req.branch
+    name: Option<String>,
+    // create: 起点（default HEAD）
+    start_point: Option<String>,
+    // create: 作ったあと切り替える / origin に push -u する
+    switch: bool,
+    push: bool,
+    // rename
+    new_name: Option<String>,
+    // delete: ローカル（default true）と origin のどちらを消すか
+    delete_local: Option<bool>,
+    delete_remote: bool,
+    // delete: ここに merge 済みでなければ拒否する（default HEAD）
+    merged_into: Option<String>,
+    // delete: merge 済みチェックを飛ばす
+    force: bool,
+    // set_upstream: default origin/<name>
+    upstream: Option<String>,
+}
+
+struct Run<'a> {
+    target: &'a RepoTarget<'a>,
+    steps: Vec<StepResult>,
+}
+
+impl Run<'_> {
+    fn git(&mut self, args: &[&str]) -> StepResult {
+        let step = self.target.git(args);
+        self.steps.push(step.clone());
+        step
+    }
+
+    fn git_origin(&mut self, args: &[&str]) -> StepResult {
+        let step = self.target.git_origin(args);
+        self.steps.push(step.clone());
+        step
+    }
+
+    // 失敗したら BR-0200（接続系はそちらのコード）
+    fn must(&mut self, args: &[&str], what: &str) -> Result<StepResult, ActionError> {
+        let step = self.git(args);
+        if step.ok {
+            Ok(step)
+        } else {
+            Err(self.target.failure(&step, "BR-0200", what))
+        }
+    }
+
+    fn must_origin(&mut self, args: &[&str], what: &str) -> Result<StepResult, ActionError> {
+        let step = self.git_origin(args);
+        if step.ok {
+            Ok(step)
+        } else {
+            Err(self.target.failure(&step, "BR-0200", what))
+        }
+    }
+
+    fn check_name(&mut self, name: &str) -> Result<(), ActionError> {
+        let step = self.git(&["check-ref-format", "--branch", name]);
+        if step.ok {
+            return Ok(());
+        }
+        let e = self.target.failure(&step, "BR-0001", "invalid branch name");
+        Err(ActionError {
+            detail: Some(name.to_string()),
+            ..e
+        })
+    }
+
+    fn has_ref(&mut self, full_ref: &str) -> bool {
+        self.git(&["show-ref", "--verify", "--quiet", full_ref]).ok
+    }
+
+    fn current_branch(&mut self) -> Option<String> {
+        let step = self.git(&["symbolic-ref", "--quiet", "--short", "HEAD"]);
+        Some(step.stdout.trim().to_string()).filter(|b| step.ok && !b.is_empty())
+    }
+
+    // exit 0 = merge 済み / 1 = 未 merge
+    fn is_merged(&mut self, commit: &str, into: &str) -> Result<bool, ActionError> {
+        let step = self.git(&["merge-base", "--is-ancestor", commit, into]);
+        match step.exit_code {
+            0 => Ok(true),
+            1 => Ok(false),
+            _ => Err(self.target.failure(&step, "BR-0200", "merged check failed")),
+        }
+    }
+}
+
+fn br_err(code: &str, message: &str, detail: Option<String>) -> ActionError {
+    ActionError {
+        code: code.into(),
+        severity: "ERROR".into(),
+        message: message.into(),
+        detail,
+    }
+}
+
+// startPoint / mergedInto がオプションとして解釈されないように（- 始まりは拒否）
+fn check_ref_arg(r: &str) -> Result<(), ActionError> {
+    if r.starts_with('-') || r.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(br_err("BR-0002", "invalid ref", Some(r.to_string())));
+    }
+    Ok(())
+}
+
+fn apply(run: &mut Run, op: &BranchOp, name: &str) -> Result<(), ActionError> {
+    match op.kind.as_str() {
+        "create" => {
+            run.check_name(name)?;
+            if run.has_ref(&format!("refs/heads/{}", name)) {
+                return Err(br_err(
+                    "BR-0102",
+                    "branch already exists",
+                    Some(name.into()),
+                ));
+            }
+            let start = op
+                .start_point
+                .as_deref()
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .unwrap_or("HEAD");
+            check_ref_arg(start)?;
+            run.must(&["branch", "--", name, start], "git branch failed")?;
+            if op.switch {
+                let sw = switch_subcommand(run);
+                run.must(&[sw, name], "switch failed")?;
+            }
+            if op.push {
+                run.must_origin(&["push", "-u", "origin", name], "git push failed")?;
+            }
+            Ok(())
+        }
+        "delete" => {
+            run.check_name(name)?;
+            let delete_local = op.delete_local.unwrap_or(true);
+            if !delete_local && !op.delete_remote {
+                return Err(br_err(
+                    "CFG-0001",
+                    "nothing to delete (deleteLocal=false and deleteRemote=false)",
+                    None,
+                ));
+            }
+            if delete_local && run.current_branch().as_deref() == Some(name) {
+                return Err(br_err(
+                    "BR-0101",
+                    "cannot delete the checked-out branch",
+                    Some(name.into()),
+                ));
+            }
+            let into = op
+                .merged_into
+                .as_deref()
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .unwrap_or("HEAD");
+            check_ref_arg(into)?;
+            let local_ref = format!("refs/heads/{}", name);
+            let remote_ref = format!("refs/remotes/origin/{}", name);
+            let has_local = delete_local && run.has_ref(&local_ref);
+            if op.delete_remote {
+                // origin の最新で判定する
+                run.must_origin(&["fetch", "--prune", "origin"], "git fetch failed")?;
+            }
+            let has_remote = op.delete_remote && run.has_ref(&remote_ref);
+            if !has_local && !has_remote {
+                return Err(br_err("BR-0103", "branch not found", Some(name.into())));
+            }
+
+            if !op.force {
+                for r in [
+                    Some(&local_ref).filter(|_| has_local),
+                    Some(&remote_ref).filter(|_| has_remote),
+                ]
+                .into_iter()
+                .flatten()
+                {
+                    if !run.is_merged(r, into)? {
+                        return Err(br_err(
+                            "BR-0100",
+                            &format!("branch is not merged into {} (use force to delete)", into),
+                            Some(r.clone()),
+                        ));
+                    }
+                }
+            }
+
+            if has_local {
+                // merge 済みは上で確認済み（-d は upstream 基準で判定するので使わない）
+                run.must(&["branch", "-D", "--", name], "git branch -D failed")?;
+            }
+            if has_remote {
+                run.must_origin(
+                    &["push", "origin", "--delete", name],
+                    "git push --delete failed",
+                )?;
+            }
+            Ok(())
+        }
+        "rename" => {
+            let new_name = op
+                .new_name
+                .as_deref()
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .ok_or_else(|| br_err("CFG-0001", "newName is required", None))?;
+            run.check_name(name)?;
+            run.check_name(new_name)?;
+            if !run.has_ref(&format!("refs/heads/{}", name)) {
+                return Err(br_err("BR-0103", "branch not found", Some(name.into())));
+            }
+            if run.has_ref(&format!("refs/heads/{}", new_name)) {
+                return Err(br_err(
+                    "BR-0102",
+                    "branch already exists",
+                    Some(new_name.into()),
+                ));
+            }
+            run.must(
+                &["branch", "-m", "--", name, new_name],
+                "git branch -m failed",
+            )?;
+            Ok(())
+        }
+        "set_upstream" => {
+            run.check_name(name)?;
+            let upstream = op
+                .upstream
+                .as_deref()
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("origin/{}", name));
+            run.must(
+                &["branch", &format!("--set-upstream-to={}", upstream), name],
+                "git branch --set-upstream-to failed",
+            )?;
+            Ok(())
+        }
+        "switch" => {
+            run.check_name(name)?;
+            let sw = switch_subcommand(run);
+            // ローカルに無くても origin/<name> があれば switch / checkout が追跡ブランチを作る
+            run.must(&[sw, name], "switch failed")?;
+            Ok(())
+        }
+        other => Err(br_err(
+            "CFG-0001",
+            "unknown branch op (expected create|delete|rename|set_upstream|switch)",
+            Some(other.to_string()),
+        )),
+    }
+}
+
+fn switch_subcommand(run: &Run) -> &'static str {
+    run.target
+        .capabilities()
+        .map(|c| c.switch_subcommand())
+        .unwrap_or("checkout")
+}
+
+/// ブランチ操作。接続・検証は run_action と同じ req を使う
+#[tauri::command(async, rename_all = "camelCase")]
+pub(crate) fn branch_action(req: RunActionRequest, op: BranchOp) -> ActionOutcome {
+    let action = format!("branch:{}", op.kind);
+    let outcome = |steps: Vec<StepResult>, error: Option<ActionError>| ActionOutcome {
+        ok: error.is_none(),
+        mode: req.mode.clone(),
+        action: action.clone(),
+        env_key: req.env_key.clone(),
+        steps,
+        error,
+    };
+
+    let name = op.name.as_deref().unwrap_or(&req.branch).trim().to_string();
+    if name.is_empty() {
+        return outcome(
+            vec![],
+            Some(br_err("CFG-0001", "branch name is required", None)),
+        );
+    }
+
+    let remote_path = req.remote_path.trim().to_string();
+    let auth = match remote_auth_plan(req.remote_auth.as_ref()) {
+        Ok(a) => a,
+        Err(e) => return outcome(vec![], Some(e)),
+    };
+    let target = match RepoTarget::open(&req, &remote_path, &auth) {
+        Ok(t) => t,
+        Err(e) => return outcome(vec![], Some(e)),
+    };
+
+    let mut run = Run {
+        target: &target,
+        steps: Vec::new(),
+    };
+    let res = apply(&mut run, &op, &name);
+    outcome(run.steps, res.err())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{path_to_string, run_capture};
+    use std::path::{Path, PathBuf};
+
+    fn git(dir: &Path, args: &[&str]) -> StepResult {
+        let d = path_to_string(dir);
+        let mut full = vec!["-C", d.as_str()];
+        full.extend_from_slice(args);
+        run_capture(Path::new("git"), &full, None)
+    }
+
+    fn git_ok(dir: &Path, args: &[&str]) -> String {
+        let s = git(dir, args);
+        assert!(s.ok, "git {:?}: {}", args, s.stderr);
+        s.stdout.trim().to_string()
+    }
+
+    // origin（bare）と main を push 済みの作業ディレクトリ
+    fn setup(name: &str) -> (PathBuf, PathBuf) {
+        let root = std::env::temp_dir().join(format!("gitshlc-{}-{}", name, std::process::id()));
+        std::fs::remove_dir_all(&root).ok();
+        let origin = root.join("origin.git");
+        let work = root.join("work");
+        std::fs::create_dir_all(&origin).unwrap();
+        std::fs::create_dir_all(&work).unwrap();
+        git_ok(&origin, &["init", "-q", "--bare"]);
+        git_ok(&work, &["init", "-q"]);
+        git_ok(&work, &["symbolic-ref", "HEAD", "refs/heads/main"]);
+        git_ok(&work, &["config", "user.name", "Tester"]);
+        git_ok(&work, &["config", "user.email", "tester@example.com"]);
+        git_ok(&work, &["commit", "-q", "--allow-empty", "-m", "init"]);
+        git_ok(
+            &work,
+            &["remote", "add", "origin", &path_to_string(&origin)],
+        );
+        git_ok(&work, &["push", "-q", "-u", "origin", "main"]);
+        (root, work)
+    }
+
+    fn run(work: &Path, op: serde_json::Value) -> ActionOutcome {
+        let req: RunActionRequest = serde_json::from_value(serde_json::json!({
+            "mode": "local",
+            "envKey": "test",
+            "action": "branch",
+            "localPath": path_to_string(work),
+            "remotePath": "",
+            "branch": "main",
+            "gitPath": "",
+            "sshPath": "",
+            "ssh": { "host": "", "user": "" },
+        }))
+        .unwrap();
+        branch_action(req, serde_json::from_value(op).unwrap())
+    }
+
+    fn code(o: &ActionOutcome) -> &str {
+        o.error.as_ref().map(|e| e.code.as_str()).unwrap_or("")
+    }
+
+    fn has_branch(dir: &Path, r: &str) -> bool {
+        git(dir, &["show-ref", "--verify", "--quiet", r]).ok
+    }
+
+    #[test]
+    fn delete_refuses_unmerged_branches_unless_forced() {
+        let (root, work) = setup("branch-delete");
+        git_ok(&work, &["branch", "done"]);
+        git_ok(&work, &["push", "-q", "origin", "done"]);
+        git_ok(&work, &["checkout", "-q", "-b", "wip"]);
+        git_ok(&work, &["commit", "-q", "--allow-empty", "-m", "wip"]);
+        git_ok(&work, &["checkout", "-q", "main"]);
+
+        let o = run(
+            &work,
+            serde_json::json!({ "kind": "delete", "name": "wip" }),
+        );
+        assert_eq!(code(&o), "BR-0100");
+        assert!(has_branch(&work, "refs/heads/wip"));
+
+        let o = run(
+            &work,
+            serde_json::json!({ "kind": "delete", "name": "wip", "force": true }),
+        );
+        assert!(o.ok, "{:?}", o.error);
+        assert!(!has_branch(&work, "refs/heads/wip"));
+
+        // merge 済みならローカルと origin の両方を消せる
+        let o = run(
+            &work,
+            serde_json::json!({ "kind": "delete", "name": "done", "deleteRemote": true }),
+        );
+        assert!(o.ok, "{:?}", o.error);
+        assert!(!has_branch(&work, "refs/heads/done"));
+        assert!(!has_branch(&root.join("origin.git"), "refs/heads/done"));
+
+        let o = run(
+            &work,
+            serde_json::json!({ "kind": "delete", "name": "main" }),
+        );
+        assert_eq!(code(&o), "BR-0101");
+        let o = run(
+            &work,
+            serde_json::json!({ "kind": "delete", "name": "gone" }),
+        );
+        assert_eq!(code(&o), "BR-0103");
+
+        // mergedInto / startPoint はオプションとして渡さない
+        git_ok(&work, &["branch", "old"]);
+        let o = run(
+            &work,
+            serde_json::json!({ "kind": "delete", "name": "old", "mergedInto": "--output=x" }),
+        );
+        assert_eq!(code(&o), "BR-0002");
+        assert!(has_branch(&work, "refs/heads/old"));
+        let o = run(
+            &work,
+            serde_json::json!({ "kind": "create", "name": "new", "startPoint": "-f" }),
+        );
+        assert_eq!(code(&o), "BR-0002");
+        assert!(!has_branch(&work, "refs/heads/new"));
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn rename_onto_an_existing_branch_is_refused() {
+        let (root, work) = setup("branch-rename");
+        git_ok(&work, &["branch", "a"]);
+        git_ok(&work, &["branch", "b"]);
+
+        let op = serde_json::json!({ "kind": "rename", "name": "a", "newName": "b" });
+        let o = run(&work, op);
+        assert_eq!(code(&o), "BR-0102");
+        assert!(has_branch(&work, "refs/heads/a") && has_branch(&work, "refs/heads/b"));
+
+        let o = run(
+            &work,
+            serde_json::json!({ "kind": "rename", "name": "a", "newName": "c" }),
+        );
+        assert!(o.ok, "{:?}", o.error);
+        assert!(!has_branch(&work, "refs/heads/a") && has_branch(&work, "refs/heads/c"));
+
+        let o = run(
+            &work,
+            serde_json::json!({ "kind": "rename", "name": "c", "newName": "bad..name" }),
+        );
+        assert_eq!(code(&o), "BR-0001");
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn set_upstream_defaults_to_origin_branch() {
+        let (root, work) = setup("branch-upstream");
+        git_ok(&work, &["branch", "feature"]);
+        git_ok(&work, &["push", "-q", "origin", "feature"]);
+
+        let o = run(
+            &work,
+            serde_json::json!({ "kind": "set_upstream", "name": "feature" }),
+        );
+        assert!(o.ok, "{:?}", o.error);
+        assert_eq!(
+            git_ok(&work, &["rev-parse", "--abbrev-ref", "feature@{upstream}"]),
+            "origin/feature"
+        );
+
+        let o = run(
+            &work,
+            serde_json::json!({ "kind": "set_upstream", "name": "feature", "upstream": "origin/nope" }),
+        );
+        assert_eq!(code(&o), "BR-0200");
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn switch_checks_out_local_and_origin_only_branches() {
+        let (root, work) = setup("branch-switch");
+        git_ok(&work, &["branch", "local-only"]);
+        git_ok(&work, &["push", "-q", "origin", "main:remote-only"]);
+        git_ok(&work, &["fetch", "-q", "origin"]);
+
+        let head = |w: &Path| git_ok(w, &["symbolic-ref", "--short", "HEAD"]);
+        let o = run(
+            &work,
+            serde_json::json!({ "kind": "switch", "name": "local-only" }),
+        );
+        assert!(o.ok, "{:?}", o.error);
+        assert_eq!(head(&work), "local-only");
+
+        // origin にだけある branch は追跡ブランチとして作られる
+        let o = run(
+            &work,
+            serde_json::json!({ "kind": "switch", "name": "remote-only" }),
+        );
+        assert!(o.ok, "{:?}", o.error);
+        assert_eq!(head(&work), "remote-only");
+        assert_eq!(
+            git_ok(
+                &work,
+                &["rev-parse", "--abbrev-ref", "remote-only@{upstream}"]
+            ),
+            "origin/remote-only"
+        );
+
+        let o = run(
+            &work,
+            serde_json::json!({ "kind": "switch", "name": "missing" }),
+        );
+        assert_eq!(code(&o), "BR-0200");
+        assert_eq!(head(&work), "remote-only");
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
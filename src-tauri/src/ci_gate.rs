@@ -0,0 +1,241 @@
+// run_action（ssh / wrapped の pull・merge）の前に、反映されるコミットの CI 結果をホスティングに問い合わせる
+use std::time::{Duration, Instant};
+
+use crate::hosting::{self, HostingProvider, HostingRequest};
+use crate::{shell_escape_posix_single, ActionError, RemoteRepo, RunActionRequest, StepResult};
+
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 15;
+const MIN_POLL_INTERVAL_SECS: u64 = 5;
+// waitSecs / pollIntervalSecs の上限（Instant の加算が溢れないように丸める）
+const MAX_WAIT_SECS: u64 = 6 * 60 * 60;
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct CiGateConfig {
+    token: String,
+    // github | gitlab | gitea（無ければ origin の host から）
+    provider: Option<String>,
+    api_base_url: Option<String>,
+    // owner/name（無ければ remote の origin URL から取る）
+    repo: Option<String>,
+    // pending を通す（failure だけ止める）
+    allow_pending: bool,
+    // > 0 なら pending の間この秒数まで待って取り直す
+    wait_secs: u64,
+    poll_interval_secs: Option<u64>,
+    // 無視する status context / check run 名
+    ignore: Vec<String>,
+    // status も check run も 1 件も無いときに止める
+    require_checks: bool,
+}
+
+#[derive(Default)]
+struct Verdict {
+    failing: Vec<String>,
+    pending: Vec<String>,
+    passed: usize,
+}
+
+impl Verdict {
+    fn summary(&self, sha: &str) -> String {
+        let mut s = format!("sha={} passed={}", sha, self.passed);
+        if !self.failing.is_empty() {
+            s.push_str(&format!(" failing=[{}]", self.failing.join(", ")));
+        }
+        if !self.pending.is_empty() {
+            s.push_str(&format!(" pending=[{}]", self.pending.join(", ")));
+        }
+        s
+    }
+}
+
+fn gate_err(code: &str, message: &str, detail: Option<String>) -> ActionError {
+    ActionError {
+        code: code.into(),
+        severity: "ERROR".into(),
+        message: message.into(),
+        detail,
+    }
+}
+
+fn info_step(cmd: String, stdout: String) -> StepResult {
+    StepResult {
+        cmd,
+        cwd: None,
+        ok: true,
+        exit_code: 0,
+        stdout,
+        stderr: "".into(),
+    }
+}
+
+// ls-remote で origin 上の branch の先頭を取る（fetch 前なので remote 側の今の値）
+fn resolve_sha(
+    repo: &RemoteRepo,
+    branch: &str,
+    steps: &mut Vec<StepResult>,
+) -> Result<String, ActionError> {
+    let step = repo.git_origin(&format!(
+        "ls-remote origin {}",
+        shell_escape_posix_single(&format!("refs/heads/{}", branch))
+    ));
+    let sha = step
+        .stdout
+        .lines()
+        .filter_map(|l| l.split_whitespace().next())
+        .find(|s| s.len() == 40 && s.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(String::from);
+    let ok = step.ok;
+    let stderr = step.stderr.trim().to_string();
+    let conn = (!ok).then(|| repo.classify(&step));
+    steps.push(step);
+    match (sha, conn) {
+        (_, Some(c)) if c.code != "SSH-0200" => Err(c),
+        (Some(s), None) => Ok(s),
+        (_, Some(_)) => Err(gate_err(
+            "GATE-0100",
+            "ls-remote failed (cannot resolve commit to gate)",
+            Some(stderr),
+        )),
+        (None, None) => Err(gate_err(
+            "GATE-0100",
+            "branch not found on origin",
+            Some(branch.into()),
+        )),
+    }
+}
+
+// origin の URL から provider / API / owner/name を決める（ciGate の指定が優先）
+fn resolve_target(
+    repo: &RemoteRepo,
+    cfg: &CiGateConfig,
+    steps: &mut Vec<StepResult>,
+) -> Result<hosting::HostingTarget, ActionError> {
+    let step = repo.run("git remote get-url origin");
+    let url = step.stdout.trim().to_string();
+    steps.push(step);
+    let target = hosting::resolve(&HostingRequest {
+        remote_url: Some(url.clone()).filter(|u| !u.is_empty()),
+        provider: cfg.provider.clone(),
+        api_base_url: cfg.api_base_url.clone(),
+        token: cfg.token.clone(),
+        repo: cfg.repo.clone(),
+    })?;
+    if target.repo.is_none() {
+        return Err(gate_err(
+            "GATE-0001",
+            "cannot derive owner/name from origin (set ciGate.repo)",
+            Some(url),
+        ));
+    }
+    Ok(target)
+}
+
+// status / check の結果をまとめて判定する（ignore の名前は数えない）
+fn evaluate(
+    provider: &dyn HostingProvider,
+    repo: &str,
+    sha: &str,
+    ignore: &[String],
+) -> Result<Verdict, ActionError> {
+    let status = provider.commit_status(repo, sha).map_err(|e| {
+        gate_err(
+            "GATE-0101",
+            "failed to query CI status",
+            Some(e.message().to_string()),
+        )
+    })?;
+    let mut v = Verdict::default();
+    for c in status
+        .checks
+        .iter()
+        .filter(|c| !ignore.iter().any(|i| i.trim() == c.name))
+    {
+        match c.state.as_str() {
+            "success" => v.passed += 1,
+            "pending" => v.pending.push(c.name.clone()),
+            _ => v.failing.push(c.name.clone()),
+        }
+    }
+    Ok(v)
+}
+
+/// gate を通らなければ GATE-xxxx を返す（pull は branch、merge は mergeFromBranch の先頭を見る）
+pub(crate) fn check(
+    repo: &RemoteRepo,
+    req: &RunActionRequest,
+    cfg: &CiGateConfig,
+    steps: &mut Vec<StepResult>,
+) -> Result<(), ActionError> {
+    if cfg.token.trim().is_empty() {
+        return Err(gate_err("GATE-0001", "ciGate.token is required", None));
+    }
+    let branch = if req.action == "merge" {
+        req.merge_from_branch.as_deref().unwrap_or("").trim()
+    } else {
+        req.branch.trim()
+    };
+    if branch.is_empty() {
+        return Err(gate_err(
+            "GATE-0001",
+            "branch to gate is empty",
+            Some(req.action.clone()),
+        ));
+    }
+
+    let target = resolve_target(repo, cfg, steps)?;
+    let full_name = target.repo.clone().unwrap_or_default();
+    let sha = resolve_sha(repo, branch, steps)?;
+    let provider = hosting::provider_for(&target, &cfg.token);
+
+    let deadline = Instant::now() + Duration::from_secs(cfg.wait_secs.min(MAX_WAIT_SECS));
+    let interval = Duration::from_secs(
+        cfg.poll_interval_secs
+            .unwrap_or(DEFAULT_POLL_INTERVAL_SECS)
+            .clamp(MIN_POLL_INTERVAL_SECS, MAX_WAIT_SECS),
+    );
+    let cmd = format!(
+        "[ci-gate] {} {} {}@{}",
+        target.api_base_url,
+        full_name,
+        branch,
+        &sha[..12]
+    );
+
+    loop {
+        let v = evaluate(provider.as_ref(), &full_name, &sha, &cfg.ignore)?;
+        let summary = v.summary(&sha);
+
+        if !v.failing.is_empty() {
+            steps.push(info_step(cmd, summary.clone()));
+            return Err(gate_err(
+                "GATE-0200",
+                "CI checks are failing",
+                Some(summary),
+            ));
+        }
+        if v.pending.is_empty() || cfg.allow_pending {
+            if v.passed == 0 && v.pending.is_empty() && cfg.require_checks {
+                steps.push(info_step(cmd, summary.clone()));
+                return Err(gate_err(
+                    "GATE-0202",
+                    "no CI checks found for the commit",
+                    Some(summary),
+                ));
+            }
+            steps.push(info_step(cmd, summary));
+            return Ok(());
+        }
+        // pending: 待てるなら待つ
+        if Instant::now() + interval > deadline {
+            steps.push(info_step(cmd, summary.clone()));
+            let message = if cfg.wait_secs > 0 {
+                "CI checks still pending (wait timed out)"
+            } else {
+                "CI checks are pending"
+            };
+            return Err(gate_err("GATE-0201", message, Some(summary)));
+        }
+        std::thread::sleep(interval);
+    }
+}
@@ -0,0 +1,690 @@
+// 既存 repo の clone（local_path / remote_path）。進捗は clone:progress イベントで流す
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use tauri::{AppHandle, Emitter};
+
+use crate::{
+    git_exe, path_to_string, remote_auth_plan, remote_repo_for, run_capture, run_capture_stream,
+    shell_escape_posix_single, step_error, ActionError, ActionOutcome, RunActionRequest,
+    StepResult,
+};
+
+const EVENT_PROGRESS: &str = "clone:progress";
+// 空でないディレクトリに clone するときの一時ディレクトリ（clone 先の中に作る）
+const TMP_DIR_PREFIX: &str = ".gitshlc-clone";
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct CloneOptions {
+    repo_url: String,
+    // 指定すると shallow clone
+    depth: Option<u32>,
+    // partial clone: blob:none / tree:0 / blob:limit=1m など
+    filter: Option<String>,
+    single_branch: bool,
+    recurse_submodules: bool,
+    // 空でないディレクトリにも clone する（既存ファイルは残し、追跡対象は上書き）
+    allow_non_empty: bool,
+    // 進捗イベントの識別子（省略時は生成）
+    clone_id: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CloneProgressEvent {
+    clone_id: String,
+    line: String,
+    // "Receiving objects" など（進捗行でなければ None）
+    phase: Option<String>,
+    percent: Option<u8>,
+}
+
+// "Receiving objects:  45% (450/1000), 1.2 MiB | 2 MiB/s" → ("Receiving objects", 45)
+fn parse_progress(line: &str) -> (Option<String>, Option<u8>) {
+    let line = line.strip_prefix("remote: ").unwrap_or(line);
+    let Some((phase, rest)) = line.split_once(':') else {
+        return (None, None);
+    };
+    let percent = rest
+        .trim_start()
+        .split('%')
+        .next()
+        .and_then(|p| p.trim().parse::<u8>().ok());
+    match percent {
+        Some(p) => (Some(phase.trim().to_string()), Some(p)),
+        None => (None, None),
+    }
+}
+
+fn cln_err(code: &str, message: &str, detail: Option<String>) -> ActionError {
+    ActionError {
+        code: code.into(),
+        severity: "ERROR".into(),
+        message: message.into(),
+        detail,
+    }
+}
+
+fn is_non_empty_dir(p: &Path) -> bool {
+    fs::read_dir(p)
+        .map(|mut it| it.next().is_some())
+        .unwrap_or(false)
+}
+
+fn ok_step(cmd: String) -> StepResult {
+    StepResult {
+        cmd,
+        cwd: None,
+        ok: true,
+        exit_code: 0,
+        stdout: "".into(),
+        stderr: "".into(),
+    }
+}
+
+// clone の引数（URL と clone 先は含まない）
+fn clone_args(
+    opts: &CloneOptions,
+    branch: &str,
+    no_checkout: bool,
+) -> Result<Vec<String>, ActionError> {
+    let mut args: Vec<String> = vec!["clone".into(), "--progress".into()];
+    if !branch.is_empty() {
+        if branch.starts_with('-') {
+            return Err(cln_err("CLN-0001", "invalid branch", Some(branch.into())));
+        }
+        args.extend(["--branch".into(), branch.into()]);
+    }
+    if let Some(d) = opts.depth {
+        if d == 0 {
+            return Err(cln_err("CLN-0001", "depth must be >= 1", None));
+        }
+        args.extend(["--depth".into(), d.to_string()]);
+    }
+    if let Some(f) = opts
+        .filter
+        .as_deref()
+        .map(str::trim)
+        .filter(|f| !f.is_empty())
+    {
+        if !f
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || ":=+-_.".contains(c))
+        {
+            return Err(cln_err("CLN-0001", "invalid filter", Some(f.into())));
+        }
+        args.push(format!("--filter={}", f));
+    }
+    if opts.single_branch {
+        args.push("--single-branch".into());
+    }
+    if no_checkout {
+        args.push("--no-checkout".into());
+    } else if opts.recurse_submodules {
+        args.push("--recurse-submodules".into());
+        if opts.depth.is_some() {
+            args.push("--shallow-submodules".into());
+        }
+    }
+    Ok(args)
+}
+
+fn clone_local(
+    git: &Path,
+    dest: &Path,
+    url: &str,
+    opts: &CloneOptions,
+    branch: &str,
+    steps: &mut Vec<StepResult>,
+    on_line: &mut dyn FnMut(&str),
+) -> Result<(), ActionError> {
+    let failed = |steps: &[StepResult], message: &str| {
+        cln_err(
+            "CLN-0200",
+            message,
+            steps.last().map(|s| s.stderr.trim().to_string()),
+        )
+    };
+
+    if dest.join(".git").exists() {
+        return Err(cln_err(
+            "CLN-0101",
+            "destination is already a git repository",
+            Some(path_to_string(dest)),
+        ));
+    }
+    let non_empty = is_non_empty_dir(dest);
+    if non_empty && !opts.allow_non_empty {
+        return Err(cln_err(
+            "CLN-0100",
+            "destination is not empty (set allowNonEmpty to clone into it)",
+            Some(path_to_string(dest)),
+        ));
+    }
+
+    if !non_empty {
+        if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+            if let Err(e) = fs::create_dir_all(parent) {
+                steps.push(step_error(
+                    format!("mkdir -p {}", path_to_string(parent)),
+                    e.to_string(),
+                ));
+                return Err(cln_err(
+                    "CLN-0201",
+                    "failed to create parent directory",
+                    Some(e.to_string()),
+                ));
+            }
+        }
+        let mut args = clone_args(opts, branch, false)?;
+        args.extend(["--".into(), url.into(), path_to_string(dest)]);
+        let refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+        let step = run_capture_stream(git, &refs, None, None, on_line);
+        let ok = step.ok;
+        steps.push(step);
+        return if ok {
+            Ok(())
+        } else {
+            Err(failed(steps, "git clone failed"))
+        };
+    }
+
+    // 空でない: 中の一時ディレクトリに --no-checkout で clone → .git を移して reset --hard
+    let tmp = dest.join(format!("{}.{}", TMP_DIR_PREFIX, std::process::id()));
+    let mut args = clone_args(opts, branch, true)?;
+    args.extend(["--".into(), url.into(), path_to_string(&tmp)]);
+    let refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    let step = run_capture_stream(git, &refs, None, None, on_line);
+    let ok = step.ok;
+    steps.push(step);
+    if !ok {
+        let _ = fs::remove_dir_all(&tmp);
+        return Err(failed(steps, "git clone failed"));
+    }
+
+    let mv = format!(
+        "mv {} {}",
+        path_to_string(&tmp.join(".git")),
+        path_to_string(&dest.join(".git"))
+    );
+    if let Err(e) =
+        fs::rename(tmp.join(".git"), dest.join(".git")).and_then(|_| fs::remove_dir(&tmp))
+    {
+        steps.push(step_error(mv, e.to_string()));
+        let _ = fs::remove_dir_all(&tmp);
+        // rename だけ通っていたら戻す（clone 前に .git が無いことは確認済み）
+        let _ = fs::remove_dir_all(dest.join(".git"));
+        return Err(cln_err(
+            "CLN-0201",
+            "failed to move .git into destination",
+            Some(e.to_string()),
+        ));
+    }
+    steps.push(ok_step(mv));
+
+    let dest_s = path_to_string(dest);
+    let step = run_capture(git, &["-C", &dest_s, "reset", "-q", "--hard"], None);
+    let ok = step.ok;
+    steps.push(step);
+    if !ok {
+        let e = failed(steps, "git reset --hard failed");
+        // 移した .git を戻す（元々 repo ではなかったので消せばよい）
+        let git_dir = dest.join(".git");
+        let rm = format!("rm -rf {}", path_to_string(&git_dir));
+        match fs::remove_dir_all(&git_dir) {
+            Ok(()) => steps.push(ok_step(rm)),
+            Err(err) => steps.push(step_error(rm, err.to_string())),
+        }
+        return Err(e);
+    }
+
+    if opts.recurse_submodules {
+        let step = run_capture_stream(
+            git,
+            &[
+                "-C",
+                &dest_s,
+                "submodule",
+                "update",
+                "--init",
+                "--recursive",
+                "--progress",
+            ],
+            None,
+            None,
+            on_line,
+        );
+        let ok = step.ok;
+        steps.push(step);
+        if !ok {
+            return Err(failed(steps, "git submodule update failed"));
+        }
+    }
+    Ok(())
+}
+
+// remote は 1 本のスクリプトで判定〜clone まで行う（exit 20 = 既に repo / 21 = 空でない）
+fn remote_script(
+    dest: &str,
+    url: &str,
+    opts: &CloneOptions,
+    branch: &str,
+    git_opts: &str,
+) -> Result<String, ActionError> {
+    let q = |v: &[String]| {
+        v.iter()
+            .map(|a| shell_escape_posix_single(a))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let normal = q(&clone_args(opts, branch, false)?);
+    let no_checkout = q(&clone_args(opts, branch, true)?);
+    let submodules = if opts.recurse_submodules {
+        format!(
+            "\n  git {}submodule update --init --recursive --progress",
+            git_opts
+        )
+    } else {
+        String::new()
+    };
+
+    Ok(format!(
+        r#"DEST={dest}; URL={url}; ALLOW={allow}
+if [ -e "$DEST/.git" ]; then echo "already a git repository: $DEST" >&2; exit 20; fi
+if [ -d "$DEST" ] && [ -n "$(ls -A "$DEST" 2>/dev/null)" ]; then
+  if [ "$ALLOW" != 1 ]; then echo "destination is not empty: $DEST" >&2; exit 21; fi
+  TMP="$DEST/{tmp}.$$"
+  git {git_opts}{no_checkout} -- "$URL" "$TMP" || {{ rm -rf "$TMP"; exit 1; }}
+  mv "$TMP/.git" "$DEST/.git" || {{ rm -rf "$TMP"; exit 1; }}
+  rmdir "$TMP"; cd "$DEST" || exit 1
+  git reset -q --hard || {{ rm -rf "$DEST/.git"; exit 1; }}{submodules}
+else
+  mkdir -p "$(dirname "$DEST")" && git {git_opts}{normal} -- "$URL" "$DEST"
+fi"#,
+        dest = shell_escape_posix_single(dest),
+        url = shell_escape_posix_single(url),
+        allow = if opts.allow_non_empty { 1 } else { 0 },
+        tmp = TMP_DIR_PREFIX,
+    ))
+}
+
+/// 既存の repo を clone する（mode=local は localPath、ssh / wrapped は remotePath へ）
+#[tauri::command(async, rename_all = "camelCase")]
+pub(crate) fn clone_repo(
+    app: AppHandle,
+    req: RunActionRequest,
+    options: CloneOptions,
+) -> ActionOutcome {
+    let mut steps: Vec<StepResult> = Vec::new();
+    let outcome = |steps: Vec<StepResult>, error: Option<ActionError>| ActionOutcome {
+        ok: error.is_none(),
+        mode: req.mode.clone(),
+        action: "clone".into(),
+        env_key: req.env_key.clone(),
+        steps,
+        error,
+    };
+
+    let url = options.repo_url.trim().to_string();
+    if url.is_empty() || url.starts_with('-') {
+        return outcome(
+            steps,
+            Some(cln_err("CLN-0001", "repoUrl is required", Some(url))),
+        );
+    }
+    let branch = req.branch.trim().to_string();
+
+    let clone_id = options.clone_id.clone().unwrap_or_else(|| {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+        format!("clone-{}", millis)
+    });
+    let mut on_line = |line: &str| {
+        let (phase, percent) = parse_progress(line);
+        let _ = app.emit(
+            EVENT_PROGRESS,
+            CloneProgressEvent {
+                clone_id: clone_id.clone(),
+                line: line.to_string(),
+                phase,
+                percent,
+            },
+        );
+    };
+
+    if req.mode == "local" {
+        let Some(git) = git_exe(if req.git_path.trim().is_empty() {
+            None
+        } else {
+            Some(req.git_path.clone())
+        }) else {
+            return outcome(
+                steps,
+                Some(ActionError {
+                    code: "GIT-0001".into(),
+                    severity: "FATAL".into(),
+                    message: "git not found".into(),
+                    detail: None,
+                }),
+            );
+        };
+        let lp = req.local_path.trim();
+        if lp.is_empty() {
+            return outcome(
+                steps,
+                Some(cln_err("FS-0100", "localPath is required", None)),
+            );
+        }
+        let dest = PathBuf::from(lp);
+        let res = clone_local(
+            &git,
+            &dest,
+            &url,
+            &options,
+            &branch,
+            &mut steps,
+            &mut on_line,
+        );
+        return outcome(steps, res.err());
+    }
+
+    let dest = req.remote_path.trim().to_string();
+    if dest.is_empty() {
+        return outcome(
+            steps,
+            Some(cln_err("CFG-0303", "remotePath is required", None)),
+        );
+    }
+    let auth = match remote_auth_plan(req.remote_auth.as_ref()) {
+        Ok(a) => a,
+        Err(e) => return outcome(steps, Some(e)),
+    };
+    // clone 先はまだ無いので、ログインディレクトリから実行する（相対 remotePath は $HOME 基準）
+    let repo = match remote_repo_for(&req, ".", &auth) {
+        Ok(r) => r,
+        Err(e) => return outcome(steps, Some(e)),
+    };
+    let script = match remote_script(&dest, &url, &options, &branch, &auth.git_opts) {
+        Ok(s) => s,
+        Err(e) => return outcome(steps, Some(e)),
+    };
+    // token / 鍵の設定はスクリプト全体に効かせる（submodule update でも使う）
+    let body = format!(
+        "{}sh -c {}",
+        auth.prelude,
+        shell_escape_posix_single(&script)
+    );
+    let step = repo.exec_stream(
+        &repo.command(&body),
+        auth.forward_agent,
+        auth.stdin.as_deref(),
+        &mut on_line,
+    );
+    let error = if step.ok {
+        None
+    } else {
+        Some(match step.exit_code {
+            20 => cln_err(
+                "CLN-0101",
+                "destination is already a git repository",
+                Some(dest.clone()),
+            ),
+            21 => cln_err(
+                "CLN-0100",
+                "destination is not empty (set allowNonEmpty to clone into it)",
+                Some(dest.clone()),
+            ),
+            _ => {
+                let conn = repo.classify(&step);
+                if conn.code != "SSH-0200" {
+                    conn
+                } else {
+                    cln_err(
+                        "CLN-0200",
+                        "git clone failed",
+                        Some(step.stderr.trim().to_string()),
+                    )
+                }
+            }
+        })
+    };
+    steps.push(step);
+    outcome(steps, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn parse_progress_reads_phase_and_percent() {
+        assert_eq!(
+            parse_progress("Receiving objects:  45% (450/1000), 1.2 MiB | 2 MiB/s"),
+            (Some("Receiving objects".into()), Some(45))
+        );
+        assert_eq!(
+            parse_progress("remote: Counting objects: 100% (12/12), done."),
+            (Some("Counting objects".into()), Some(100))
+        );
+        assert_eq!(parse_progress("Cloning into 'app'..."), (None, None));
+        assert_eq!(parse_progress("fatal: repository not found"), (None, None));
+        assert_eq!(parse_progress("remote: Total 12 (delta 0)"), (None, None));
+    }
+
+    #[test]
+    fn clone_args_validate_options() {
+        let opts = CloneOptions {
+            depth: Some(1),
+            filter: Some(" blob:none ".into()),
+            single_branch: true,
+            recurse_submodules: true,
+            ..CloneOptions::default()
+        };
+        assert_eq!(
+            clone_args(&opts, "main", false).unwrap(),
+            [
+                "clone",
+                "--progress",
+                "--branch",
+                "main",
+                "--depth",
+                "1",
+                "--filter=blob:none",
+                "--single-branch",
+                "--recurse-submodules",
+                "--shallow-submodules",
+            ]
+        );
+        // no-checkout のときは submodule を取りに行かない
+        assert_eq!(
+            clone_args(&opts, "", true)
+                .unwrap()
+                .last()
+                .map(String::as_str),
+            Some("--no-checkout")
+        );
+
+        let err = |o: CloneOptions, b: &str| clone_args(&o, b, false).unwrap_err().code;
+        assert_eq!(err(CloneOptions::default(), "--upload-pack=x"), "CLN-0001");
+        let zero = CloneOptions {
+            depth: Some(0),
+            ..CloneOptions::default()
+        };
+        assert_eq!(err(zero, ""), "CLN-0001");
+        let bad = CloneOptions {
+            filter: Some("blob:none --x".into()),
+            ..CloneOptions::default()
+        };
+        assert_eq!(err(bad, ""), "CLN-0001");
+    }
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let d = path_to_string(dir);
+        let mut full = vec!["-C", d.as_str()];
+        full.extend_from_slice(args);
+        let s = run_capture(Path::new("git"), &full, None);
+        assert!(s.ok, "git {:?}: {}", args, s.stderr);
+        s.stdout.trim().to_string()
+    }
+
+    // a.txt を commit した src repo（file:// で clone する）と、reset だけ失敗する git の wrapper
+    fn setup(name: &str) -> (PathBuf, String, PathBuf) {
+        let root = std::env::temp_dir().join(format!("gitshlc-{}-{}", name, std::process::id()));
+        fs::remove_dir_all(&root).ok();
+        let src = root.join("src");
+        fs::create_dir_all(&src).unwrap();
+        git(&src, &["init", "-q"]);
+        git(&src, &["config", "user.name", "Tester"]);
+        git(&src, &["config", "user.email", "tester@example.com"]);
+        fs::write(src.join("a.txt"), "tracked\n").unwrap();
+        git(&src, &["add", "a.txt"]);
+        git(&src, &["commit", "-q", "-m", "init"]);
+
+        let bin = root.join("bin");
+        fs::create_dir_all(&bin).unwrap();
+        let real = path_to_string(&git_exe(None).unwrap());
+        let wrapper = bin.join("git");
+        fs::write(
+            &wrapper,
+            format!(
+                "#!/bin/sh\nfor a; do [ \"$a\" = reset ] && {{ echo 'reset failed' >&2; exit 1; }}; done\nexec {} \"$@\"\n",
+                shell_escape_posix_single(&real)
+            ),
+        )
+        .unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&wrapper, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        (root, format!("file://{}", path_to_string(&src)), bin)
+    }
+
+    // 既存ファイル keep.txt と、追跡対象と同じ名前の a.txt がある clone 先
+    fn non_empty(dest: &Path) {
+        fs::create_dir_all(dest).unwrap();
+        fs::write(dest.join("keep.txt"), "mine\n").unwrap();
+        fs::write(dest.join("a.txt"), "local\n").unwrap();
+    }
+
+    fn leftovers(dest: &Path) -> Vec<String> {
+        fs::read_dir(dest)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .filter(|n| n.starts_with(TMP_DIR_PREFIX))
+            .collect()
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn clone_local_into_empty_and_non_empty_dirs() {
+        let (root, url, bin) = setup("clone-local");
+        let git_exe = git_exe(None).unwrap();
+        let allow = CloneOptions {
+            allow_non_empty: true,
+            ..CloneOptions::default()
+        };
+        let clone = |git: &Path, dest: &Path, opts: &CloneOptions| {
+            let mut steps = Vec::new();
+            let res = clone_local(git, dest, &url, opts, "", &mut steps, &mut |_| {});
+            (res, steps)
+        };
+
+        // 空（まだ無い）ディレクトリ
+        let fresh = root.join("nested/fresh");
+        let (res, _) = clone(&git_exe, &fresh, &CloneOptions::default());
+        assert!(res.is_ok(), "{:?}", res);
+        assert_eq!(
+            fs::read_to_string(fresh.join("a.txt")).unwrap(),
+            "tracked\n"
+        );
+        let (res, _) = clone(&git_exe, &fresh, &allow);
+        assert_eq!(res.unwrap_err().code, "CLN-0101");
+
+        // 空でない: allowNonEmpty が無ければ止める
+        let dest = root.join("dest");
+        non_empty(&dest);
+        let (res, steps) = clone(&git_exe, &dest, &CloneOptions::default());
+        assert_eq!(res.unwrap_err().code, "CLN-0100");
+        assert!(steps.is_empty());
+
+        // no-checkout → .git を移す → reset --hard（追跡対象は上書き、それ以外は残す）
+        let (res, steps) = clone(&git_exe, &dest, &allow);
+        assert!(res.is_ok(), "{:?}", res);
+        assert!(steps[0].cmd.contains("--no-checkout"));
+        assert!(steps[1].cmd.starts_with("mv "));
+        assert_eq!(fs::read_to_string(dest.join("a.txt")).unwrap(), "tracked\n");
+        assert_eq!(fs::read_to_string(dest.join("keep.txt")).unwrap(), "mine\n");
+        assert!(leftovers(&dest).is_empty());
+
+        // reset --hard に失敗したら移した .git を戻す
+        let broken = root.join("broken");
+        non_empty(&broken);
+        let (res, steps) = clone(&bin.join("git"), &broken, &allow);
+        let e = res.unwrap_err();
+        assert_eq!(e.code, "CLN-0200");
+        assert_eq!(e.message, "git reset --hard failed");
+        assert!(steps.last().unwrap().cmd.starts_with("rm -rf "));
+        assert!(!broken.join(".git").exists());
+        assert!(leftovers(&broken).is_empty());
+        assert_eq!(
+            fs::read_to_string(broken.join("keep.txt")).unwrap(),
+            "mine\n"
+        );
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn remote_script_clones_like_clone_local() {
+        let (root, url, bin) = setup("clone-script");
+        let allow = CloneOptions {
+            allow_non_empty: true,
+            ..CloneOptions::default()
+        };
+        let run = |dest: &Path, opts: &CloneOptions, path: Option<&Path>| {
+            let script = remote_script(&path_to_string(dest), &url, opts, "", "").unwrap();
+            let mut cmd = Command::new("sh");
+            cmd.args(["-c", &script]).current_dir(&root);
+            if let Some(p) = path {
+                let old = std::env::var("PATH").unwrap_or_default();
+                cmd.env("PATH", format!("{}:{}", path_to_string(p), old));
+            }
+            cmd.output().unwrap().status.code()
+        };
+
+        let fresh = root.join("nested/fresh");
+        assert_eq!(run(&fresh, &CloneOptions::default(), None), Some(0));
+        assert_eq!(
+            fs::read_to_string(fresh.join("a.txt")).unwrap(),
+            "tracked\n"
+        );
+        assert_eq!(run(&fresh, &allow, None), Some(20));
+
+        let dest = root.join("dest");
+        non_empty(&dest);
+        assert_eq!(run(&dest, &CloneOptions::default(), None), Some(21));
+        assert!(!dest.join(".git").exists());
+        assert_eq!(run(&dest, &allow, None), Some(0));
+        assert_eq!(fs::read_to_string(dest.join("a.txt")).unwrap(), "tracked\n");
+        assert_eq!(fs::read_to_string(dest.join("keep.txt")).unwrap(), "mine\n");
+        assert!(leftovers(&dest).is_empty());
+
+        let broken = root.join("broken");
+        non_empty(&broken);
+        assert_eq!(run(&broken, &allow, Some(&bin)), Some(1));
+        assert!(!broken.join(".git").exists());
+        assert!(leftovers(&broken).is_empty());
+
+        fs::remove_dir_all(&root).ok();
+    }
+}
@@ -0,0 +1,317 @@
+// サーバー側の deploy key（ed25519）を用意して repo に紐づける
+use crate::hosting::{self, HostingRequest};
+use crate::{
+    shell_escape_posix_single, ssh_exe, step_error, validate_run_as, ActionError, RemoteAuthPlan,
+    RemoteRepo, RunAsConfig, SshConfig, StepResult, Transport,
+};
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DeployKeyHosting {
+    token: String,
+    // github | gitlab | gitea | bitbucket（無ければ origin の host から）
+    provider: Option<String>,
+    // owner/name（無ければ origin のパス）
+    repo: Option<String>,
+    title: Option<String>,
+    api_base_url: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DeployKeyResult {
+    ok: bool,
+    key_path: Option<String>,
+    public_key: Option<String>,
+    // 今回生成したか（false = 既存の鍵を再利用）
+    created: bool,
+    hosting_key_id: Option<u64>,
+    steps: Vec<StepResult>,
+    error: Option<ActionError>,
+}
+
+fn valid_key_name(s: &str) -> bool {
+    !s.is_empty()
+        && !s.starts_with('.')
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+fn default_key_name(remote_path: &str) -> String {
+    let base = remote_path
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or("");
+    let base: String = base
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if base.is_empty() {
+        "deploy".into()
+    } else {
+        format!("deploy_{}", base)
+    }
+}
+
+// 鍵は $HOME/.ssh/gitshlc/<name> に置く
+fn keygen_script(name: &str, comment: &str) -> String {
+    format!(
+        r#"
+set -e
+DIR="$HOME/.ssh/gitshlc"
+KEY="$DIR/{name}"
+mkdir -p "$DIR"
+chmod 700 "$HOME/.ssh" "$DIR"
+if [ ! -f "$KEY" ]; then
+  ssh-keygen -q -t ed25519 -N '' -C {comment} -f "$KEY"
+  echo GITSHLC_KEY_CREATED
+fi
+echo "GITSHLC_KEY_PATH=$KEY"
+"#,
+        name = name,
+        comment = shell_escape_posix_single(comment),
+    )
+}
+
+fn config_ssh_command(key_path: &str) -> String {
+    let ssh_command = format!(
+        "ssh -i {} -o IdentitiesOnly=yes",
+        shell_escape_posix_single(key_path)
+    );
+    format!(
+        "git config core.sshCommand {}",
+        shell_escape_posix_single(&ssh_command)
+    )
+}
+
+#[tauri::command(async, rename_all = "camelCase")]
+pub(crate) fn provision_deploy_key(
+    ssh_path: Option<String>,
+    ssh: SshConfig,
+    remote_path: String,
+    key_name: Option<String>,
+    run_as: Option<RunAsConfig>,
+    hosting: Option<DeployKeyHosting>,
+) -> DeployKeyResult {
+    let mut out = DeployKeyResult {
+        ok: false,
+        key_path: None,
+        public_key: None,
+        created: false,
+        hosting_key_id: None,
+        steps: Vec::new(),
+        error: None,
+    };
+
+    let fail = |mut out: DeployKeyResult, code: &str, message: &str, detail: Option<String>| {
+        out.ok = false;
+        out.error = Some(ActionError {
+            code: code.into(),
+            severity: "ERROR".into(),
+            message: message.into(),
+            detail,
+        });
+        out
+    };
+
+    let Some(ssh_exe) = ssh_exe(ssh_path) else {
+        return fail(out, "SSH-0001", "ssh not found", None);
+    };
+    if ssh.host.trim().is_empty() || ssh.user.trim().is_empty() {
+        return fail(out, "CFG-0302", "ssh host/user is required", None);
+    }
+    let remote_path = remote_path.trim().to_string();
+    if remote_path.is_empty() {
+        return fail(out, "CFG-0303", "remotePath is required", None);
+    }
+
+    let name = key_name
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| default_key_name(&remote_path));
+    if !valid_key_name(&name) {
+        return fail(out, "KEY-0100", "invalid keyName", Some(name));
+    }
+
+    // runAs 指定時は鍵もそのユーザーの $HOME に置く
+    let auth = RemoteAuthPlan::default();
+    if let Err(e) = validate_run_as(run_as.as_ref(), &auth) {
+        return fail(out, &e.code, &e.message, e.detail);
+    }
+    let repo = RemoteRepo {
+        transport: Transport::Ssh {
+            exe: ssh_exe,
+            cfg: ssh.clone(),
+        },
+        path: &remote_path,
+        run_as: run_as.as_ref(),
+        wrapper: None,
+        auth: &auth,
+    };
+
+    // 1) 鍵が無ければ生成（既存なら触らない）。絶対パスを返してもらう
+    let comment = format!("gitshlc-{}@{}", name, ssh.host.trim());
+    let script = keygen_script(&name, &comment);
+    let gen = repo.run_home(&format!("sh -c {}", shell_escape_posix_single(&script)));
+    let created = gen
+        .stdout
+        .lines()
+        .any(|l| l.trim() == "GITSHLC_KEY_CREATED");
+    let key_path = gen
+        .stdout
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("GITSHLC_KEY_PATH="))
+        .map(|s| s.to_string());
+    let gen_ok = gen.ok;
+    let gen_err = gen.stderr.clone();
+    out.steps.push(gen);
+    out.created = created;
+
+    let key_path = match key_path {
+        Some(p) if gen_ok => p,
+        _ => {
+            return fail(
+                out,
+                "KEY-0101",
+                "failed to generate deploy key",
+                Some(gen_err),
+            );
+        }
+    };
+    out.key_path = Some(key_path.clone());
+
+    // 2) 公開鍵
+    let pub_step = repo.run_home(&format!(
+        "cat {}",
+        shell_escape_posix_single(&format!("{}.pub", key_path))
+    ));
+    let public_key = pub_step.stdout.trim().to_string();
+    if !pub_step.ok || public_key.is_empty() {
+        let detail = Some(pub_step.stderr.clone());
+        out.steps.push(pub_step);
+        return fail(out, "KEY-0102", "failed to read public key", detail);
+    }
+    out.steps.push(pub_step);
+    out.public_key = Some(public_key.clone());
+
+    // 3) repo 単位で core.sshCommand を設定（グローバル設定は汚さない）。ここだけ remote_path で実行
+    let cfg_step = repo.run(&config_ssh_command(&key_path));
+    if !cfg_step.ok {
+        let detail = Some(cfg_step.stderr.clone());
+        out.steps.push(cfg_step);
+        return fail(out, "KEY-0103", "failed to set core.sshCommand", detail);
+    }
+    out.steps.push(cfg_step);
+
+    // 4) ホスティング側に read-only deploy key として登録（任意）。provider は origin から
+    if let Some(h) = hosting {
+        if h.token.trim().is_empty() {
+            return fail(out, "CFG-0401", "hosting.token is required", None);
+        }
+        let origin_step = repo.run("git remote get-url origin");
+        let origin = Some(origin_step.stdout.trim().to_string()).filter(|_| origin_step.ok);
+        out.steps.push(origin_step);
+        let target = match hosting::resolve(&HostingRequest::from_origin(
+            origin,
+            h.provider.clone(),
+            h.api_base_url.clone(),
+            &h.token,
+            h.repo.clone(),
+        )) {
+            Ok(t) => t,
+            Err(e) => {
+                out.error = Some(e);
+                return out;
+            }
+        };
+        let Some(repo) = target.repo.clone() else {
+            return fail(out, "HOST-0002", "repo (owner/name) is required", None);
+        };
+        let title = h.title.filter(|t| !t.trim().is_empty()).unwrap_or(comment);
+        let cmd = format!("add deploy key {} ({})", repo, target.api_base_url);
+
+        let added = hosting::provider_for(&target, &h.token).add_deploy_key(
+            &repo,
+            &title,
+            public_key.trim(),
+            true,
+        );
+        match added {
+            Ok(id) => {
+                out.steps.push(StepResult {
+                    cmd,
+                    cwd: None,
+                    ok: true,
+                    exit_code: 0,
+                    stdout: format!("deploy key id={}", id),
+                    stderr: "".into(),
+                });
+                out.hosting_key_id = Some(id);
+            }
+            Err(e) => {
+                out.steps.push(step_error(cmd, e.message().to_string()));
+                out.error = Some(e.into_action("HOST", "failed to add deploy key"));
+                return out;
+            }
+        }
+    }
+
+    out.ok = true;
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_name_defaults_to_repo_dir() {
+        assert_eq!(default_key_name("/srv/app/"), "deploy_app");
+        assert_eq!(default_key_name("/srv/my app.git"), "deploy_my_app_git");
+        assert_eq!(default_key_name("/"), "deploy");
+        assert!(valid_key_name("deploy_app.v2"));
+        assert!(!valid_key_name(".hidden"));
+        assert!(!valid_key_name("../x"));
+        assert!(!valid_key_name(""));
+    }
+
+    #[test]
+    fn keygen_script_uses_key_under_gitshlc_dir() {
+        let s = keygen_script("deploy_app", "gitshlc-deploy_app@host");
+        assert!(s.contains(r#"KEY="$DIR/deploy_app""#));
+        assert!(s.contains(r#"DIR="$HOME/.ssh/gitshlc""#));
+        assert!(s.contains(r#"-C 'gitshlc-deploy_app@host' -f "$KEY""#));
+    }
+
+    #[test]
+    fn ssh_command_is_quoted_twice() {
+        assert_eq!(
+            config_ssh_command("/home/u/.ssh/gitshlc/deploy_app"),
+            r"git config core.sshCommand 'ssh -i '\''/home/u/.ssh/gitshlc/deploy_app'\'' -o IdentitiesOnly=yes'"
+        );
+    }
+
+    #[test]
+    fn ssh_command_keeps_key_path_as_one_argument() {
+        let sh = |cmd: &str| {
+            let o = std::process::Command::new("sh")
+                .arg("-c")
+                .arg(cmd)
+                .output()
+                .unwrap();
+            String::from_utf8_lossy(&o.stdout).into_owned()
+        };
+        // git config に渡る値 → git が sh で実行するときの引数
+        let cmd = config_ssh_command("/k/it's key");
+        let value = sh(&cmd.replacen("git config core.sshCommand", "printf %s", 1));
+        let args = sh(&value.replacen("ssh", "printf '%s|'", 1));
+        assert_eq!(args, "-i|/k/it's key|-o|IdentitiesOnly=yes|");
+    }
+}
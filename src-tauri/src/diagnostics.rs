@@ -0,0 +1,545 @@
+// 実行環境の診断（identity / credential helper / safe.directory / ssh-agent / CRLF）。
+// ローカルと remote（SSH 先の remote_path）で同じ判定を使い、状態・説明・対処をまとめて返す
+use std::path::{Path, PathBuf};
+
+use crate::{
+    is_windows, path_to_string, remote_auth_plan, remote_url, run_capture,
+    shell_escape_posix_single, ssh_exe, validate_run_as, RemoteRepo, RunAsConfig, SshConfig,
+    Transport,
+};
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Diagnostic {
+    // identity | credential_helper | safe_directory | ssh_agent | crlf
+    id: String,
+    // ok | warn | error | skipped
+    status: String,
+    message: String,
+    explanation: String,
+    // そのまま実行できるコマンド（無ければ None）
+    fix: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DiagnosticsReport {
+    // error が 1 つも無い
+    ok: bool,
+    // local | user@host:remote_path
+    target: String,
+    diagnostics: Vec<Diagnostic>,
+}
+
+enum RepoState {
+    NotChecked,
+    Ok,
+    Dubious,
+    NotARepo,
+}
+
+enum AgentState {
+    NotChecked,
+    NoSocket,
+    NoClient,
+    // ssh-add -l の終了コード: 0 = 鍵あり / 1 = 鍵なし / それ以外 = 接続できない
+    Exit(i32),
+}
+
+struct Facts {
+    user_name: Option<String>,
+    user_email: Option<String>,
+    credential_helper: Option<String>,
+    autocrlf: Option<String>,
+    origin_url: Option<String>,
+    repo: RepoState,
+    agent: AgentState,
+    windows: bool,
+    remote: bool,
+    path: Option<String>,
+}
+
+fn non_empty(s: &str) -> Option<String> {
+    let t = s.trim();
+    if t.is_empty() {
+        None
+    } else {
+        Some(t.to_string())
+    }
+}
+
+fn diag(
+    id: &str,
+    status: &str,
+    message: &str,
+    explanation: &str,
+    fix: Option<String>,
+) -> Diagnostic {
+    Diagnostic {
+        id: id.into(),
+        status: status.into(),
+        message: message.into(),
+        explanation: explanation.into(),
+        fix,
+    }
+}
+
+/// OS ごとの無難な credential.helper（remote は Linux サーバー想定）
+pub(crate) fn default_credential_helper(remote: bool) -> &'static str {
+    if remote {
+        "cache --timeout=3600"
+    } else if is_windows() {
+        "manager"
+    } else if std::env::consts::OS == "macos" {
+        "osxkeychain"
+    } else {
+        "cache --timeout=3600"
+    }
+}
+
+fn evaluate(f: &Facts) -> Vec<Diagnostic> {
+    let mut out = Vec::new();
+
+    // identity
+    let mut missing = Vec::new();
+    let mut fixes = Vec::new();
+    if f.user_name.is_none() {
+        missing.push("user.name");
+        fixes.push("git config --global user.name \"Your Name\"");
+    }
+    if f.user_email.is_none() {
+        missing.push("user.email");
+        fixes.push("git config --global user.email \"you@example.com\"");
+    }
+    out.push(if missing.is_empty() {
+        diag(
+            "identity",
+            "ok",
+            &format!(
+                "{} <{}>",
+                f.user_name.clone().unwrap_or_default(),
+                f.user_email.clone().unwrap_or_default()
+            ),
+            "commit identity is configured",
+            None,
+        )
+    } else {
+        diag(
+            "identity",
+            "error",
+            &format!("{} not set", missing.join(" / ")),
+            "merge (--no-ff) and the initial empty commit on push create commits and fail without an identity",
+            Some(fixes.join(" && ")),
+        )
+    });
+
+    // credential helper（origin が HTTPS のときだけ必要）
+    let origin_scheme = f
+        .origin_url
+        .as_deref()
+        .and_then(remote_url::parse)
+        .map(|u| u.scheme);
+    let https_origin = matches!(origin_scheme.as_deref(), Some("https") | Some("http"));
+    out.push(match (&f.credential_helper, &origin_scheme) {
+        (Some(h), _) => diag(
+            "credential_helper",
+            "ok",
+            &format!("credential.helper = {}", h),
+            "HTTPS credentials are stored by the helper",
+            None,
+        ),
+        (None, Some(_)) if !https_origin => diag(
+            "credential_helper",
+            "ok",
+            "not needed (origin does not use HTTPS)",
+            "SSH and local remotes do not use credential helpers",
+            None,
+        ),
+        (None, _) => {
+            let helper = default_credential_helper(f.remote);
+            diag(
+                "credential_helper",
+                if https_origin { "error" } else { "warn" },
+                "no credential.helper configured",
+                "git runs with GIT_TERMINAL_PROMPT=0, so HTTPS remotes that need a password fail instead of prompting",
+                Some(format!("git config --global credential.helper '{}'", helper)),
+            )
+        }
+    });
+
+    // safe.directory
+    out.push(match f.repo {
+        RepoState::NotChecked => diag(
+            "safe_directory",
+            "skipped",
+            "no repository path given",
+            "pass a repository path to check ownership",
+            None,
+        ),
+        RepoState::Ok => diag(
+            "safe_directory",
+            "ok",
+            "repository is accessible",
+            "git accepts the repository owner",
+            None,
+        ),
+        RepoState::Dubious => diag(
+            "safe_directory",
+            "error",
+            "dubious ownership",
+            if f.remote {
+                "the repository is owned by another user; git refuses to run in it (or use runAs to run as the owner)"
+            } else {
+                "the repository is owned by another user; git refuses to run in it"
+            },
+            Some(format!(
+                "git config --global --add safe.directory {}",
+                shell_escape_posix_single(f.path.as_deref().unwrap_or_default())
+            )),
+        ),
+        RepoState::NotARepo => diag(
+            "safe_directory",
+            "warn",
+            "not a git repository",
+            "the path is not inside a git work tree",
+            None,
+        ),
+    });
+
+    // ssh-agent
+    let start_agent = if is_windows() && !f.remote {
+        "Get-Service ssh-agent | Set-Service -StartupType Automatic; Start-Service ssh-agent; ssh-add"
+    } else {
+        "eval \"$(ssh-agent -s)\" && ssh-add"
+    };
+    out.push(match f.agent {
+        AgentState::NotChecked => diag(
+            "ssh_agent",
+            "skipped",
+            "agent not forwarded",
+            "enable agent forwarding for this check to inspect the forwarded agent",
+            None,
+        ),
+        AgentState::NoClient => diag(
+            "ssh_agent",
+            "skipped",
+            "ssh-add not found",
+            "cannot query the agent without ssh-add",
+            None,
+        ),
+        AgentState::NoSocket => diag(
+            "ssh_agent",
+            "warn",
+            "no ssh-agent (SSH_AUTH_SOCK is not set)",
+            "keys with a passphrase cannot be used non-interactively, and agent forwarding has nothing to forward",
+            Some(start_agent.into()),
+        ),
+        AgentState::Exit(0) => diag(
+            "ssh_agent",
+            "ok",
+            "ssh-agent has identities",
+            "keys are available to ssh and git",
+            None,
+        ),
+        AgentState::Exit(1) => diag(
+            "ssh_agent",
+            "warn",
+            "ssh-agent has no identities",
+            "the agent is running but no key is loaded",
+            Some("ssh-add ~/.ssh/id_ed25519".into()),
+        ),
+        AgentState::Exit(_) => diag(
+            "ssh_agent",
+            "warn",
+            "ssh-agent is unreachable",
+            "SSH_AUTH_SOCK points to an agent that does not answer",
+            Some(start_agent.into()),
+        ),
+    });
+
+    // CRLF
+    let autocrlf = f.autocrlf.as_deref().map(|s| s.to_ascii_lowercase());
+    out.push(match (f.windows, autocrlf.as_deref()) {
+        (true, Some("true")) | (true, Some("input")) => diag(
+            "crlf",
+            "ok",
+            &format!("core.autocrlf = {}", autocrlf.clone().unwrap_or_default()),
+            "line endings are normalized to LF in the repository",
+            None,
+        ),
+        (true, _) => diag(
+            "crlf",
+            "warn",
+            "core.autocrlf is not enabled on Windows",
+            "files edited on Windows may be committed with CRLF and show up as whole-file changes elsewhere",
+            Some("git config --global core.autocrlf true".into()),
+        ),
+        (false, Some("true")) => diag(
+            "crlf",
+            "warn",
+            "core.autocrlf = true on a non-Windows system",
+            "checkouts get CRLF line endings, which breaks shell scripts and produces dirty trees",
+            Some("git config --global core.autocrlf input".into()),
+        ),
+        (false, v) => diag(
+            "crlf",
+            "ok",
+            &format!("core.autocrlf = {}", v.unwrap_or("(unset)")),
+            "line endings are left as committed",
+            None,
+        ),
+    });
+
+    out
+}
+
+fn report(target: String, diagnostics: Vec<Diagnostic>) -> DiagnosticsReport {
+    DiagnosticsReport {
+        ok: diagnostics.iter().all(|d| d.status != "error"),
+        target,
+        diagnostics,
+    }
+}
+
+fn git_config(git: &Path, repo: Option<&Path>, key: &str) -> Option<String> {
+    let mut args: Vec<String> = Vec::new();
+    if let Some(r) = repo {
+        args.push("-C".into());
+        args.push(path_to_string(r));
+    }
+    args.extend(["config".into(), "--get-all".into(), key.into()]);
+    let refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    let step = run_capture(git, &refs, None);
+    // 複数あれば最後の値が有効
+    step.stdout.lines().rev().find_map(non_empty)
+}
+
+// ssh と同じディレクトリの ssh-add / ssh-keygen 等を優先する（Windows の OpenSSH 同梱版）
+pub(crate) fn ssh_tool_exe(ssh: Option<&Path>, base: &str) -> Option<PathBuf> {
+    let name = if is_windows() {
+        format!("{}.exe", base)
+    } else {
+        base.to_string()
+    };
+    ssh.and_then(|s| s.parent())
+        .map(|d| d.join(&name))
+        .filter(|p| p.is_file())
+        .or_else(|| crate::find_in_path(base))
+}
+
+/// ローカルの診断（local_path があれば repo 単位の設定と所有者も見る）
+pub(crate) fn local_report(
+    git: &Path,
+    ssh: Option<&Path>,
+    local_path: Option<&str>,
+) -> DiagnosticsReport {
+    let repo = local_path
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from);
+
+    let repo_state = match &repo {
+        None => RepoState::NotChecked,
+        Some(p) => {
+            let step = run_capture(
+                git,
+                &["-C", &path_to_string(p), "rev-parse", "--git-dir"],
+                None,
+            );
+            if step.ok {
+                RepoState::Ok
+            } else if step.stderr.contains("dubious ownership") {
+                RepoState::Dubious
+            } else {
+                RepoState::NotARepo
+            }
+        }
+    };
+    let cfg_repo = match repo_state {
+        RepoState::Ok => repo.as_deref(),
+        _ => None,
+    };
+
+    let agent = if !is_windows() && std::env::var_os("SSH_AUTH_SOCK").is_none() {
+        AgentState::NoSocket
+    } else {
+        match ssh_tool_exe(ssh, "ssh-add") {
+            Some(exe) => AgentState::Exit(run_capture(&exe, &["-l"], None).exit_code),
+            None => AgentState::NoClient,
+        }
+    };
+
+    let facts = Facts {
+        user_name: git_config(git, cfg_repo, "user.name"),
+        user_email: git_config(git, cfg_repo, "user.email"),
+        credential_helper: git_config(git, cfg_repo, "credential.helper"),
+        autocrlf: git_config(git, cfg_repo, "core.autocrlf"),
+        origin_url: cfg_repo.and_then(|r| git_config(git, Some(r), "remote.origin.url")),
+        repo: repo_state,
+        agent,
+        windows: is_windows(),
+        remote: false,
+        path: repo.as_deref().map(path_to_string),
+    };
+    report("local".into(), evaluate(&facts))
+}
+
+const REMOTE_SCRIPT: &str = r#"
+echo "NAME=$(git config user.name 2>/dev/null)"
+echo "EMAIL=$(git config user.email 2>/dev/null)"
+echo "HELPER=$(git config --get-all credential.helper 2>/dev/null | tail -n 1)"
+echo "AUTOCRLF=$(git config core.autocrlf 2>/dev/null)"
+echo "ORIGIN=$(git config remote.origin.url 2>/dev/null)"
+if out="$(git rev-parse --git-dir 2>&1)"; then
+  echo "REPO=ok"
+else
+  case "$out" in
+    *"dubious ownership"*) echo "REPO=dubious" ;;
+    *) echo "REPO=none" ;;
+  esac
+fi
+if [ -z "$SSH_AUTH_SOCK" ]; then
+  echo "AGENT=nosock"
+elif command -v ssh-add >/dev/null 2>&1; then
+  ssh-add -l >/dev/null 2>&1
+  echo "AGENT=$?"
+else
+  echo "AGENT=noclient"
+fi
+"#;
+
+// cd に失敗したら全体を実行しない（$HOME で診断した結果を返さない）
+fn remote_body() -> String {
+    format!("{{ {}}}", REMOTE_SCRIPT)
+}
+
+/// SSH 先の remote_path で診断する（runAs があればその利用者として）。
+/// forwardAgent=true のときだけ -A を付けて転送された agent を確認する
+#[tauri::command(async, rename_all = "camelCase")]
+pub(crate) fn remote_diagnostics(
+    ssh_path: Option<String>,
+    ssh: SshConfig,
+    remote_path: String,
+    run_as: Option<RunAsConfig>,
+    forward_agent: Option<bool>,
+) -> Result<DiagnosticsReport, String> {
+    let Some(exe) = ssh_exe(ssh_path) else {
+        return Err("ssh not found. Run preflight and set sshPath if needed.".into());
+    };
+    if ssh.host.trim().is_empty() || ssh.user.trim().is_empty() {
+        return Err("ssh.host / ssh.user is required".into());
+    }
+    let remote_path = remote_path.trim().to_string();
+    if remote_path.is_empty() {
+        return Err("remotePath is required".into());
+    }
+
+    let auth = remote_auth_plan(None).map_err(|e| e.message)?;
+    validate_run_as(run_as.as_ref(), &auth).map_err(|e| e.message)?;
+    let target = format!("{}@{}:{}", ssh.user.trim(), ssh.host.trim(), remote_path);
+
+    let repo = RemoteRepo {
+        transport: Transport::Ssh { exe, cfg: ssh },
+        path: &remote_path,
+        run_as: run_as.as_ref(),
+        wrapper: None,
+        auth: &auth,
+    };
+    let forward = forward_agent.unwrap_or(false);
+    let step = repo.exec(&repo.command(&remote_body()), forward, None);
+    if !step.ok {
+        return Err(format!(
+            "remote diagnostics failed: exit={} stderr={}",
+            step.exit_code,
+            step.stderr.trim()
+        ));
+    }
+
+    let get = |key: &str| {
+        step.stdout
+            .lines()
+            .find_map(|l| l.strip_prefix(&format!("{}=", key)))
+            .and_then(non_empty)
+    };
+
+    let agent = match get("AGENT").as_deref() {
+        _ if !forward => AgentState::NotChecked,
+        Some("nosock") => AgentState::NoSocket,
+        Some("noclient") | None => AgentState::NoClient,
+        Some(code) => AgentState::Exit(code.parse().unwrap_or(2)),
+    };
+
+    let facts = Facts {
+        user_name: get("NAME"),
+        user_email: get("EMAIL"),
+        credential_helper: get("HELPER"),
+        autocrlf: get("AUTOCRLF"),
+        origin_url: get("ORIGIN"),
+        repo: match get("REPO").as_deref() {
+            Some("ok") => RepoState::Ok,
+            Some("dubious") => RepoState::Dubious,
+            _ => RepoState::NotARepo,
+        },
+        agent,
+        windows: false,
+        remote: true,
+        path: Some(remote_path.clone()),
+    };
+    Ok(report(target, evaluate(&facts)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RemoteAuthPlan;
+
+    #[test]
+    fn safe_directory_fix_quotes_path() {
+        let f = Facts {
+            user_name: None,
+            user_email: None,
+            credential_helper: None,
+            autocrlf: None,
+            origin_url: None,
+            repo: RepoState::Dubious,
+            agent: AgentState::NotChecked,
+            windows: false,
+            remote: true,
+            path: Some("/srv/it's here".into()),
+        };
+        let d = evaluate(&f)
+            .into_iter()
+            .find(|d| d.id == "safe_directory")
+            .unwrap();
+        assert_eq!(
+            d.fix.as_deref(),
+            Some(r"git config --global --add safe.directory '/srv/it'\''s here'")
+        );
+    }
+
+    #[test]
+    fn remote_script_does_not_run_when_cd_fails() {
+        let auth = RemoteAuthPlan::default();
+        let repo = RemoteRepo {
+            transport: Transport::Local {
+                sh: PathBuf::from("sh"),
+            },
+            path: "/nonexistent/gitshlc-diagnostics",
+            run_as: None,
+            wrapper: None,
+            auth: &auth,
+        };
+        let step = repo.exec(&repo.command(&remote_body()), false, None);
+        assert!(!step.ok);
+        assert!(!step.stdout.contains("NAME="), "{}", step.stdout);
+
+        let home = std::env::temp_dir();
+        let path = path_to_string(&home);
+        let repo = RemoteRepo {
+            path: &path,
+            ..repo
+        };
+        let step = repo.exec(&repo.command(&remote_body()), false, None);
+        assert!(step.stdout.contains("AGENT="), "{}", step.stderr);
+    }
+}
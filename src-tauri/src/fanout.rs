@@ -0,0 +1,315 @@
+// 1 つの環境に複数ホストがある場合: 全ホストに同じ action を流し、最後に HEAD が揃っているか確認する
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use crate::{
+    remote_auth_plan, remote_repo_for, run_action, ActionError, ActionOutcome, RunActionRequest,
+    SshConfig,
+};
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HostTarget {
+    ssh: SshConfig,
+    // 省略時は req.remotePath
+    remote_path: Option<String>,
+    label: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FanoutConfig {
+    // parallel (default) | sequential | canary
+    strategy: Option<String>,
+    // 失敗数がこれを超えたら残りのホストは実行しない（default 0）
+    max_failures: Option<usize>,
+    // 同時に実行するホスト数（default: 全ホスト。maxFailures 指定時は 1）
+    parallelism: Option<usize>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HostOutcome {
+    label: String,
+    host: String,
+    remote_path: String,
+    // max_failures / canary 失敗で実行しなかった
+    skipped: bool,
+    outcome: Option<ActionOutcome>,
+    head: Option<String>,
+    head_error: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FanoutOutcome {
+    ok: bool,
+    // ok | partial | failed | diverged
+    verdict: String,
+    strategy: String,
+    mode: String,
+    action: String,
+    env_key: String,
+    heads_consistent: bool,
+    hosts: Vec<HostOutcome>,
+    error: Option<ActionError>,
+}
+
+fn host_requests(req: &RunActionRequest) -> Vec<RunActionRequest> {
+    if req.hosts.is_empty() {
+        return vec![req.clone()];
+    }
+    req.hosts
+        .iter()
+        .map(|h| {
+            let mut r = req.clone();
+            r.ssh = h.ssh.clone();
+            if let Some(p) = &h.remote_path {
+                if !p.trim().is_empty() {
+                    r.remote_path = p.clone();
+                }
+            }
+            r.hosts = Vec::new();
+            r
+        })
+        .collect()
+}
+
+fn host_label(req: &RunActionRequest, t: Option<&HostTarget>) -> String {
+    t.and_then(|h| h.label.clone())
+        .filter(|l| !l.trim().is_empty())
+        .unwrap_or_else(|| req.ssh.host.clone())
+}
+
+// 各要素を別スレッドで実行して順番どおりに返す
+fn run_parallel<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    thread::scope(|s| {
+        let handles: Vec<_> = items.iter().map(|it| s.spawn(|| f(it))).collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("host worker panicked"))
+            .collect()
+    })
+}
+
+// 空いたワーカーが次のホストを取る。失敗数が max_failures を超えたらそれ以降は取らない
+fn dispatch<R, F>(
+    indices: &[usize],
+    parallelism: usize,
+    max_failures: usize,
+    f: F,
+) -> Vec<(usize, R)>
+where
+    R: Send,
+    F: Fn(usize) -> (R, bool) + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let failures = AtomicUsize::new(0);
+    let done = Mutex::new(Vec::new());
+    thread::scope(|s| {
+        for _ in 0..parallelism.clamp(1, indices.len().max(1)) {
+            s.spawn(|| loop {
+                if failures.load(Ordering::SeqCst) > max_failures {
+                    break;
+                }
+                let Some(&i) = indices.get(next.fetch_add(1, Ordering::SeqCst)) else {
+                    break;
+                };
+                let (r, ok) = f(i);
+                if !ok {
+                    failures.fetch_add(1, Ordering::SeqCst);
+                }
+                done.lock().unwrap().push((i, r));
+            });
+        }
+    });
+    done.into_inner().unwrap()
+}
+
+fn remote_head(req: &RunActionRequest) -> Result<String, String> {
+    let remote_path = req.remote_path.trim().to_string();
+    let auth = remote_auth_plan(None).map_err(|e| e.message)?;
+    let repo = remote_repo_for(req, &remote_path, &auth).map_err(|e| e.message)?;
+    let step = repo.run("git rev-parse HEAD");
+    let head = step.stdout.trim().to_string();
+    if step.ok && !head.is_empty() {
+        Ok(head)
+    } else {
+        Err(step.stderr.trim().to_string())
+    }
+}
+
+#[tauri::command(async, rename_all = "camelCase")]
+pub(crate) fn run_action_hosts(req: RunActionRequest) -> FanoutOutcome {
+    let fan = req.fanout.clone().unwrap_or_default();
+    let strategy = fan
+        .strategy
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .unwrap_or("parallel")
+        .to_string();
+
+    let mut out = FanoutOutcome {
+        ok: false,
+        verdict: "failed".into(),
+        strategy: strategy.clone(),
+        mode: req.mode.clone(),
+        action: req.action.clone(),
+        env_key: req.env_key.clone(),
+        heads_consistent: false,
+        hosts: Vec::new(),
+        error: None,
+    };
+
+    let error = |code: &str, message: &str, detail: Option<String>| ActionError {
+        code: code.into(),
+        severity: "ERROR".into(),
+        message: message.into(),
+        detail,
+    };
+
+    if req.mode != "ssh" && req.mode != "wrapped" {
+        out.error = Some(error(
+            "CFG-0330",
+            "multiple hosts require mode=ssh|wrapped",
+            Some(req.mode.clone()),
+        ));
+        return out;
+    }
+    if !matches!(strategy.as_str(), "parallel" | "sequential" | "canary") {
+        out.error = Some(error(
+            "CFG-0331",
+            "unknown fanout.strategy (expected parallel|sequential|canary)",
+            Some(strategy),
+        ));
+        return out;
+    }
+
+    let targets = host_requests(&req);
+    let n = targets.len();
+    let max_failures = fan.max_failures.unwrap_or(0);
+    // 全ホスト同時だと maxFailures で止める余地が無いので、指定時の既定は 1 台ずつ
+    let parallelism = match (strategy.as_str(), fan.parallelism) {
+        ("sequential", _) => 1,
+        (_, Some(p)) => p.max(1),
+        (_, None) if fan.max_failures.is_some() => 1,
+        (_, None) => n,
+    };
+
+    let run = |i: usize| {
+        let o = run_action(targets[i].clone());
+        let ok = o.ok;
+        (o, ok)
+    };
+    let mut results: Vec<Option<ActionOutcome>> = vec![None; n];
+    let mut canary_failed = false;
+
+    // canary は先頭 1 台を単独で先に流す
+    let rest: Vec<usize> = if strategy == "canary" {
+        let (o, ok) = run(0);
+        results[0] = Some(o);
+        canary_failed = !ok;
+        (1..n).collect()
+    } else {
+        (0..n).collect()
+    };
+    if !canary_failed {
+        for (i, o) in dispatch(&rest, parallelism, max_failures, run) {
+            results[i] = Some(o);
+        }
+    }
+
+    // 最後に全ホストの HEAD を確認する（スキップしたホストも含む）
+    let heads = run_parallel(&targets, remote_head);
+    let head_values: Vec<&String> = heads.iter().filter_map(|h| h.as_ref().ok()).collect();
+    let heads_consistent =
+        head_values.len() == n && head_values.iter().all(|h| *h == head_values[0]);
+
+    for (i, (result, head)) in results.into_iter().zip(heads).enumerate() {
+        let t = &targets[i];
+        out.hosts.push(HostOutcome {
+            label: host_label(t, req.hosts.get(i)),
+            host: t.ssh.host.clone(),
+            remote_path: t.remote_path.trim().to_string(),
+            skipped: result.is_none(),
+            outcome: result,
+            head: head.as_ref().ok().cloned(),
+            head_error: head.err(),
+        });
+    }
+
+    let ran_ok = out
+        .hosts
+        .iter()
+        .filter(|h| h.outcome.as_ref().map(|o| o.ok).unwrap_or(false))
+        .count();
+
+    out.heads_consistent = heads_consistent;
+    if ran_ok == n && heads_consistent {
+        out.ok = true;
+        out.verdict = "ok".into();
+    } else if ran_ok == n {
+        out.verdict = "diverged".into();
+        out.error = Some(error(
+            "FAN-0102",
+            "hosts report different HEAD after the action",
+            None,
+        ));
+    } else if ran_ok == 0 {
+        out.verdict = "failed".into();
+        out.error = Some(if canary_failed {
+            error(
+                "FAN-0103",
+                "canary host failed; remaining hosts were skipped",
+                Some(out.hosts[0].label.clone()),
+            )
+        } else {
+            error("FAN-0100", "action failed on all hosts", None)
+        });
+    } else {
+        out.verdict = "partial".into();
+        out.error = Some(error(
+            "FAN-0101",
+            "action failed or was skipped on some hosts",
+            Some(format!("ok={} total={}", ran_ok, n)),
+        ));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dispatch_stops_after_max_failures() {
+        let items: Vec<usize> = (0..6).collect();
+        let out = dispatch(&items, 1, 1, |i| (i, false));
+        assert_eq!(out.iter().map(|(i, _)| *i).collect::<Vec<_>>(), vec![0, 1]);
+
+        let out = dispatch(&items, 1, 0, |i| (i, i != 2));
+        assert_eq!(out.len(), 3);
+    }
+
+    #[test]
+    fn dispatch_runs_everything_when_healthy() {
+        let items: Vec<usize> = (0..6).collect();
+        let mut out = dispatch(&items, 4, 0, |i| (i * 10, true));
+        out.sort();
+        assert_eq!(out.len(), 6);
+        assert_eq!(out[5], (5, 50));
+        assert!(dispatch(&[], 3, 0, |i: usize| (i, true)).is_empty());
+    }
+}
@@ -0,0 +1,939 @@
+// 診断で見つかった問題の対処（identity / safe.directory / credential helper / known_hosts / core.sshCommand）。
+// 実行したコマンドはすべて StepResult に残し、config の変更は undo できるように元の値を返す
+use std::{fs, io::Write, path::PathBuf};
+
+use crate::diagnostics::{default_credential_helper, ssh_tool_exe};
+use crate::{
+    expand_tilde, git_exe, is_windows, normalize_path_input, path_to_string, run_capture_input,
+    shell_escape_posix_single, ssh_exe, step_error, validate_run_as, ActionError, RemoteAuthPlan,
+    RemoteRepo, RunAsConfig, SshConfig, StepResult, Transport,
+};
+
+// undo で触ってよい key（apply_fix が書くものだけ）
+const FIX_KEYS: &[&str] = &[
+    "user.name",
+    "user.email",
+    "safe.directory",
+    "credential.helper",
+    "core.sshCommand",
+];
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FixTarget {
+    // local (default) | ssh
+    #[serde(default)]
+    target: Option<String>,
+    // scope=repo の対象（local: ローカルパス / ssh: remote_path）。safe.directory の値にも使う
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    git_path: Option<String>,
+    #[serde(default)]
+    ssh_path: Option<String>,
+    #[serde(default)]
+    ssh: Option<SshConfig>,
+    #[serde(default)]
+    run_as: Option<RunAsConfig>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ApplyFixRequest {
+    // identity | safe_directory | credential_helper | known_hosts | ssh_command
+    fix: String,
+    #[serde(flatten)]
+    on: FixTarget,
+    // global (default) | repo。safe.directory は常に global
+    #[serde(default)]
+    scope: Option<String>,
+    #[serde(default)]
+    user_name: Option<String>,
+    #[serde(default)]
+    user_email: Option<String>,
+    // 省略時は OS ごとの既定（diagnostics と同じ）
+    #[serde(default)]
+    helper: Option<String>,
+    // known_hosts
+    #[serde(default)]
+    host: Option<String>,
+    #[serde(default)]
+    port: Option<u16>,
+    // SHA256:... が一致した鍵だけ登録する
+    #[serde(default)]
+    expected_fingerprint: Option<String>,
+    // ssh_command: そのまま使う。無ければ key_path から組み立てる
+    #[serde(default)]
+    ssh_command: Option<String>,
+    #[serde(default)]
+    key_path: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UndoEntry {
+    // config | known_hosts
+    kind: String,
+    // config: global | repo
+    #[serde(default)]
+    scope: Option<String>,
+    #[serde(default)]
+    key: Option<String>,
+    // 変更前の値（空 = 未設定だった）
+    #[serde(default)]
+    previous: Vec<String>,
+    // --add した値（safe.directory）。undo ではこの値だけ消す
+    #[serde(default)]
+    added: Option<String>,
+    // known_hosts: ssh-keygen -R に渡す名前（host / [host]:port）
+    #[serde(default)]
+    host: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UndoFixRequest {
+    #[serde(flatten)]
+    on: FixTarget,
+    entries: Vec<UndoEntry>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ApplyFixResult {
+    ok: bool,
+    fix: String,
+    // local | ssh
+    target: String,
+    // false = すでに望む状態だった（何も書いていない）
+    changed: bool,
+    steps: Vec<StepResult>,
+    // undo_fix にそのまま渡す
+    undo: Vec<UndoEntry>,
+    error: Option<ActionError>,
+}
+
+fn err(code: &str, severity: &str, message: &str, detail: Option<String>) -> ActionError {
+    ActionError {
+        code: code.into(),
+        severity: severity.into(),
+        message: message.into(),
+        detail,
+    }
+}
+
+fn non_empty(s: Option<&String>) -> Option<String> {
+    s.map(|x| x.trim().to_string()).filter(|x| !x.is_empty())
+}
+
+// git config の value-pattern（ERE）として値そのものに一致させる
+fn regex_exact(s: &str) -> String {
+    let mut out = String::from("^");
+    for c in s.chars() {
+        if "\\^$.|?*+()[]{}".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('$');
+    out
+}
+
+// 22 以外は [host]:port で known_hosts に載る
+fn known_hosts_name(host: &str, port: u16) -> String {
+    if port == 22 {
+        host.to_string()
+    } else {
+        format!("[{}]:{}", host, port)
+    }
+}
+
+enum Runner<'a> {
+    Local {
+        git: PathBuf,
+        ssh: Option<PathBuf>,
+        repo: Option<String>,
+    },
+    Remote(RemoteRepo<'a>),
+}
+
+impl Runner<'_> {
+    fn is_remote(&self) -> bool {
+        matches!(self, Runner::Remote(_))
+    }
+
+    // name: git / ssh-keygen / ssh-keyscan
+    fn tool(&self, name: &str, args: &[String], input: Option<&str>) -> StepResult {
+        match self {
+            Runner::Local { git, ssh, .. } => {
+                let exe = if name == "git" {
+                    Some(git.clone())
+                } else {
+                    ssh_tool_exe(ssh.as_deref(), name)
+                };
+                let Some(exe) = exe else {
+                    return step_error(
+                        format!("{} {}", name, args.join(" ")),
+                        format!("{} not found", name),
+                    );
+                };
+                let refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+                run_capture_input(&exe, &refs, None, input)
+            }
+            Runner::Remote(repo) => {
+                let body = std::iter::once(name.to_string())
+                    .chain(args.iter().map(|a| shell_escape_posix_single(a)))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                repo.exec(&repo.command(&body), false, input)
+            }
+        }
+    }
+
+    fn config(&self, scope: &str, args: &[&str]) -> StepResult {
+        let mut full: Vec<String> = Vec::new();
+        if scope == "repo" {
+            if let Runner::Local { repo: Some(p), .. } = self {
+                full.extend(["-C".into(), p.clone()]);
+            }
+        }
+        let flag = if scope == "repo" {
+            "--local"
+        } else {
+            "--global"
+        };
+        full.extend(["config".into(), flag.into()]);
+        full.extend(args.iter().map(|s| s.to_string()));
+        self.tool("git", &full, None)
+    }
+}
+
+// target / scope から Runner を作る（remote は scope=repo なら path に cd、それ以外は / で実行）
+fn runner_for<'a>(
+    on: &'a FixTarget,
+    scope: &str,
+    auth: &'a RemoteAuthPlan,
+) -> Result<Runner<'a>, ActionError> {
+    let path = non_empty(on.path.as_ref());
+    if scope == "repo" && path.is_none() {
+        return Err(err(
+            "FIX-0003",
+            "ERROR",
+            "path is required for scope=repo",
+            None,
+        ));
+    }
+
+    match on.target.as_deref().map(str::trim).unwrap_or("local") {
+        "" | "local" => {
+            let git = git_exe(on.git_path.clone()).ok_or_else(|| {
+                err(
+                    "GIT-0001",
+                    "FATAL",
+                    "git not found. Run preflight and set gitPath if needed.",
+                    None,
+                )
+            })?;
+            Ok(Runner::Local {
+                git,
+                ssh: ssh_exe(on.ssh_path.clone()),
+                repo: path.map(|p| normalize_path_input(&p)),
+            })
+        }
+        "ssh" => {
+            let exe = ssh_exe(on.ssh_path.clone())
+                .ok_or_else(|| err("SSH-0001", "FATAL", "ssh not found", None))?;
+            let cfg = on
+                .ssh
+                .clone()
+                .filter(|c| !c.host.trim().is_empty() && !c.user.trim().is_empty())
+                .ok_or_else(|| err("CFG-0302", "ERROR", "ssh host/user is required", None))?;
+            validate_run_as(on.run_as.as_ref(), auth)?;
+            let cwd = match (scope, on.path.as_deref().map(str::trim)) {
+                ("repo", Some(p)) => p,
+                _ => "/",
+            };
+            Ok(Runner::Remote(RemoteRepo {
+                transport: Transport::Ssh { exe, cfg },
+                path: cwd,
+                run_as: on.run_as.as_ref(),
+                wrapper: None,
+                auth,
+            }))
+        }
+        other => Err(err(
+            "FIX-0001",
+            "ERROR",
+            "unknown target (expected local|ssh)",
+            Some(other.to_string()),
+        )),
+    }
+}
+
+// --get-all の終了コード 1 は「未設定」なので失敗扱いにしない
+fn read_config(
+    runner: &Runner,
+    scope: &str,
+    key: &str,
+    steps: &mut Vec<StepResult>,
+) -> Result<Vec<String>, ()> {
+    let mut step = runner.config(scope, &["--get-all", key]);
+    if step.exit_code == 1 {
+        step.ok = true;
+    }
+    let ok = step.ok;
+    let values = step
+        .stdout
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect();
+    steps.push(step);
+    if ok {
+        Ok(values)
+    } else {
+        Err(())
+    }
+}
+
+// 単一値の key を value にする。すでに同じなら何もしない
+fn set_config(
+    runner: &Runner,
+    scope: &str,
+    key: &str,
+    value: &str,
+    steps: &mut Vec<StepResult>,
+    undo: &mut Vec<UndoEntry>,
+) -> Result<(), ActionError> {
+    let failed = |steps: &[StepResult]| {
+        err(
+            "FIX-0100",
+            "ERROR",
+            &format!("git config {} failed", key),
+            steps.last().map(|s| s.stderr.trim().to_string()),
+        )
+    };
+    let previous = read_config(runner, scope, key, steps).map_err(|_| failed(steps))?;
+    if previous.len() == 1 && previous[0] == value {
+        return Ok(());
+    }
+    let step = runner.config(scope, &["--replace-all", key, value]);
+    let ok = step.ok;
+    steps.push(step);
+    if !ok {
+        return Err(failed(steps));
+    }
+    undo.push(UndoEntry {
+        kind: "config".into(),
+        scope: Some(scope.into()),
+        key: Some(key.into()),
+        previous,
+        added: None,
+        host: None,
+    });
+    Ok(())
+}
+
+// 複数値の key（safe.directory）に value を足す。すでにあれば何もしない
+fn add_config(
+    runner: &Runner,
+    key: &str,
+    value: &str,
+    steps: &mut Vec<StepResult>,
+    undo: &mut Vec<UndoEntry>,
+) -> Result<(), ActionError> {
+    let failed = |steps: &[StepResult]| {
+        err(
+            "FIX-0100",
+            "ERROR",
+            &format!("git config {} failed", key),
+            steps.last().map(|s| s.stderr.trim().to_string()),
+        )
+    };
+    let previous = read_config(runner, "global", key, steps).map_err(|_| failed(steps))?;
+    if previous.iter().any(|p| p == value || p == "*") {
+        return Ok(());
+    }
+    let step = runner.config("global", &["--add", key, value]);
+    let ok = step.ok;
+    steps.push(step);
+    if !ok {
+        return Err(failed(steps));
+    }
+    undo.push(UndoEntry {
+        kind: "config".into(),
+        scope: Some("global".into()),
+        key: Some(key.into()),
+        previous,
+        added: Some(value.into()),
+        host: None,
+    });
+    Ok(())
+}
+
+// ssh-keyscan で取った鍵を known_hosts に足す（既に載っていれば何もしない）
+fn add_known_host(
+    runner: &Runner,
+    req: &ApplyFixRequest,
+    steps: &mut Vec<StepResult>,
+    undo: &mut Vec<UndoEntry>,
+) -> Result<(), ActionError> {
+    let host = non_empty(req.host.as_ref())
+        .or_else(|| {
+            req.on
+                .ssh
+                .as_ref()
+                .filter(|_| !runner.is_remote())
+                .map(|c| c.host.trim().to_string())
+                .filter(|h| !h.is_empty())
+        })
+        .ok_or_else(|| err("FIX-0002", "ERROR", "host is required", None))?;
+    let port = req.port.unwrap_or(22);
+    let name = known_hosts_name(&host, port);
+
+    let mut found = runner.tool("ssh-keygen", &["-F".into(), name.clone()], None);
+    // 1 = 載っていない / known_hosts 自体が無いと 255 で "No such file" を出す
+    let already = found.exit_code == 0;
+    if found.exit_code == 1 || found.stderr.contains("No such file") {
+        found.ok = true;
+    }
+    let found_ok = found.ok;
+    steps.push(found);
+    if !found_ok {
+        return Err(err(
+            "FIX-0100",
+            "ERROR",
+            "ssh-keygen -F failed",
+            steps.last().map(|s| s.stderr.trim().to_string()),
+        ));
+    }
+    if already {
+        return Ok(());
+    }
+
+    let scan = runner.tool(
+        "ssh-keyscan",
+        &[
+            "-T".into(),
+            "5".into(),
+            "-p".into(),
+            port.to_string(),
+            host.clone(),
+        ],
+        None,
+    );
+    let keys: Vec<String> = scan
+        .stdout
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(str::to_string)
+        .collect();
+    let scan_stderr = scan.stderr.trim().to_string();
+    steps.push(scan);
+    if keys.is_empty() {
+        return Err(err(
+            "FIX-0101",
+            "ERROR",
+            "ssh-keyscan returned no host keys",
+            Some(scan_stderr),
+        ));
+    }
+
+    let keys = match non_empty(req.expected_fingerprint.as_ref()) {
+        None => keys,
+        Some(expected) => {
+            let input = format!("{}\n", keys.join("\n"));
+            let fp = runner.tool("ssh-keygen", &["-lf".into(), "-".into()], Some(&input));
+            let fp_ok = fp.ok;
+            let prints: Vec<String> = fp
+                .stdout
+                .lines()
+                .map(|l| l.split_whitespace().nth(1).unwrap_or("").to_string())
+                .collect();
+            steps.push(fp);
+            if !fp_ok || prints.len() != keys.len() {
+                return Err(err(
+                    "FIX-0101",
+                    "ERROR",
+                    "could not compute host key fingerprints",
+                    steps.last().map(|s| s.stderr.trim().to_string()),
+                ));
+            }
+            let matched: Vec<String> = keys
+                .into_iter()
+                .zip(prints.iter())
+                .filter(|(_, p)| **p == expected)
+                .map(|(k, _)| k)
+                .collect();
+            if matched.is_empty() {
+                return Err(err(
+                    "FIX-0101",
+                    "ERROR",
+                    "host key fingerprint does not match expectedFingerprint",
+                    Some(prints.join(", ")),
+                ));
+            }
+            matched
+        }
+    };
+
+    let input = format!("{}\n", keys.join("\n"));
+    let append = match runner {
+        Runner::Local { .. } => append_local_known_hosts(&input),
+        Runner::Remote(repo) => repo.exec(
+            &repo.command(
+                "umask 077 && mkdir -p \"$HOME/.ssh\" && cat >> \"$HOME/.ssh/known_hosts\"",
+            ),
+            false,
+            Some(&input),
+        ),
+    };
+    let append_ok = append.ok;
+    steps.push(append);
+    if !append_ok {
+        return Err(err(
+            "FIX-0100",
+            "ERROR",
+            "failed to update known_hosts",
+            steps.last().map(|s| s.stderr.trim().to_string()),
+        ));
+    }
+
+    undo.push(UndoEntry {
+        kind: "known_hosts".into(),
+        scope: None,
+        key: None,
+        previous: Vec::new(),
+        added: None,
+        host: Some(name),
+    });
+    Ok(())
+}
+
+// ローカルはシェルを使わず直接追記する（Windows でも同じ動き）
+fn append_local_known_hosts(input: &str) -> StepResult {
+    let path = PathBuf::from(expand_tilde("~/.ssh/known_hosts"));
+    let cmd = format!(
+        "append {} line(s) to {}",
+        input.lines().count(),
+        path_to_string(&path)
+    );
+    let res = path
+        .parent()
+        .map(fs::create_dir_all)
+        .unwrap_or(Ok(()))
+        .and_then(|_| fs::OpenOptions::new().create(true).append(true).open(&path))
+        .and_then(|mut f| f.write_all(input.as_bytes()));
+    match res {
+        Ok(()) => StepResult {
+            cmd,
+            cwd: None,
+            ok: true,
+            exit_code: 0,
+            stdout: "".into(),
+            stderr: "".into(),
+        },
+        Err(e) => step_error(cmd, e.to_string()),
+    }
+}
+
+fn apply(
+    req: &ApplyFixRequest,
+    runner: &Runner,
+    scope: &str,
+    steps: &mut Vec<StepResult>,
+    undo: &mut Vec<UndoEntry>,
+) -> Result<(), ActionError> {
+    match req.fix.trim() {
+        "identity" => {
+            let name = non_empty(req.user_name.as_ref());
+            let email = non_empty(req.user_email.as_ref());
+            if name.is_none() && email.is_none() {
+                return Err(err(
+                    "FIX-0002",
+                    "ERROR",
+                    "userName or userEmail is required",
+                    None,
+                ));
+            }
+            if let Some(n) = name {
+                set_config(runner, scope, "user.name", &n, steps, undo)?;
+            }
+            if let Some(e) = email {
+                set_config(runner, scope, "user.email", &e, steps, undo)?;
+            }
+            Ok(())
+        }
+        "safe_directory" => {
+            let path = non_empty(req.on.path.as_ref())
+                .ok_or_else(|| err("FIX-0002", "ERROR", "path is required", None))?;
+            let value = if runner.is_remote() {
+                path
+            } else {
+                let p = normalize_path_input(&path);
+                // Git for Windows は / 区切りで比較する
+                if is_windows() {
+                    p.replace('\\', "/")
+                } else {
+                    p
+                }
+            };
+            add_config(runner, "safe.directory", &value, steps, undo)
+        }
+        "credential_helper" => {
+            let helper = non_empty(req.helper.as_ref())
+                .unwrap_or_else(|| default_credential_helper(runner.is_remote()).to_string());
+            set_config(runner, scope, "credential.helper", &helper, steps, undo)
+        }
+        "ssh_command" => {
+            let cmd = match (
+                non_empty(req.ssh_command.as_ref()),
+                non_empty(req.key_path.as_ref()),
+            ) {
+                (Some(c), _) => c,
+                (None, Some(k)) => {
+                    // core.sshCommand はシェル経由で実行されるので鍵パスはクォートする
+                    let key = if runner.is_remote() {
+                        k
+                    } else {
+                        normalize_path_input(&k).replace('\\', "/")
+                    };
+                    format!(
+                        "ssh -i {} -o IdentitiesOnly=yes",
+                        shell_escape_posix_single(&key)
+                    )
+                }
+                (None, None) => {
+                    return Err(err(
+                        "FIX-0002",
+                        "ERROR",
+                        "sshCommand or keyPath is required",
+                        None,
+                    ))
+                }
+            };
+            set_config(runner, scope, "core.sshCommand", &cmd, steps, undo)
+        }
+        "known_hosts" => add_known_host(runner, req, steps, undo),
+        other => Err(err(
+            "FIX-0001",
+            "ERROR",
+            "unknown fix (expected identity|safe_directory|credential_helper|known_hosts|ssh_command)",
+            Some(other.to_string()),
+        )),
+    }
+}
+
+fn target_label(on: &FixTarget) -> String {
+    match on.target.as_deref().map(str::trim) {
+        Some("ssh") => "ssh".into(),
+        _ => "local".into(),
+    }
+}
+
+// safe.directory / known_hosts はユーザー単位にしか置けないので scope に関係なく global
+fn fix_scope(req: &ApplyFixRequest) -> Result<&'static str, ActionError> {
+    match (
+        req.fix.trim(),
+        req.scope.as_deref().map(str::trim).unwrap_or("global"),
+    ) {
+        ("safe_directory" | "known_hosts", _) => Ok("global"),
+        (_, "" | "global") => Ok("global"),
+        (_, "repo") => Ok("repo"),
+        (_, other) => Err(err(
+            "FIX-0001",
+            "ERROR",
+            "unknown scope (expected global|repo)",
+            Some(other.to_string()),
+        )),
+    }
+}
+
+/// 診断の指摘を 1 つ直す（ローカル / ssh）。config の変更は undo に元の値を返す
+#[tauri::command(async, rename_all = "camelCase")]
+pub(crate) fn apply_fix(req: ApplyFixRequest) -> ApplyFixResult {
+    let mut steps = Vec::new();
+    let mut undo = Vec::new();
+
+    let scope = fix_scope(&req);
+
+    // apply_fix は origin と通信しないので認証は不要
+    let auth = RemoteAuthPlan::default();
+    let res = scope.and_then(|scope| {
+        let runner = runner_for(&req.on, scope, &auth)?;
+        apply(&req, &runner, scope, &mut steps, &mut undo)
+    });
+
+    ApplyFixResult {
+        ok: res.is_ok(),
+        fix: req.fix.trim().to_string(),
+        target: target_label(&req.on),
+        changed: !undo.is_empty(),
+        steps,
+        undo,
+        error: res.err(),
+    }
+}
+
+fn undo_one(
+    runner: &Runner,
+    e: &UndoEntry,
+    steps: &mut Vec<StepResult>,
+) -> Result<(), ActionError> {
+    let failed = |steps: &[StepResult], what: &str| {
+        err(
+            "FIX-0100",
+            "ERROR",
+            &format!("undo failed: {}", what),
+            steps.last().map(|s| s.stderr.trim().to_string()),
+        )
+    };
+    let push = |steps: &mut Vec<StepResult>, mut step: StepResult, ok_codes: &[i32]| {
+        if ok_codes.contains(&step.exit_code) {
+            step.ok = true;
+        }
+        let ok = step.ok;
+        steps.push(step);
+        ok
+    };
+
+    match e.kind.as_str() {
+        "known_hosts" => {
+            let host = non_empty(e.host.as_ref())
+                .ok_or_else(|| err("FIX-0002", "ERROR", "undo entry has no host", None))?;
+            let step = runner.tool("ssh-keygen", &["-R".into(), host.clone()], None);
+            if !push(steps, step, &[]) {
+                return Err(failed(steps, &host));
+            }
+            Ok(())
+        }
+        "config" => {
+            let key = non_empty(e.key.as_ref()).unwrap_or_default();
+            if !FIX_KEYS.contains(&key.as_str()) {
+                return Err(err(
+                    "FIX-0001",
+                    "ERROR",
+                    "undo entry key is not managed by apply_fix",
+                    Some(key),
+                ));
+            }
+            let scope = match e.scope.as_deref() {
+                Some("repo") => "repo",
+                _ => "global",
+            };
+            if let Some(added) = &e.added {
+                // 5 = 既に消えている
+                let step = runner.config(scope, &["--unset", &key, &regex_exact(added)]);
+                if !push(steps, step, &[5]) {
+                    return Err(failed(steps, &key));
+                }
+                return Ok(());
+            }
+            match e.previous.split_first() {
+                None => {
+                    let step = runner.config(scope, &["--unset-all", &key]);
+                    if !push(steps, step, &[5]) {
+                        return Err(failed(steps, &key));
+                    }
+                }
+                Some((first, rest)) => {
+                    let step = runner.config(scope, &["--replace-all", &key, first]);
+                    if !push(steps, step, &[]) {
+                        return Err(failed(steps, &key));
+                    }
+                    for v in rest {
+                        let step = runner.config(scope, &["--add", &key, v]);
+                        if !push(steps, step, &[]) {
+                            return Err(failed(steps, &key));
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+        other => Err(err(
+            "FIX-0001",
+            "ERROR",
+            "unknown undo kind (expected config|known_hosts)",
+            Some(other.to_string()),
+        )),
+    }
+}
+
+/// apply_fix が返した undo を逆順に戻す
+#[tauri::command(async, rename_all = "camelCase")]
+pub(crate) fn undo_fix(req: UndoFixRequest) -> ApplyFixResult {
+    let mut steps = Vec::new();
+    let auth = RemoteAuthPlan::default();
+    let scope = if req
+        .entries
+        .iter()
+        .any(|e| e.scope.as_deref() == Some("repo"))
+    {
+        "repo"
+    } else {
+        "global"
+    };
+
+    let res = runner_for(&req.on, scope, &auth).and_then(|runner| {
+        req.entries
+            .iter()
+            .rev()
+            .try_for_each(|e| undo_one(&runner, e, &mut steps))
+    });
+
+    ApplyFixResult {
+        ok: res.is_ok(),
+        fix: "undo".into(),
+        target: target_label(&req.on),
+        changed: steps.iter().any(|s| s.ok),
+        steps,
+        undo: Vec::new(),
+        error: res.err(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fix_request(v: serde_json::Value) -> ApplyFixRequest {
+        serde_json::from_value(v).unwrap()
+    }
+
+    fn temp_repo(name: &str) -> (PathBuf, Runner<'static>) {
+        let dir = std::env::temp_dir().join(format!("gitshlc-{}-{}", name, std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let git = PathBuf::from("git");
+        let init = run_capture_input(&git, &["init", "-q", &path_to_string(&dir)], None, None);
+        assert!(init.ok, "{}", init.stderr);
+        let runner = Runner::Local {
+            git,
+            ssh: None,
+            repo: Some(path_to_string(&dir)),
+        };
+        (dir, runner)
+    }
+
+    fn local_values(runner: &Runner, key: &str) -> Vec<String> {
+        let mut steps = Vec::new();
+        read_config(runner, "repo", key, &mut steps).unwrap()
+    }
+
+    #[test]
+    fn regex_exact_escapes_ere_metacharacters() {
+        assert_eq!(regex_exact("/srv/app"), "^/srv/app$");
+        assert_eq!(
+            regex_exact(r"C:/a.b (1)/[x]+$y|z\\"),
+            r"^C:/a\.b \(1\)/\[x\]\+\$y\|z\\\\$"
+        );
+        assert_eq!(regex_exact("*"), r"^\*$");
+    }
+
+    #[test]
+    fn known_hosts_name_brackets_non_default_ports() {
+        assert_eq!(known_hosts_name("github.com", 22), "github.com");
+        assert_eq!(known_hosts_name("10.0.0.5", 2222), "[10.0.0.5]:2222");
+    }
+
+    #[test]
+    fn scope_is_forced_global_for_user_level_fixes() {
+        let scope = |fix: &str, scope: Option<&str>| {
+            fix_scope(&fix_request(
+                serde_json::json!({ "fix": fix, "scope": scope }),
+            ))
+        };
+        assert_eq!(scope("identity", None).unwrap(), "global");
+        assert_eq!(scope("identity", Some(" repo ")).unwrap(), "repo");
+        assert_eq!(scope("credential_helper", Some("")).unwrap(), "global");
+        assert_eq!(scope("safe_directory", Some("repo")).unwrap(), "global");
+        assert_eq!(scope("known_hosts", Some("system")).unwrap(), "global");
+        assert_eq!(
+            scope("identity", Some("system")).unwrap_err().code,
+            "FIX-0001"
+        );
+
+        // scope=repo は path が無ければ何もしない
+        let on = FixTarget::default();
+        let auth = RemoteAuthPlan::default();
+        let code = runner_for(&on, "repo", &auth).err().unwrap().code;
+        assert_eq!(code, "FIX-0003");
+    }
+
+    #[test]
+    fn apply_and_undo_identity_in_repo_scope() {
+        let (dir, runner) = temp_repo("fix-identity");
+        let path = path_to_string(&dir);
+        assert!(runner.config("repo", &["user.name", "Old Name"]).ok);
+
+        let req = fix_request(serde_json::json!({
+            "fix": "identity",
+            "target": "local",
+            "scope": "repo",
+            "path": path,
+            "userName": "New Name",
+            "userEmail": "new@example.com",
+        }));
+        let res = apply_fix(req.clone());
+        assert!(res.ok, "{:?}", res.error);
+        assert!(res.changed);
+        assert_eq!(local_values(&runner, "user.name"), ["New Name"]);
+        assert_eq!(res.undo.len(), 2);
+        assert_eq!(res.undo[0].previous, ["Old Name"]);
+        assert!(res.undo[1].previous.is_empty());
+
+        // 2 回目は何も書かない
+        let again = apply_fix(req);
+        assert!(again.ok && !again.changed && again.undo.is_empty());
+
+        let undone = undo_fix(UndoFixRequest {
+            on: serde_json::from_value(serde_json::json!({ "target": "local", "path": path }))
+                .unwrap(),
+            entries: res.undo,
+        });
+        assert!(undone.ok, "{:?}", undone.error);
+        assert_eq!(local_values(&runner, "user.name"), ["Old Name"]);
+        // 未設定だった値は unset に戻る
+        assert!(local_values(&runner, "user.email").is_empty());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn undo_removes_only_the_added_value_and_restores_lists() {
+        let (dir, runner) = temp_repo("fix-undo");
+        let entry = |previous: &[&str], added: Option<&str>| UndoEntry {
+            kind: "config".into(),
+            scope: Some("repo".into()),
+            key: Some("safe.directory".into()),
+            previous: previous.iter().map(|s| s.to_string()).collect(),
+            added: added.map(str::to_string),
+            host: None,
+        };
+        for v in ["/srv/a.b", "/srv/aXb"] {
+            assert!(runner.config("repo", &["--add", "safe.directory", v]).ok);
+        }
+
+        // "." が任意の 1 文字として扱われると両方に当たってしまう
+        let mut steps = Vec::new();
+        undo_one(&runner, &entry(&[], Some("/srv/a.b")), &mut steps).unwrap();
+        assert_eq!(local_values(&runner, "safe.directory"), ["/srv/aXb"]);
+        // 既に消えていても失敗にしない
+        undo_one(&runner, &entry(&[], Some("/srv/a.b")), &mut steps).unwrap();
+        assert!(steps.iter().all(|s| s.ok));
+
+        undo_one(&runner, &entry(&["/one", "/two"], None), &mut steps).unwrap();
+        assert_eq!(local_values(&runner, "safe.directory"), ["/one", "/two"]);
+        undo_one(&runner, &entry(&[], None), &mut steps).unwrap();
+        assert!(local_values(&runner, "safe.directory").is_empty());
+
+        let mut foreign = entry(&[], None);
+        foreign.key = Some("core.hooksPath".into());
+        let code = undo_one(&runner, &foreign, &mut steps).unwrap_err().code;
+        assert_eq!(code, "FIX-0001");
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,181 @@
+// `git --version` を semver に直して、使える機能（capability）を判定する
+use std::{fmt, path::Path};
+
+use crate::{run_capture, ActionError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct GitVersion {
+    major: u32,
+    minor: u32,
+    patch: u32,
+}
+
+impl fmt::Display for GitVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+const fn v(major: u32, minor: u32, patch: u32) -> GitVersion {
+    GitVersion {
+        major,
+        minor,
+        patch,
+    }
+}
+
+// run_action が前提にしている最低バージョン（git -C）
+pub(crate) const MIN_ACTION_VERSION: GitVersion = v(1, 8, 5);
+
+pub(crate) const CAP_SWITCH: &str = "switch";
+pub(crate) const CAP_MERGE_TREE_WRITE_TREE: &str = "merge-tree --write-tree";
+pub(crate) const CAP_FORCE_IF_INCLUDES: &str = "push --force-if-includes";
+pub(crate) const CAP_SAFE_DIRECTORY: &str = "safe.directory";
+pub(crate) const CAP_INIT_BRANCH: &str = "init --initial-branch";
+pub(crate) const CAP_LS_REMOTE_SYMREF: &str = "ls-remote --symref";
+pub(crate) const CAP_NO_OPTIONAL_LOCKS: &str = "--no-optional-locks";
+
+const CAPABILITIES: &[(&str, GitVersion)] = &[
+    (CAP_NO_OPTIONAL_LOCKS, v(2, 15, 0)),
+    (CAP_LS_REMOTE_SYMREF, v(2, 8, 0)),
+    (CAP_SWITCH, v(2, 23, 0)),
+    (CAP_INIT_BRANCH, v(2, 28, 0)),
+    (CAP_FORCE_IF_INCLUDES, v(2, 30, 0)),
+    (CAP_SAFE_DIRECTORY, v(2, 35, 2)),
+    (CAP_MERGE_TREE_WRITE_TREE, v(2, 38, 0)),
+];
+
+/// "git version 2.39.2 (Apple Git-143)" / "git version 2.45.1.windows.1" など
+pub(crate) fn parse_version(raw: &str) -> Option<GitVersion> {
+    let s = raw.trim();
+    let s = s.strip_prefix("git version").unwrap_or(s).trim();
+    let token = s.split_whitespace().next()?;
+    let mut nums = token.split('.').map(|p| {
+        let digits: String = p.chars().take_while(|c| c.is_ascii_digit()).collect();
+        digits.parse::<u32>().ok()
+    });
+    let major = nums.next()??;
+    let minor = nums.next().flatten().unwrap_or(0);
+    let patch = nums.next().flatten().unwrap_or(0);
+    Some(v(major, minor, patch))
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Capability {
+    name: String,
+    min_version: String,
+    supported: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GitCapabilities {
+    raw: String,
+    // major.minor.patch（読めなければ None）
+    version: Option<String>,
+    capabilities: Vec<Capability>,
+    #[serde(skip)]
+    parsed: Option<GitVersion>,
+}
+
+impl GitCapabilities {
+    pub(crate) fn from_version_output(raw: &str) -> Self {
+        let parsed = parse_version(raw);
+        GitCapabilities {
+            raw: raw.trim().to_string(),
+            version: parsed.map(|p| p.to_string()),
+            capabilities: CAPABILITIES
+                .iter()
+                .map(|(name, min)| Capability {
+                    name: name.to_string(),
+                    min_version: min.to_string(),
+                    supported: parsed.map(|p| p >= *min).unwrap_or(false),
+                })
+                .collect(),
+            parsed,
+        }
+    }
+
+    pub(crate) fn supports(&self, name: &str) -> bool {
+        self.capabilities
+            .iter()
+            .any(|c| c.name == name && c.supported)
+    }
+
+    /// 最低バージョン未満なら GIT-0003。バージョンが読めないときは通す
+    pub(crate) fn require(&self, min: GitVersion, what: &str) -> Result<(), ActionError> {
+        match self.parsed {
+            Some(p) if p < min => Err(ActionError {
+                code: "GIT-0003".into(),
+                severity: "FATAL".into(),
+                message: format!("git too old for {} (need >= {})", what, min),
+                detail: Some(self.raw.clone()),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// ブランチ切り替え: switch が使えれば switch、無ければ checkout
+    pub(crate) fn switch_subcommand(&self) -> &'static str {
+        if self.supports(CAP_SWITCH) {
+            "switch"
+        } else {
+            "checkout"
+        }
+    }
+}
+
+pub(crate) fn local_capabilities(git: &Path) -> Option<GitCapabilities> {
+    let step = run_capture(git, &["--version"], None);
+    if step.ok {
+        Some(GitCapabilities::from_version_output(&step.stdout))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_version_handles_vendor_suffixes() {
+        assert_eq!(
+            parse_version("git version 2.39.2 (Apple Git-143)"),
+            Some(v(2, 39, 2))
+        );
+        assert_eq!(
+            parse_version("git version 2.45.1.windows.1\n"),
+            Some(v(2, 45, 1))
+        );
+        assert_eq!(parse_version("git version 2.43.0-rc1"), Some(v(2, 43, 0)));
+        assert_eq!(parse_version("2.7"), Some(v(2, 7, 0)));
+        assert_eq!(parse_version("git version"), None);
+        assert_eq!(parse_version("not git"), None);
+        assert!(v(2, 35, 2) > v(2, 35, 1) && v(2, 100, 0) > v(2, 9, 9));
+    }
+
+    #[test]
+    fn capabilities_follow_parsed_version() {
+        let caps = GitCapabilities::from_version_output("git version 2.25.1");
+        assert!(caps.supports(CAP_SWITCH));
+        assert!(!caps.supports(CAP_INIT_BRANCH));
+        assert_eq!(caps.switch_subcommand(), "switch");
+        assert!(caps.require(MIN_ACTION_VERSION, "x").is_ok());
+
+        let old = GitCapabilities::from_version_output("git version 1.8.3.1");
+        assert_eq!(old.switch_subcommand(), "checkout");
+        assert_eq!(
+            old.require(MIN_ACTION_VERSION, "run_action")
+                .unwrap_err()
+                .code,
+            "GIT-0003"
+        );
+
+        // 読めないときは何も使わないが、require は通す
+        let unknown = GitCapabilities::from_version_output("???");
+        assert!(!unknown.supports(CAP_NO_OPTIONAL_LOCKS));
+        assert!(unknown.require(MIN_ACTION_VERSION, "x").is_ok());
+    }
+}
@@ -0,0 +1,410 @@
+// .git（ディレクトリ / gitfile）と config を git を起動せずに読む
+use std::path::{Path, PathBuf};
+
+pub(crate) struct GitDirs {
+    // HEAD がある場所（worktree なら .git/worktrees/<name>）
+    pub(crate) git_dir: PathBuf,
+    // config / refs / objects がある場所（worktree なら本体の .git）
+    pub(crate) common_dir: PathBuf,
+    // normal | bare | worktree | submodule
+    pub(crate) kind: &'static str,
+}
+
+fn read_trimmed(p: &Path) -> Option<String> {
+    std::fs::read_to_string(p)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// .git を持たない git ディレクトリ（bare repo）か
+pub(crate) fn is_bare_repo_dir(dir: &Path) -> bool {
+    dir.file_name().map(|n| n != ".git").unwrap_or(false)
+        && !dir.join(".git").exists()
+        && dir.join("HEAD").is_file()
+        && dir.join("objects").is_dir()
+        && dir.join("refs").is_dir()
+}
+
+/// repo_dir/.git を解決する。gitfile（`gitdir: ...`）・commondir・bare repo にも対応
+pub(crate) fn resolve_git_dirs(repo_dir: &Path) -> Option<GitDirs> {
+    let dot_git = repo_dir.join(".git");
+    let (git_dir, kind) = if dot_git.is_dir() {
+        (dot_git, "normal")
+    } else if dot_git.is_file() {
+        let s = read_trimmed(&dot_git)?;
+        let target = s.strip_prefix("gitdir:")?.trim();
+        let p = PathBuf::from(target);
+        let p = if p.is_absolute() { p } else { repo_dir.join(p) };
+        // worktree: commondir を持つ / submodule: <super>/.git/modules/<name>
+        let parts: Vec<String> = p
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        let in_modules = parts
+            .iter()
+            .position(|c| c == ".git")
+            .map(|i| parts[i..].iter().any(|c| c == "modules"))
+            .unwrap_or(false);
+        let kind = if p.join("commondir").is_file() {
+            "worktree"
+        } else if in_modules {
+            "submodule"
+        } else {
+            "normal"
+        };
+        (p, kind)
+    } else if is_bare_repo_dir(repo_dir) {
+        (repo_dir.to_path_buf(), "bare")
+    } else {
+        return None;
+    };
+
+    let common_dir = match read_trimmed(&git_dir.join("commondir")) {
+        Some(c) => {
+            let p = PathBuf::from(c);
+            if p.is_absolute() {
+                p
+            } else {
+                git_dir.join(p)
+            }
+        }
+        None => git_dir.clone(),
+    };
+
+    Some(GitDirs {
+        git_dir,
+        common_dir,
+        kind,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ConfigEntry {
+    // 小文字化済み
+    pub(crate) section: String,
+    // 大文字小文字はそのまま（remote 名など）
+    pub(crate) subsection: Option<String>,
+    // 小文字化済み
+    pub(crate) key: String,
+    pub(crate) value: String,
+}
+
+fn unquote_value(raw: &str) -> String {
+    let mut out = String::new();
+    let mut in_quotes = false;
+    let mut chars = raw.trim().chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '\\' => match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some(x) => out.push(x),
+                None => {}
+            },
+            '#' | ';' if !in_quotes => break,
+            _ => out.push(c),
+        }
+    }
+    out.trim().to_string()
+}
+
+/// git config（INI 風）の最小パーサ。include は追わない
+pub(crate) fn parse_config(text: &str) -> Vec<ConfigEntry> {
+    let mut out = Vec::new();
+    let mut section = String::new();
+    let mut subsection: Option<String> = None;
+
+    for line in text.lines() {
+        let t = line.trim();
+        if t.is_empty() || t.starts_with('#') || t.starts_with(';') {
+            continue;
+        }
+
+        if t.starts_with('[') {
+            let Some(end) = t.find(']') else {
+                continue;
+            };
+            let head = &t[1..end];
+            match head.find('"') {
+                // [remote "origin"]
+                Some(q) => {
+                    section = head[..q].trim().to_ascii_lowercase();
+                    let sub = head[q + 1..].trim_end_matches('"');
+                    subsection = Some(sub.replace("\\\"", "\"").replace("\\\\", "\\"));
+                }
+                // [core] / 旧形式 [remote.origin]
+                None => match head.split_once('.') {
+                    Some((a, b)) => {
+                        section = a.trim().to_ascii_lowercase();
+                        subsection = Some(b.trim().to_string());
+                    }
+                    None => {
+                        section = head.trim().to_ascii_lowercase();
+                        subsection = None;
+                    }
+                },
+            }
+            continue;
+        }
+
+        if section.is_empty() {
+            continue;
+        }
+
+        let (key, value) = match t.split_once('=') {
+            Some((k, v)) => (k.trim(), unquote_value(v)),
+            // 値なしのキーは true 扱い
+            None => (t, "true".to_string()),
+        };
+        out.push(ConfigEntry {
+            section: section.clone(),
+            subsection: subsection.clone(),
+            key: key.to_ascii_lowercase(),
+            value,
+        });
+    }
+    out
+}
+
+pub(crate) fn read_config(dirs: &GitDirs) -> Option<Vec<ConfigEntry>> {
+    let text = std::fs::read_to_string(dirs.common_dir.join("config")).ok()?;
+    Some(parse_config(&text))
+}
+
+pub(crate) fn remote_url(entries: &[ConfigEntry], remote: &str) -> Option<String> {
+    entries
+        .iter()
+        .filter(|e| {
+            e.section == "remote" && e.subsection.as_deref() == Some(remote) && e.key == "url"
+        })
+        .map(|e| e.value.clone())
+        .find(|v| !v.is_empty())
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RemoteInfo {
+    pub(crate) name: String,
+    pub(crate) fetch_url: Option<String>,
+    // pushurl が無ければ url と同じ
+    pub(crate) push_url: Option<String>,
+}
+
+pub(crate) fn remotes(entries: &[ConfigEntry]) -> Vec<RemoteInfo> {
+    let mut out: Vec<RemoteInfo> = Vec::new();
+    for e in entries.iter().filter(|e| e.section == "remote") {
+        let Some(name) = e.subsection.clone() else {
+            continue;
+        };
+        let idx = match out.iter().position(|r| r.name == name) {
+            Some(i) => i,
+            None => {
+                out.push(RemoteInfo {
+                    name,
+                    fetch_url: None,
+                    push_url: None,
+                });
+                out.len() - 1
+            }
+        };
+        match e.key.as_str() {
+            "url" if out[idx].fetch_url.is_none() => out[idx].fetch_url = Some(e.value.clone()),
+            "pushurl" if out[idx].push_url.is_none() => out[idx].push_url = Some(e.value.clone()),
+            _ => {}
+        }
+    }
+    for r in out.iter_mut() {
+        if r.push_url.is_none() {
+            r.push_url = r.fetch_url.clone();
+        }
+    }
+    out.retain(|r| r.fetch_url.is_some());
+    out
+}
+
+pub(crate) fn remote_from_config_line(key: &str, url: &str) -> Option<(String, String, String)> {
+    // `git config --get-regexp` の出力: remote.<name>.url <value>
+    let rest = key.strip_prefix("remote.")?;
+    let (name, k) = rest.rsplit_once('.')?;
+    Some((
+        name.to_string(),
+        k.to_ascii_lowercase(),
+        url.trim().to_string(),
+    ))
+}
+
+pub(crate) fn remotes_from_pairs(pairs: &[(String, String, String)]) -> Vec<RemoteInfo> {
+    let entries: Vec<ConfigEntry> = pairs
+        .iter()
+        .map(|(name, key, value)| ConfigEntry {
+            section: "remote".into(),
+            subsection: Some(name.clone()),
+            key: key.clone(),
+            value: value.clone(),
+        })
+        .collect();
+    remotes(&entries)
+}
+
+/// HEAD を読んで (branch, sha) を返す。detached なら branch は None
+pub(crate) fn read_head(dirs: &GitDirs) -> (Option<String>, Option<String>) {
+    let Some(head) = read_trimmed(&dirs.git_dir.join("HEAD")) else {
+        return (None, None);
+    };
+    match head.strip_prefix("ref:") {
+        Some(r) => {
+            let r = r.trim();
+            let branch = r.strip_prefix("refs/heads/").map(|b| b.to_string());
+            (branch, resolve_ref(dirs, r))
+        }
+        None => (None, Some(head)),
+    }
+}
+
+fn resolve_ref(dirs: &GitDirs, refname: &str) -> Option<String> {
+    if let Some(sha) = read_trimmed(&dirs.common_dir.join(refname)) {
+        return Some(sha);
+    }
+    // packed-refs: "<sha> <refname>"
+    let packed = std::fs::read_to_string(dirs.common_dir.join("packed-refs")).ok()?;
+    packed
+        .lines()
+        .filter(|l| !l.starts_with('#') && !l.starts_with('^'))
+        .find_map(|l| {
+            let (sha, name) = l.split_once(' ')?;
+            if name.trim() == refname {
+                Some(sha.trim().to_string())
+            } else {
+                None
+            }
+        })
+}
+
+/// ディレクトリ以下のファイルサイズ合計（symlink はたどらない）
+pub(crate) fn dir_size(dir: &Path) -> u64 {
+    let mut total = 0u64;
+    let mut stack = vec![dir.to_path_buf()];
+    while let Some(d) = stack.pop() {
+        let Ok(rd) = std::fs::read_dir(&d) else {
+            continue;
+        };
+        for entry in rd.flatten() {
+            let Ok(ft) = entry.file_type() else {
+                continue;
+            };
+            if ft.is_dir() {
+                stack.push(entry.path());
+            } else if ft.is_file() {
+                total += entry.metadata().map(|m| m.len()).unwrap_or(0);
+            }
+        }
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_config_reads_sections_and_values() {
+        let text = r##"
+# comment
+[core]
+	bare = false
+	logAllRefUpdates
+[remote "Origin"]
+	url = "git@example.com:o/r.git" ; trailing
+	fetch = +refs/heads/*:refs/remotes/origin/*
+[branch.main]
+	remote = origin
+merge = refs/heads/main
+[Alias]
+	lg = "log --oneline \"#x\""
+"##;
+        let e = parse_config(text);
+        let got: Vec<(&str, Option<&str>, &str, &str)> = e
+            .iter()
+            .map(|e| {
+                (
+                    e.section.as_str(),
+                    e.subsection.as_deref(),
+                    e.key.as_str(),
+                    e.value.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            got,
+            [
+                ("core", None, "bare", "false"),
+                ("core", None, "logallrefupdates", "true"),
+                ("remote", Some("Origin"), "url", "git@example.com:o/r.git"),
+                (
+                    "remote",
+                    Some("Origin"),
+                    "fetch",
+                    "+refs/heads/*:refs/remotes/origin/*"
+                ),
+                ("branch", Some("main"), "remote", "origin"),
+                ("branch", Some("main"), "merge", "refs/heads/main"),
+                ("alias", None, "lg", "log --oneline \"#x\""),
+            ]
+        );
+        assert_eq!(
+            remote_url(&e, "Origin").as_deref(),
+            Some("git@example.com:o/r.git")
+        );
+        assert_eq!(remote_url(&e, "origin"), None);
+    }
+
+    #[test]
+    fn head_resolves_loose_and_packed_refs_through_worktree() {
+        let root = std::env::temp_dir().join(format!("gitshlc-gitdir-{}", std::process::id()));
+        let main = root.join("main/.git");
+        std::fs::create_dir_all(main.join("refs/heads")).unwrap();
+        std::fs::create_dir_all(main.join("worktrees/wt")).unwrap();
+        std::fs::create_dir_all(root.join("wt")).unwrap();
+        std::fs::write(main.join("HEAD"), "ref: refs/heads/main\n").unwrap();
+        std::fs::write(main.join("refs/heads/main"), "aaaa\n").unwrap();
+        std::fs::write(
+            main.join("packed-refs"),
+            "# pack-refs with: peeled\nbbbb refs/heads/feature\n^cccc\naaab refs/heads/main\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("wt/.git"),
+            format!("gitdir: {}\n", main.join("worktrees/wt").display()),
+        )
+        .unwrap();
+        std::fs::write(main.join("worktrees/wt/commondir"), "../..\n").unwrap();
+        std::fs::write(main.join("worktrees/wt/HEAD"), "ref: refs/heads/feature\n").unwrap();
+
+        let dirs = resolve_git_dirs(&root.join("main")).unwrap();
+        assert_eq!(dirs.kind, "normal");
+        // loose ref が packed-refs より優先
+        assert_eq!(
+            read_head(&dirs),
+            (Some("main".to_string()), Some("aaaa".to_string()))
+        );
+        assert_eq!(
+            resolve_ref(&dirs, "refs/heads/feature").as_deref(),
+            Some("bbbb")
+        );
+        assert_eq!(resolve_ref(&dirs, "refs/heads/none"), None);
+
+        let wt = resolve_git_dirs(&root.join("wt")).unwrap();
+        assert_eq!(wt.kind, "worktree");
+        assert_eq!(
+            read_head(&wt),
+            (Some("feature".to_string()), Some("bbbb".to_string()))
+        );
+
+        std::fs::write(main.join("HEAD"), "dddd\n").unwrap();
+        assert_eq!(read_head(&dirs), (None, Some("dddd".to_string())));
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
@@ -0,0 +1,592 @@
+// GitHub REST API（api_base_url を差し替えればローカルのモックや GHE にも向けられる）
+use std::path::PathBuf;
+
+use crate::api::{ApiClient, RateLimit};
+use crate::hosting::{self, HostingRequest};
+use crate::{ls_remote::GitAuth, step_error, ActionError, StepResult};
+
+pub(crate) const DEFAULT_API_BASE: &str = "https://api.github.com";
+
+const GITHUB_HEADERS: &[(&str, &str)] = &[
+    ("Accept", "application/vnd.github+json"),
+    ("X-GitHub-Api-Version", "2022-11-28"),
+];
+
+/// GitHub 用の ApiClient（api_base_url が無ければ api.github.com）
+pub(crate) fn client(api_base_url: Option<&str>, token: &str) -> ApiClient {
+    let base = api_base_url
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .unwrap_or(DEFAULT_API_BASE);
+    ApiClient::new(base, token).with_headers(GITHUB_HEADERS)
+}
+
+// init_local_repo の githubRepo: repo を作ってその URL を origin にする
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InitGitHubRepo {
+    token: String,
+    #[serde(default)]
+    api_base_url: Option<String>,
+    // 無ければ token のユーザーに作る
+    #[serde(default)]
+    org: Option<String>,
+    // default: ディレクトリ名
+    #[serde(default)]
+    name: Option<String>,
+    // default true
+    #[serde(default)]
+    private: Option<bool>,
+    #[serde(default)]
+    description: Option<String>,
+    // origin に使う URL: https（clone_url）| ssh（ssh_url）。default https
+    #[serde(default)]
+    protocol: Option<String>,
+    // 最初の commit を push -u する（default true）。https なら token を askpass で渡す
+    #[serde(default)]
+    push: Option<bool>,
+}
+
+impl InitGitHubRepo {
+    pub(crate) fn push(&self) -> bool {
+        self.push.unwrap_or(true)
+    }
+
+    /// 入力だけ先に確認する（git init の前に呼ぶ）
+    pub(crate) fn validate(&self) -> Result<(), ActionError> {
+        let bad = |message: &str, detail: Option<String>| ActionError {
+            code: "CFG-0401".into(),
+            severity: "ERROR".into(),
+            message: message.into(),
+            detail,
+        };
+        if self.token.trim().is_empty() {
+            return Err(bad("githubRepo.token is required", None));
+        }
+        match self.protocol.as_deref().map(str::trim) {
+            None | Some("") | Some("https") | Some("ssh") => {}
+            Some(other) => {
+                return Err(bad(
+                    "githubRepo.protocol must be https or ssh",
+                    Some(other.into()),
+                ))
+            }
+        }
+        if let Some(o) = self.org.as_deref().map(str::trim).filter(|o| !o.is_empty()) {
+            if !valid_segment(o) {
+                return Err(bad("invalid githubRepo.org", Some(o.into())));
+            }
+        }
+        if let Some(n) = self
+            .name
+            .as_deref()
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+        {
+            if !valid_segment(n) {
+                return Err(bad("invalid githubRepo.name", Some(n.into())));
+            }
+        }
+        Ok(())
+    }
+
+    fn ssh(&self) -> bool {
+        self.protocol.as_deref().map(str::trim) == Some("ssh")
+    }
+
+    /// push -u 用の認証。ssh は鍵に任せ、https は token を askpass で渡す（保存済みの credential より優先）
+    pub(crate) fn push_auth(
+        &self,
+        askpass: impl FnOnce() -> Result<PathBuf, String>,
+    ) -> Result<GitAuth, ActionError> {
+        if !self.push() || self.ssh() {
+            return Ok(GitAuth::default());
+        }
+        let path = askpass().map_err(|e| ActionError {
+            code: "FS-0103".into(),
+            severity: "ERROR".into(),
+            message: "askpass setup failed".into(),
+            detail: Some(e),
+        })?;
+        Ok(GitAuth::token(&path, "x-access-token", self.token.trim()))
+    }
+
+    /// repo を作って origin に使う URL を返す
+    pub(crate) fn create(
+        &self,
+        dir_name: &str,
+        steps: &mut Vec<StepResult>,
+    ) -> Result<String, ActionError> {
+        let name = self
+            .name
+            .as_deref()
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .unwrap_or(dir_name);
+        let org = self.org.as_deref().map(str::trim).filter(|o| !o.is_empty());
+        let target = hosting::resolve(&HostingRequest::github(
+            None,
+            self.api_base_url.clone(),
+            &self.token,
+            None,
+        ))?;
+        let cmd = format!(
+            "POST {}{}",
+            target.api_base_url,
+            match org {
+                Some(o) => format!("/orgs/{}/repos", o),
+                None => "/user/repos".into(),
+            }
+        );
+
+        let created = hosting::provider_for(&target, &self.token)
+            .create_repo(
+                org,
+                name,
+                self.private.unwrap_or(true),
+                self.description
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|d| !d.is_empty()),
+            )
+            .map_err(|e| e.message().to_string())
+            .and_then(|repo| {
+                if repo.clone_url.is_empty() {
+                    Err("unexpected response (no clone_url)".to_string())
+                } else {
+                    Ok(repo)
+                }
+            });
+        match created {
+            Ok(repo) => {
+                let url = if self.ssh() && !repo.ssh_url.is_empty() {
+                    repo.ssh_url.clone()
+                } else {
+                    repo.clone_url.clone()
+                };
+                steps.push(StepResult {
+                    cmd,
+                    cwd: None,
+                    ok: true,
+                    exit_code: 0,
+                    stdout: format!("created {}\n{}\n", repo.full_name, repo.html_url),
+                    stderr: "".into(),
+                });
+                Ok(url)
+            }
+            Err(e) => {
+                steps.push(step_error(cmd, e.clone()));
+                Err(ActionError {
+                    code: "GH-0101".into(),
+                    severity: "ERROR".into(),
+                    message: "failed to create GitHub repository".into(),
+                    detail: Some(e),
+                })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GitHubRepoWire {
+    id: u64,
+    full_name: String,
+    description: Option<String>,
+    html_url: String,
+    clone_url: String,
+    ssh_url: String,
+    default_branch: String,
+    updated_at: String,
+    stargazers_count: u64,
+    is_private: bool,
+    archived: bool,
+}
+
+impl GitHubRepoWire {
+    fn from_json(v: &serde_json::Value) -> Option<Self> {
+        let s = |k: &str| v.get(k).and_then(|x| x.as_str()).unwrap_or("").to_string();
+        Some(GitHubRepoWire {
+            id: v.get("id")?.as_u64()?,
+            full_name: v.get("full_name")?.as_str()?.to_string(),
+            description: v
+                .get("description")
+                .and_then(|x| x.as_str())
+                .map(String::from),
+            html_url: s("html_url"),
+            clone_url: s("clone_url"),
+            ssh_url: s("ssh_url"),
+            default_branch: s("default_branch"),
+            updated_at: s("updated_at"),
+            stargazers_count: v
+                .get("stargazers_count")
+                .and_then(|x| x.as_u64())
+                .unwrap_or(0),
+            is_private: v.get("private").and_then(|x| x.as_bool()).unwrap_or(false),
+            archived: v.get("archived").and_then(|x| x.as_bool()).unwrap_or(false),
+        })
+    }
+
+    // 空白区切りの語が全部 full_name / description に含まれるか（大文字小文字は無視）
+    fn matches(&self, query: &str) -> bool {
+        let hay = format!(
+            "{} {}",
+            self.full_name,
+            self.description.as_deref().unwrap_or("")
+        )
+        .to_lowercase();
+        query
+            .split_whitespace()
+            .all(|w| hay.contains(&w.to_lowercase()))
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GitHubRepoList {
+    ok: bool,
+    repos: Vec<GitHubRepoWire>,
+    // maxPages で打ち切った
+    truncated: bool,
+    rate_limit: Option<RateLimit>,
+    error: Option<ActionError>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GitHubBranch {
+    name: String,
+    sha: String,
+    protected: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GitHubBranchList {
+    ok: bool,
+    branches: Vec<GitHubBranch>,
+    default_branch: Option<String>,
+    truncated: bool,
+    rate_limit: Option<RateLimit>,
+    error: Option<ActionError>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GitHubDefaultBranch {
+    ok: bool,
+    default_branch: Option<String>,
+    rate_limit: Option<RateLimit>,
+    error: Option<ActionError>,
+}
+
+fn cfg_err(message: &str, detail: Option<String>) -> ActionError {
+    ActionError {
+        code: "CFG-0401".into(),
+        severity: "ERROR".into(),
+        message: message.into(),
+        detail,
+    }
+}
+
+// "." / ".." は path を遡るので弾く
+pub(crate) fn valid_segment(s: &str) -> bool {
+    !s.is_empty()
+        && !s.chars().all(|c| c == '.')
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+}
+
+// クエリや path の 1 要素として URL エンコードする（unreserved 以外は %XX）
+pub(crate) fn url_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+// owner/name を検証して返す
+pub(crate) fn full_name(repo: &str) -> Result<String, ActionError> {
+    let r = repo.trim().trim_end_matches(".git");
+    match r.split_once('/') {
+        Some((o, n)) if valid_segment(o) && valid_segment(n) => Ok(r.to_string()),
+        _ => Err(cfg_err("repo must be owner/name", Some(repo.into()))),
+    }
+}
+
+fn client_for(api_base_url: &Option<String>, token: &Option<String>) -> ApiClient {
+    client(api_base_url.as_deref(), token.as_deref().unwrap_or(""))
+}
+
+/// repo 一覧（全ページ）。org 指定ならその org の repo、query で絞り込み
+#[tauri::command(async, rename_all = "camelCase")]
+pub(crate) fn github_list_repos(
+    token: Option<String>,
+    api_base_url: Option<String>,
+    org: Option<String>,
+    query: Option<String>,
+    max_pages: Option<u32>,
+) -> GitHubRepoList {
+    let client = client_for(&api_base_url, &token);
+    let mut out = GitHubRepoList {
+        ok: false,
+        repos: Vec::new(),
+        truncated: false,
+        rate_limit: None,
+        error: None,
+    };
+
+    let org = org.as_deref().map(str::trim).filter(|o| !o.is_empty());
+    let path = match org {
+        Some(o) if !valid_segment(o) => {
+            out.error = Some(cfg_err("invalid org", Some(o.into())));
+            return out;
+        }
+        Some(o) => format!("/orgs/{}/repos?per_page=100&type=all&sort=updated", o),
+        None if !client.has_token() => {
+            out.error = Some(cfg_err("token is required to list your repos", None));
+            return out;
+        }
+        None => "/user/repos?per_page=100&sort=updated&affiliation=owner,collaborator,organization_member"
+            .into(),
+    };
+
+    let res = client.get_all(&path, max_pages);
+    out.rate_limit = client.rate_limit();
+    match res {
+        Ok((items, truncated)) => {
+            let q = query.as_deref().map(str::trim).unwrap_or("");
+            out.repos = items
+                .iter()
+                .filter_map(GitHubRepoWire::from_json)
+                .filter(|r| r.matches(q))
+                .collect();
+            out.truncated = truncated;
+            out.ok = true;
+        }
+        Err(e) => out.error = Some(e.into_action("GH", "failed to list GitHub repos")),
+    }
+    out
+}
+
+/// branch 一覧（全ページ）と default branch
+#[tauri::command(async, rename_all = "camelCase")]
+pub(crate) fn github_list_branches(
+    token: Option<String>,
+    api_base_url: Option<String>,
+    repo: String,
+    max_pages: Option<u32>,
+) -> GitHubBranchList {
+    let client = client_for(&api_base_url, &token);
+    let mut out = GitHubBranchList {
+        ok: false,
+        branches: Vec::new(),
+        default_branch: None,
+        truncated: false,
+        rate_limit: None,
+        error: None,
+    };
+    let name = match full_name(&repo) {
+        Ok(n) => n,
+        Err(e) => {
+            out.error = Some(e);
+            return out;
+        }
+    };
+
+    let res = client.get_json(&format!("/repos/{}", name)).and_then(|v| {
+        let default = v
+            .get("default_branch")
+            .and_then(|x| x.as_str())
+            .map(String::from);
+        let (items, truncated) =
+            client.get_all(&format!("/repos/{}/branches?per_page=100", name), max_pages)?;
+        Ok((default, items, truncated))
+    });
+    out.rate_limit = client.rate_limit();
+    match res {
+        Ok((default, items, truncated)) => {
+            out.branches = items
+                .iter()
+                .filter_map(|b| {
+                    Some(GitHubBranch {
+                        name: b.get("name")?.as_str()?.to_string(),
+                        sha: b
+                            .pointer("/commit/sha")
+                            .and_then(|x| x.as_str())
+                            .unwrap_or("")
+                            .to_string(),
+                        protected: b
+                            .get("protected")
+                            .and_then(|x| x.as_bool())
+                            .unwrap_or(false),
+                    })
+                })
+                .collect();
+            out.default_branch = default;
+            out.truncated = truncated;
+            out.ok = true;
+        }
+        Err(e) => out.error = Some(e.into_action("GH", "failed to list GitHub branches")),
+    }
+    out
+}
+
+/// default branch だけ取る（GET /repos/{owner}/{name}）
+#[tauri::command(async, rename_all = "camelCase")]
+pub(crate) fn github_default_branch(
+    token: Option<String>,
+    api_base_url: Option<String>,
+    repo: String,
+) -> GitHubDefaultBranch {
+    let client = client_for(&api_base_url, &token);
+    let mut out = GitHubDefaultBranch {
+        ok: false,
+        default_branch: None,
+        rate_limit: None,
+        error: None,
+    };
+    let name = match full_name(&repo) {
+        Ok(n) => n,
+        Err(e) => {
+            out.error = Some(e);
+            return out;
+        }
+    };
+    let res = client.get_json(&format!("/repos/{}", name));
+    out.rate_limit = client.rate_limit();
+    match res {
+        Ok(v) => {
+            out.default_branch = v
+                .get("default_branch")
+                .and_then(|x| x.as_str())
+                .map(String::from);
+            out.ok = out.default_branch.is_some();
+            if !out.ok {
+                out.error = Some(cfg_err("unexpected response (no default_branch)", None));
+            }
+        }
+        Err(e) => out.error = Some(e.into_action("GH", "failed to get GitHub repo")),
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_http::{MockServer, Reply};
+
+    fn init_repo(v: serde_json::Value) -> InitGitHubRepo {
+        serde_json::from_value(v).unwrap()
+    }
+
+    #[test]
+    fn init_repo_creates_in_org_and_picks_url() {
+        let server = MockServer::start(|_, _| {
+            Reply::json(
+                201,
+                serde_json::json!({
+                    "full_name": "acme/app",
+                    "html_url": "https://github.com/acme/app",
+                    "clone_url": "https://github.com/acme/app.git",
+                    "ssh_url": "git@github.com:acme/app.git",
+                }),
+            )
+        });
+        let gh = init_repo(serde_json::json!({
+            "token": " tok ",
+            "apiBaseUrl": server.base,
+            "org": "acme",
+            "description": "demo",
+        }));
+        let mut steps = Vec::new();
+        let url = gh
+            .create("app", &mut steps)
+            .unwrap_or_else(|e| panic!("{:?}", e.detail));
+        assert_eq!(url, "https://github.com/acme/app.git");
+        assert!(steps[0].ok);
+        assert_eq!(
+            steps[0].cmd,
+            format!("POST {}/orgs/acme/repos", server.base)
+        );
+
+        let reqs = server.requests();
+        assert_eq!(reqs[0].method, "POST");
+        assert_eq!(reqs[0].path, "/orgs/acme/repos");
+        assert_eq!(reqs[0].header("authorization"), Some("Bearer tok"));
+        assert_eq!(
+            reqs[0].json(),
+            serde_json::json!({"name": "app", "private": true, "description": "demo"})
+        );
+
+        let gh = init_repo(serde_json::json!({
+            "token": "tok",
+            "apiBaseUrl": server.base,
+            "name": "other",
+            "protocol": "ssh",
+        }));
+        let url = gh
+            .create("app", &mut steps)
+            .unwrap_or_else(|e| panic!("{:?}", e.detail));
+        assert_eq!(url, "git@github.com:acme/app.git");
+        assert_eq!(server.requests()[1].path, "/user/repos");
+        assert_eq!(server.requests()[1].json()["name"], "other");
+    }
+
+    #[test]
+    fn init_repo_create_failure_is_gh_0101() {
+        let server = MockServer::start(|_, _| {
+            Reply::json(422, serde_json::json!({"message": "name already exists"}))
+        });
+        let gh = init_repo(serde_json::json!({"token": "tok", "apiBaseUrl": server.base}));
+        let mut steps = Vec::new();
+        let err = gh.create("app", &mut steps).unwrap_err();
+        assert_eq!(err.code, "GH-0101");
+        assert_eq!(err.detail.as_deref(), Some("HTTP 422: name already exists"));
+        assert!(!steps[0].ok);
+    }
+
+    #[test]
+    fn init_repo_validates_org_name_and_push_auth() {
+        for org in ["../x", "a/b", "acme repos"] {
+            let gh = init_repo(serde_json::json!({"token": "tok", "org": org}));
+            assert_eq!(gh.validate().unwrap_err().code, "CFG-0401", "{}", org);
+        }
+        for name in [".", "..", "a/b", "my app"] {
+            let gh = init_repo(serde_json::json!({"token": "tok", "name": name}));
+            assert_eq!(gh.validate().unwrap_err().code, "CFG-0401", "{}", name);
+        }
+        assert!(
+            init_repo(serde_json::json!({"token": "tok", "org": " acme "}))
+                .validate()
+                .is_ok()
+        );
+
+        let askpass = || Ok(PathBuf::from("/tmp/askpass.sh"));
+        let https = init_repo(serde_json::json!({"token": " tok "}))
+            .push_auth(askpass)
+            .unwrap();
+        assert_eq!(https.args(), ["-c", "credential.helper="]);
+        assert_eq!(
+            https.envs(),
+            [
+                ("GIT_ASKPASS", "/tmp/askpass.sh"),
+                ("GITSHLC_ASKPASS_USER", "x-access-token"),
+                ("GITSHLC_ASKPASS_TOKEN", "tok"),
+            ]
+        );
+
+        // ssh / push しないときは askpass を用意しない
+        for v in [
+            serde_json::json!({"token": "tok", "protocol": "ssh"}),
+            serde_json::json!({"token": "tok", "push": false}),
+        ] {
+            let auth = init_repo(v).push_auth(|| Err("unused".into())).unwrap();
+            assert!(auth.args().is_empty() && auth.envs().is_empty());
+        }
+    }
+}
@@ -0,0 +1,1603 @@
+// ホスティング（GitHub / GitLab / Gitea / Bitbucket）の違いを HostingProvider にまとめる。どれを使うかは remote URL の host で決める
+use crate::api::{ApiClient, ApiError};
+use crate::{github, remote_url, ActionError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ProviderKind {
+    GitHub,
+    GitLab,
+    Gitea,
+    Bitbucket,
+}
+
+impl ProviderKind {
+    fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "github" => Some(ProviderKind::GitHub),
+            "gitlab" => Some(ProviderKind::GitLab),
+            // Forgejo は Gitea と同じ API
+            "gitea" | "forgejo" => Some(ProviderKind::Gitea),
+            "bitbucket" => Some(ProviderKind::Bitbucket),
+            _ => None,
+        }
+    }
+
+    // host から推測する（自前ホストは gitlab.* / gitea.* のような名前だけ見る）
+    fn guess(host: &str) -> Option<Self> {
+        let h = host.to_ascii_lowercase();
+        let first = h.split('.').next().unwrap_or("");
+        if h == "github.com" || first == "github" {
+            Some(ProviderKind::GitHub)
+        } else if h == "gitlab.com" || first == "gitlab" {
+            Some(ProviderKind::GitLab)
+        } else if h == "codeberg.org" || first == "gitea" || first == "forgejo" {
+            Some(ProviderKind::Gitea)
+        } else if h == "bitbucket.org" {
+            // bitbucket.* の自前ホスト（Server / Data Center）は API が別物なので推測しない
+            Some(ProviderKind::Bitbucket)
+        } else {
+            None
+        }
+    }
+
+    // host から API の base URL を作る（GHE は /api/v3）
+    fn default_api_base(self, host: &str) -> String {
+        match self {
+            ProviderKind::GitHub if host.eq_ignore_ascii_case("github.com") => {
+                github::DEFAULT_API_BASE.into()
+            }
+            ProviderKind::GitHub => format!("https://{}/api/v3", host),
+            ProviderKind::GitLab => format!("https://{}/api/v4", host),
+            ProviderKind::Gitea => format!("https://{}/api/v1", host),
+            ProviderKind::Bitbucket if host.eq_ignore_ascii_case("bitbucket.org") => {
+                "https://api.bitbucket.org/2.0".into()
+            }
+            ProviderKind::Bitbucket => format!("https://{}/2.0", host),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HostedRepo {
+    // owner/name（GitLab のサブグループなら a/b/name）
+    pub(crate) full_name: String,
+    pub(crate) description: Option<String>,
+    pub(crate) html_url: String,
+    pub(crate) clone_url: String,
+    pub(crate) ssh_url: String,
+    pub(crate) default_branch: Option<String>,
+    pub(crate) is_private: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HostedPullRequest {
+    // GitHub / Gitea は number、GitLab は iid、Bitbucket は id
+    number: u64,
+    url: String,
+    state: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CheckState {
+    pub(crate) name: String,
+    // success | pending | failure
+    pub(crate) state: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CommitStatus {
+    // success | pending | failure | none（1 件も無い）
+    pub(crate) state: String,
+    pub(crate) checks: Vec<CheckState>,
+}
+
+impl CommitStatus {
+    fn from_checks(checks: Vec<CheckState>) -> Self {
+        let state = if checks.is_empty() {
+            "none"
+        } else if checks.iter().any(|c| c.state == "failure") {
+            "failure"
+        } else if checks.iter().any(|c| c.state == "pending") {
+            "pending"
+        } else {
+            "success"
+        };
+        CommitStatus {
+            state: state.into(),
+            checks,
+        }
+    }
+}
+
+pub(crate) trait HostingProvider {
+    /// org（GitLab は group）を指定しなければ token のユーザーが見える repo
+    fn list_repos(&self, org: Option<&str>) -> Result<Vec<HostedRepo>, ApiError>;
+    fn create_repo(
+        &self,
+        org: Option<&str>,
+        name: &str,
+        private: bool,
+        description: Option<&str>,
+    ) -> Result<HostedRepo, ApiError>;
+    fn open_pull_request(
+        &self,
+        repo: &str,
+        head: &str,
+        base: &str,
+        title: &str,
+        body: Option<&str>,
+    ) -> Result<HostedPullRequest, ApiError>;
+    fn commit_status(&self, repo: &str, sha: &str) -> Result<CommitStatus, ApiError>;
+    /// 戻り値は deploy key の id
+    fn add_deploy_key(
+        &self,
+        repo: &str,
+        title: &str,
+        key: &str,
+        read_only: bool,
+    ) -> Result<u64, ApiError>;
+}
+
+fn s(v: &serde_json::Value, p: &str) -> String {
+    v.pointer(p)
+        .and_then(|x| x.as_str())
+        .unwrap_or("")
+        .to_string()
+}
+
+fn s_opt(v: &serde_json::Value, p: &str) -> Option<String> {
+    v.pointer(p).and_then(|x| x.as_str()).map(String::from)
+}
+
+fn id(v: &serde_json::Value, p: &str) -> u64 {
+    v.pointer(p).and_then(|x| x.as_u64()).unwrap_or(0)
+}
+
+// GitHub と Gitea は repo の JSON がほぼ同じ
+fn github_like_repo(v: &serde_json::Value) -> HostedRepo {
+    HostedRepo {
+        full_name: s(v, "/full_name"),
+        description: s_opt(v, "/description").filter(|d| !d.is_empty()),
+        html_url: s(v, "/html_url"),
+        clone_url: s(v, "/clone_url"),
+        ssh_url: s(v, "/ssh_url"),
+        default_branch: s_opt(v, "/default_branch"),
+        is_private: v.get("private").and_then(|x| x.as_bool()).unwrap_or(false),
+    }
+}
+
+fn github_like_pr(v: &serde_json::Value) -> HostedPullRequest {
+    HostedPullRequest {
+        number: id(v, "/number"),
+        url: s(v, "/html_url"),
+        state: s(v, "/state"),
+    }
+}
+
+fn owner_path(org: Option<&str>) -> Option<&str> {
+    org.map(str::trim).filter(|o| !o.is_empty())
+}
+
+pub(crate) struct GitHubProvider(ApiClient);
+
+impl HostingProvider for GitHubProvider {
+    fn list_repos(&self, org: Option<&str>) -> Result<Vec<HostedRepo>, ApiError> {
+        let path = match owner_path(org) {
+            Some(o) => format!("/orgs/{}/repos?per_page=100&type=all", o),
+            None => {
+                "/user/repos?per_page=100&affiliation=owner,collaborator,organization_member".into()
+            }
+        };
+        let (items, _) = self.0.get_all(&path, None)?;
+        Ok(items.iter().map(github_like_repo).collect())
+    }
+
+    fn create_repo(
+        &self,
+        org: Option<&str>,
+        name: &str,
+        private: bool,
+        description: Option<&str>,
+    ) -> Result<HostedRepo, ApiError> {
+        let path = match owner_path(org) {
+            Some(o) => format!("/orgs/{}/repos", o),
+            None => "/user/repos".into(),
+        };
+        let mut body = serde_json::json!({ "name": name, "private": private });
+        if let Some(d) = description {
+            body["description"] = d.into();
+        }
+        Ok(github_like_repo(&self.0.send_json("POST", &path, body)?))
+    }
+
+    fn open_pull_request(
+        &self,
+        repo: &str,
+        head: &str,
+        base: &str,
+        title: &str,
+        body: Option<&str>,
+    ) -> Result<HostedPullRequest, ApiError> {
+        let v = self.0.send_json(
+            "POST",
+            &format!("/repos/{}/pulls", repo),
+            serde_json::json!({ "head": head, "base": base, "title": title, "body": body }),
+        )?;
+        Ok(github_like_pr(&v))
+    }
+
+    // combined status（外部 CI）と check runs（Actions）を合わせる
+    fn commit_status(&self, repo: &str, sha: &str) -> Result<CommitStatus, ApiError> {
+        let mut checks = Vec::new();
+        let v = self
+            .0
+            .get_json(&format!("/repos/{}/commits/{}/status", repo, sha))?;
+        for st in v
+            .get("statuses")
+            .and_then(|x| x.as_array())
+            .into_iter()
+            .flatten()
+        {
+            checks.push(CheckState {
+                name: s(st, "/context"),
+                state: match s(st, "/state").as_str() {
+                    "success" => "success",
+                    "pending" => "pending",
+                    _ => "failure",
+                }
+                .into(),
+            });
+        }
+        let (runs, _) = self.0.get_all(
+            &format!("/repos/{}/commits/{}/check-runs?per_page=100", repo, sha),
+            None,
+        )?;
+        for r in &runs {
+            let state = if s(r, "/status") != "completed" {
+                "pending"
+            } else if matches!(
+                s(r, "/conclusion").as_str(),
+                "success" | "neutral" | "skipped"
+            ) {
+                "success"
+            } else {
+                "failure"
+            };
+            checks.push(CheckState {
+                name: s(r, "/name"),
+                state: state.into(),
+            });
+        }
+        Ok(CommitStatus::from_checks(checks))
+    }
+
+    fn add_deploy_key(
+        &self,
+        repo: &str,
+        title: &str,
+        key: &str,
+        read_only: bool,
+    ) -> Result<u64, ApiError> {
+        let v = self.0.send_json(
+            "POST",
+            &format!("/repos/{}/keys", repo),
+            serde_json::json!({ "title": title, "key": key, "read_only": read_only }),
+        )?;
+        Ok(id(&v, "/id"))
+    }
+}
+
+pub(crate) struct GitLabProvider(ApiClient);
+
+// GitLab は project / group を URL エンコードした path で指定する（a/b → a%2Fb）
+fn gitlab_id(path: &str) -> String {
+    github::url_encode(path)
+}
+
+fn gitlab_repo(v: &serde_json::Value) -> HostedRepo {
+    HostedRepo {
+        full_name: s(v, "/path_with_namespace"),
+        description: s_opt(v, "/description").filter(|d| !d.is_empty()),
+        html_url: s(v, "/web_url"),
+        clone_url: s(v, "/http_url_to_repo"),
+        ssh_url: s(v, "/ssh_url_to_repo"),
+        default_branch: s_opt(v, "/default_branch"),
+        is_private: s(v, "/visibility") != "public",
+    }
+}
+
+impl HostingProvider for GitLabProvider {
+    fn list_repos(&self, org: Option<&str>) -> Result<Vec<HostedRepo>, ApiError> {
+        let path = match owner_path(org) {
+            Some(g) => format!(
+                "/groups/{}/projects?include_subgroups=true&per_page=100",
+                gitlab_id(g)
+            ),
+            None => "/projects?membership=true&per_page=100&order_by=last_activity_at".into(),
+        };
+        let (items, _) = self.0.get_all(&path, None)?;
+        Ok(items.iter().map(gitlab_repo).collect())
+    }
+
+    fn create_repo(
+        &self,
+        org: Option<&str>,
+        name: &str,
+        private: bool,
+        description: Option<&str>,
+    ) -> Result<HostedRepo, ApiError> {
+        let mut body = serde_json::json!({
+            "name": name,
+            "path": name,
+            "visibility": if private { "private" } else { "public" },
+        });
+        if let Some(d) = description {
+            body["description"] = d.into();
+        }
+        // group に作るときは namespace_id が要る
+        if let Some(g) = owner_path(org) {
+            let group = self.0.get_json(&format!("/groups/{}", gitlab_id(g)))?;
+            body["namespace_id"] = id(&group, "/id").into();
+        }
+        Ok(gitlab_repo(&self.0.send_json("POST", "/projects", body)?))
+    }
+
+    fn open_pull_request(
+        &self,
+        repo: &str,
+        head: &str,
+        base: &str,
+        title: &str,
+        body: Option<&str>,
+    ) -> Result<HostedPullRequest, ApiError> {
+        let v = self.0.send_json(
+            "POST",
+            &format!("/projects/{}/merge_requests", gitlab_id(repo)),
+            serde_json::json!({
+                "source_branch": head,
+                "target_branch": base,
+                "title": title,
+                "description": body,
+            }),
+        )?;
+        Ok(HostedPullRequest {
+            number: id(&v, "/iid"),
+            url: s(&v, "/web_url"),
+            state: s(&v, "/state"),
+        })
+    }
+
+    fn commit_status(&self, repo: &str, sha: &str) -> Result<CommitStatus, ApiError> {
+        let (items, _) = self.0.get_all(
+            &format!(
+                "/projects/{}/repository/commits/{}/statuses?per_page=100",
+                gitlab_id(repo),
+                sha
+            ),
+            None,
+        )?;
+        let checks = items
+            .iter()
+            .map(|st| CheckState {
+                name: s(st, "/name"),
+                state: match s(st, "/status").as_str() {
+                    "success" | "skipped" => "success",
+                    "created"
+                    | "waiting_for_resource"
+                    | "preparing"
+                    | "pending"
+                    | "running"
+                    | "scheduled"
+                    | "manual" => "pending",
+                    _ => "failure",
+                }
+                .into(),
+            })
+            .collect();
+        Ok(CommitStatus::from_checks(checks))
+    }
+
+    fn add_deploy_key(
+        &self,
+        repo: &str,
+        title: &str,
+        key: &str,
+        read_only: bool,
+    ) -> Result<u64, ApiError> {
+        let v = self.0.send_json(
+            "POST",
+            &format!("/projects/{}/deploy_keys", gitlab_id(repo)),
+            serde_json::json!({ "title": title, "key": key, "can_push": !read_only }),
+        )?;
+        Ok(id(&v, "/id"))
+    }
+}
+
+pub(crate) struct GiteaProvider(ApiClient);
+
+impl HostingProvider for GiteaProvider {
+    fn list_repos(&self, org: Option<&str>) -> Result<Vec<HostedRepo>, ApiError> {
+        // Gitea の 1 ページの上限は 50
+        let path = match owner_path(org) {
+            Some(o) => format!("/orgs/{}/repos?limit=50", o),
+            None => "/user/repos?limit=50".into(),
+        };
+        let (items, _) = self.0.get_all(&path, None)?;
+        Ok(items.iter().map(github_like_repo).collect())
+    }
+
+    fn create_repo(
+        &self,
+        org: Option<&str>,
+        name: &str,
+        private: bool,
+        description: Option<&str>,
+    ) -> Result<HostedRepo, ApiError> {
+        let path = match owner_path(org) {
+            Some(o) => format!("/orgs/{}/repos", o),
+            None => "/user/repos".into(),
+        };
+        let mut body = serde_json::json!({ "name": name, "private": private });
+        if let Some(d) = description {
+            body["description"] = d.into();
+        }
+        Ok(github_like_repo(&self.0.send_json("POST", &path, body)?))
+    }
+
+    fn open_pull_request(
+        &self,
+        repo: &str,
+        head: &str,
+        base: &str,
+        title: &str,
+        body: Option<&str>,
+    ) -> Result<HostedPullRequest, ApiError> {
+        let v = self.0.send_json(
+            "POST",
+            &format!("/repos/{}/pulls", repo),
+            serde_json::json!({ "head": head, "base": base, "title": title, "body": body }),
+        )?;
+        Ok(github_like_pr(&v))
+    }
+
+    // Gitea には check runs が無く、combined status だけ（各 status は "status" キー）
+    fn commit_status(&self, repo: &str, sha: &str) -> Result<CommitStatus, ApiError> {
+        let v = self
+            .0
+            .get_json(&format!("/repos/{}/commits/{}/status", repo, sha))?;
+        let checks = v
+            .get("statuses")
+            .and_then(|x| x.as_array())
+            .into_iter()
+            .flatten()
+            .map(|st| CheckState {
+                name: s(st, "/context"),
+                state: match s(st, "/status").as_str() {
+                    // warning は通す
+                    "success" | "warning" => "success",
+                    "pending" => "pending",
+                    _ => "failure",
+                }
+                .into(),
+            })
+            .collect();
+        Ok(CommitStatus::from_checks(checks))
+    }
+
+    fn add_deploy_key(
+        &self,
+        repo: &str,
+        title: &str,
+        key: &str,
+        read_only: bool,
+    ) -> Result<u64, ApiError> {
+        let v = self.0.send_json(
+            "POST",
+            &format!("/repos/{}/keys", repo),
+            serde_json::json!({ "title": title, "key": key, "read_only": read_only }),
+        )?;
+        Ok(id(&v, "/id"))
+    }
+}
+
+pub(crate) struct BitbucketProvider(ApiClient);
+
+// links.clone は [{"name": "https", "href": ...}, {"name": "ssh", ...}]
+fn bitbucket_clone_url(v: &serde_json::Value, name: &str) -> String {
+    v.pointer("/links/clone")
+        .and_then(|x| x.as_array())
+        .into_iter()
+        .flatten()
+        .find(|c| s(c, "/name") == name)
+        .map(|c| s(c, "/href"))
+        .unwrap_or_default()
+}
+
+fn bitbucket_repo(v: &serde_json::Value) -> HostedRepo {
+    HostedRepo {
+        full_name: s(v, "/full_name"),
+        description: s_opt(v, "/description").filter(|d| !d.is_empty()),
+        html_url: s(v, "/links/html/href"),
+        clone_url: bitbucket_clone_url(v, "https"),
+        ssh_url: bitbucket_clone_url(v, "ssh"),
+        default_branch: s_opt(v, "/mainbranch/name"),
+        is_private: v
+            .get("is_private")
+            .and_then(|x| x.as_bool())
+            .unwrap_or(false),
+    }
+}
+
+impl HostingProvider for BitbucketProvider {
+    // org は workspace
+    fn list_repos(&self, org: Option<&str>) -> Result<Vec<HostedRepo>, ApiError> {
+        let path = match owner_path(org) {
+            Some(w) => format!("/repositories/{}?pagelen=100", w),
+            None => "/repositories?role=member&pagelen=100".into(),
+        };
+        let (items, _) = self.0.get_all(&path, None)?;
+        Ok(items.iter().map(bitbucket_repo).collect())
+    }
+
+    // repo は POST /repositories/{workspace}/{slug}。workspace が無ければ token のユーザーの個人 workspace
+    fn create_repo(
+        &self,
+        org: Option<&str>,
+        name: &str,
+        private: bool,
+        description: Option<&str>,
+    ) -> Result<HostedRepo, ApiError> {
+        let workspace = match owner_path(org) {
+            Some(w) => w.to_string(),
+            None => s(&self.0.get_json("/user")?, "/username"),
+        };
+        let mut body = serde_json::json!({ "scm": "git", "name": name, "is_private": private });
+        if let Some(d) = description {
+            body["description"] = d.into();
+        }
+        let path = format!("/repositories/{}/{}", workspace, name.to_ascii_lowercase());
+        Ok(bitbucket_repo(&self.0.send_json("POST", &path, body)?))
+    }
+
+    fn open_pull_request(
+        &self,
+        repo: &str,
+        head: &str,
+        base: &str,
+        title: &str,
+        body: Option<&str>,
+    ) -> Result<HostedPullRequest, ApiError> {
+        let v = self.0.send_json(
+            "POST",
+            &format!("/repositories/{}/pullrequests", repo),
+            serde_json::json!({
+                "title": title,
+                "source": { "branch": { "name": head } },
+                "destination": { "branch": { "name": base } },
+                "description": body,
+            }),
+        )?;
+        Ok(HostedPullRequest {
+            number: id(&v, "/id"),
+            url: s(&v, "/links/html/href"),
+            // OPEN / MERGED / DECLINED
+            state: s(&v, "/state").to_ascii_lowercase(),
+        })
+    }
+
+    fn commit_status(&self, repo: &str, sha: &str) -> Result<CommitStatus, ApiError> {
+        let (items, _) = self.0.get_all(
+            &format!("/repositories/{}/commit/{}/statuses?pagelen=100", repo, sha),
+            None,
+        )?;
+        let checks = items
+            .iter()
+            .map(|st| CheckState {
+                name: s_opt(st, "/name")
+                    .filter(|n| !n.is_empty())
+                    .unwrap_or_else(|| s(st, "/key")),
+                state: match s(st, "/state").as_str() {
+                    "SUCCESSFUL" => "success",
+                    "INPROGRESS" => "pending",
+                    _ => "failure",
+                }
+                .into(),
+            })
+            .collect();
+        Ok(CommitStatus::from_checks(checks))
+    }
+
+    // Bitbucket の deploy key は常に read-only（書き込み可は hosting_add_deploy_key で弾く）
+    fn add_deploy_key(
+        &self,
+        repo: &str,
+        title: &str,
+        key: &str,
+        _read_only: bool,
+    ) -> Result<u64, ApiError> {
+        let v = self.0.send_json(
+            "POST",
+            &format!("/repositories/{}/deploy-keys", repo),
+            serde_json::json!({ "label": title, "key": key }),
+        )?;
+        Ok(id(&v, "/id"))
+    }
+}
+
+// ---- 選択とコマンド ----
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct HostingRequest {
+    // project の repoUrl。provider / API / repo はここから決める
+    pub(crate) remote_url: Option<String>,
+    // github | gitlab | gitea | bitbucket（host から分からない自前ホスト用）
+    pub(crate) provider: Option<String>,
+    // 無ければ host から（GHE: /api/v3、GitLab: /api/v4、Gitea: /api/v1、Bitbucket: api.bitbucket.org/2.0）
+    pub(crate) api_base_url: Option<String>,
+    pub(crate) token: String,
+    // owner/name（無ければ remoteUrl のパス）
+    pub(crate) repo: Option<String>,
+}
+
+impl HostingRequest {
+    /// GitHub 固定の呼び出し元用（remoteUrl も apiBaseUrl も無ければ api.github.com）
+    pub(crate) fn github(
+        remote_url: Option<String>,
+        api_base_url: Option<String>,
+        token: &str,
+        repo: Option<String>,
+    ) -> Self {
+        let api_base_url = api_base_url.filter(|b| !b.trim().is_empty()).or_else(|| {
+            remote_url
+                .is_none()
+                .then(|| github::DEFAULT_API_BASE.to_string())
+        });
+        HostingRequest {
+            remote_url,
+            provider: Some("github".into()),
+            api_base_url,
+            token: token.to_string(),
+            repo,
+        }
+    }
+
+    /// project の origin から provider を決める呼び出し元用（origin も provider も無ければ GitHub）
+    pub(crate) fn from_origin(
+        origin: Option<String>,
+        provider: Option<String>,
+        api_base_url: Option<String>,
+        token: &str,
+        repo: Option<String>,
+    ) -> Self {
+        let origin = origin.filter(|u| !u.trim().is_empty());
+        if origin.is_none() && opt(&provider).is_none() {
+            return HostingRequest::github(None, api_base_url, token, repo);
+        }
+        HostingRequest {
+            remote_url: origin,
+            provider,
+            api_base_url,
+            token: token.to_string(),
+            repo,
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HostingTarget {
+    pub(crate) provider: ProviderKind,
+    pub(crate) api_base_url: String,
+    pub(crate) repo: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HostingResult<T> {
+    ok: bool,
+    target: Option<HostingTarget>,
+    data: Option<T>,
+    error: Option<ActionError>,
+}
+
+fn host_err(code: &str, message: &str, detail: Option<String>) -> ActionError {
+    ActionError {
+        code: code.into(),
+        severity: "ERROR".into(),
+        message: message.into(),
+        detail,
+    }
+}
+
+fn opt(s: &Option<String>) -> Option<&str> {
+    s.as_deref().map(str::trim).filter(|x| !x.is_empty())
+}
+
+// owner/name（GitLab はサブグループ込みで a/b/name も可）。".." などは path に入れない
+fn repo_path(provider: ProviderKind, repo: &str) -> Result<String, ActionError> {
+    let r = repo.trim().trim_end_matches(".git");
+    let parts: Vec<&str> = r.split('/').collect();
+    let depth_ok = match provider {
+        ProviderKind::GitLab => parts.len() >= 2,
+        _ => parts.len() == 2,
+    };
+    if depth_ok && parts.iter().all(|p| github::valid_segment(p)) {
+        Ok(r.to_string())
+    } else {
+        Err(host_err(
+            "CFG-0401",
+            "repo must be owner/name",
+            Some(repo.into()),
+        ))
+    }
+}
+
+// org（GitLab の group はサブグループ込みで a/b も可）。GitHub / Gitea は 1 セグメントだけ
+fn org_path(provider: ProviderKind, org: Option<&str>) -> Result<(), ActionError> {
+    let Some(o) = owner_path(org) else {
+        return Ok(());
+    };
+    let parts: Vec<&str> = o.split('/').collect();
+    let depth_ok = provider == ProviderKind::GitLab || parts.len() == 1;
+    if depth_ok && parts.iter().all(|p| github::valid_segment(p)) {
+        Ok(())
+    } else {
+        Err(host_err("CFG-0401", "invalid org", Some(o.into())))
+    }
+}
+
+// commit の SHA（短縮形も可）。path に入れるので 16 進以外は通さない
+fn valid_sha(sha: &str) -> bool {
+    (7..=64).contains(&sha.len()) && sha.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// remoteUrl / provider / apiBaseUrl から provider と API と repo を決める
+pub(crate) fn resolve(req: &HostingRequest) -> Result<HostingTarget, ActionError> {
+    let parsed = opt(&req.remote_url).and_then(remote_url::parse);
+    let host = parsed.as_ref().and_then(|u| u.host.clone());
+
+    let provider = match opt(&req.provider) {
+        Some(p) => ProviderKind::parse(p)
+            .ok_or_else(|| host_err("HOST-0001", "unsupported provider", Some(p.into())))?,
+        None => match host.as_deref() {
+            Some(h) => ProviderKind::guess(h).ok_or_else(|| {
+                host_err(
+                    "HOST-0001",
+                    "cannot tell the provider from the host (set provider)",
+                    Some(h.into()),
+                )
+            })?,
+            None => {
+                return Err(host_err(
+                    "HOST-0002",
+                    "remoteUrl (with a host) or provider + apiBaseUrl is required",
+                    None,
+                ))
+            }
+        },
+    };
+
+    let api_base_url = match (opt(&req.api_base_url), host.as_deref()) {
+        (Some(b), _) => b.trim_end_matches('/').to_string(),
+        (None, Some(h)) => provider.default_api_base(h),
+        (None, None) => {
+            return Err(host_err(
+                "HOST-0002",
+                "apiBaseUrl is required without remoteUrl",
+                None,
+            ))
+        }
+    };
+
+    let repo = match opt(&req.repo) {
+        Some(r) => Some(repo_path(provider, r)?),
+        None => match parsed.filter(|u| u.host.is_some() && u.owner.is_some()) {
+            Some(u) => Some(repo_path(provider, &u.path)?),
+            None => None,
+        },
+    };
+
+    Ok(HostingTarget {
+        provider,
+        api_base_url,
+        repo,
+    })
+}
+
+/// provider に合わせたヘッダの ApiClient（GitHub 固有の API を直接呼ぶ場合もこれを使う）
+pub(crate) fn client_for(target: &HostingTarget, token: &str) -> ApiClient {
+    match target.provider {
+        ProviderKind::GitHub => github::client(Some(&target.api_base_url), token),
+        ProviderKind::GitLab | ProviderKind::Gitea | ProviderKind::Bitbucket => {
+            ApiClient::new(&target.api_base_url, token)
+        }
+    }
+}
+
+pub(crate) fn provider_for(target: &HostingTarget, token: &str) -> Box<dyn HostingProvider> {
+    let client = client_for(target, token);
+    match target.provider {
+        ProviderKind::GitHub => Box::new(GitHubProvider(client)),
+        ProviderKind::GitLab => Box::new(GitLabProvider(client)),
+        ProviderKind::Gitea => Box::new(GiteaProvider(client)),
+        ProviderKind::Bitbucket => Box::new(BitbucketProvider(client)),
+    }
+}
+
+// 共通: 解決 → 実行 → HostingResult
+fn run<T>(
+    req: &HostingRequest,
+    need_repo: bool,
+    message: &str,
+    f: impl FnOnce(&dyn HostingProvider, Option<&str>) -> Result<T, ApiError>,
+) -> HostingResult<T> {
+    run_checked(req, need_repo, message, |_| Ok(()), f)
+}
+
+// 解決後、HTTP を呼ぶ前に引数を provider に合わせて検証する
+fn run_checked<T>(
+    req: &HostingRequest,
+    need_repo: bool,
+    message: &str,
+    check: impl FnOnce(&HostingTarget) -> Result<(), ActionError>,
+    f: impl FnOnce(&dyn HostingProvider, Option<&str>) -> Result<T, ApiError>,
+) -> HostingResult<T> {
+    let mut out = HostingResult {
+        ok: false,
+        target: None,
+        data: None,
+        error: None,
+    };
+    let target = match resolve(req) {
+        Ok(t) => t,
+        Err(e) => {
+            out.error = Some(e);
+            return out;
+        }
+    };
+    if req.token.trim().is_empty() {
+        out.target = Some(target);
+        out.error = Some(host_err("CFG-0401", "token is required", None));
+        return out;
+    }
+    if need_repo && target.repo.is_none() {
+        out.target = Some(target);
+        out.error = Some(host_err("HOST-0002", "repo (owner/name) is required", None));
+        return out;
+    }
+    if let Err(e) = check(&target) {
+        out.target = Some(target);
+        out.error = Some(e);
+        return out;
+    }
+    let provider = provider_for(&target, &req.token);
+    match f(provider.as_ref(), target.repo.as_deref()) {
+        Ok(v) => {
+            out.data = Some(v);
+            out.ok = true;
+        }
+        Err(e) => out.error = Some(e.into_action("HOST", message)),
+    }
+    out.target = Some(target);
+    out
+}
+
+/// provider / API base / repo の解決結果だけ返す（UI の表示用）
+#[tauri::command(rename_all = "camelCase")]
+pub(crate) fn hosting_resolve(hosting: HostingRequest) -> Result<HostingTarget, ActionError> {
+    resolve(&hosting)
+}
+
+#[tauri::command(async, rename_all = "camelCase")]
+pub(crate) fn hosting_list_repos(
+    hosting: HostingRequest,
+    org: Option<String>,
+) -> HostingResult<Vec<HostedRepo>> {
+    run_checked(
+        &hosting,
+        false,
+        "failed to list repositories",
+        |t| org_path(t.provider, org.as_deref()),
+        |p, _| p.list_repos(org.as_deref()),
+    )
+}
+
+#[tauri::command(async, rename_all = "camelCase")]
+pub(crate) fn hosting_create_repo(
+    hosting: HostingRequest,
+    org: Option<String>,
+    name: String,
+    private: Option<bool>,
+    description: Option<String>,
+) -> HostingResult<HostedRepo> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return HostingResult {
+            ok: false,
+            target: None,
+            data: None,
+            error: Some(host_err("CFG-0001", "name is required", None)),
+        };
+    }
+    run_checked(
+        &hosting,
+        false,
+        "failed to create repository",
+        |t| {
+            org_path(t.provider, org.as_deref())?;
+            // Bitbucket は name（小文字にして slug）が path に入る
+            if t.provider == ProviderKind::Bitbucket && !github::valid_segment(&name) {
+                return Err(host_err("CFG-0001", "invalid name", Some(name.clone())));
+            }
+            Ok(())
+        },
+        |p, _| {
+            p.create_repo(
+                org.as_deref(),
+                &name,
+                private.unwrap_or(true),
+                opt(&description),
+            )
+        },
+    )
+}
+
+/// PR（GitLab は MR）を作る
+#[tauri::command(async, rename_all = "camelCase")]
+pub(crate) fn hosting_open_pull_request(
+    hosting: HostingRequest,
+    head: String,
+    base: String,
+    title: Option<String>,
+    body: Option<String>,
+) -> HostingResult<HostedPullRequest> {
+    let title = opt(&title)
+        .map(String::from)
+        .unwrap_or_else(|| format!("Merge {} into {}", head.trim(), base.trim()));
+    run(&hosting, true, "failed to open pull request", |p, repo| {
+        p.open_pull_request(
+            repo.unwrap_or(""),
+            head.trim(),
+            base.trim(),
+            &title,
+            opt(&body),
+        )
+    })
+}
+
+#[tauri::command(async, rename_all = "camelCase")]
+pub(crate) fn hosting_commit_status(
+    hosting: HostingRequest,
+    sha: String,
+) -> HostingResult<CommitStatus> {
+    let sha = sha.trim().to_string();
+    run_checked(
+        &hosting,
+        true,
+        "failed to get commit status",
+        |_| match valid_sha(&sha) {
+            true => Ok(()),
+            false => Err(host_err("CFG-0001", "sha must be hex", Some(sha.clone()))),
+        },
+        |p, repo| p.commit_status(repo.unwrap_or(""), &sha),
+    )
+}
+
+#[tauri::command(async, rename_all = "camelCase")]
+pub(crate) fn hosting_add_deploy_key(
+    hosting: HostingRequest,
+    title: String,
+    public_key: String,
+    read_only: Option<bool>,
+) -> HostingResult<u64> {
+    let read_only = read_only.unwrap_or(true);
+    run_checked(
+        &hosting,
+        true,
+        "failed to add deploy key",
+        |t| match t.provider == ProviderKind::Bitbucket && !read_only {
+            true => Err(host_err(
+                "CFG-0001",
+                "Bitbucket deploy keys are read-only",
+                None,
+            )),
+            false => Ok(()),
+        },
+        |p, repo| {
+            p.add_deploy_key(
+                repo.unwrap_or(""),
+                title.trim(),
+                public_key.trim(),
+                read_only,
+            )
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_http::{MockServer, Recorded, Reply};
+
+    fn provider(kind: ProviderKind, server: &MockServer) -> Box<dyn HostingProvider> {
+        let target = HostingTarget {
+            provider: kind,
+            api_base_url: server.base.clone(),
+            repo: None,
+        };
+        provider_for(&target, "tok")
+    }
+
+    // 1 ページ目は Link で 2 ページ目を指す
+    fn paged(
+        req: &Recorded,
+        base: &str,
+        first: serde_json::Value,
+        second: serde_json::Value,
+    ) -> Reply {
+        if req.path.contains("page=2") {
+            Reply::json(200, second)
+        } else {
+            let next = format!("{}{}&page=2", base, req.path);
+            Reply::json(200, first).header("Link", &format!("<{}>; rel=\"next\"", next))
+        }
+    }
+
+    fn names(repos: &[HostedRepo]) -> Vec<&str> {
+        repos.iter().map(|r| r.full_name.as_str()).collect()
+    }
+
+    fn states(s: &CommitStatus) -> Vec<(&str, &str)> {
+        s.checks
+            .iter()
+            .map(|c| (c.name.as_str(), c.state.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn github_provider() {
+        let server = MockServer::start(|req, base| {
+            let p = req.path.as_str();
+            match (req.method.as_str(), p) {
+                ("GET", _) if p.starts_with("/orgs/acme/repos") => paged(
+                    req,
+                    base,
+                    serde_json::json!([{"full_name": "acme/a", "private": true}]),
+                    serde_json::json!([{"full_name": "acme/b"}]),
+                ),
+                ("POST", "/orgs/acme/repos") => Reply::json(
+                    201,
+                    serde_json::json!({
+                        "full_name": "acme/new",
+                        "clone_url": "https://github.com/acme/new.git",
+                        "ssh_url": "git@github.com:acme/new.git",
+                        "private": true,
+                    }),
+                ),
+                ("POST", "/repos/acme/a/pulls") => Reply::json(
+                    201,
+                    serde_json::json!({"number": 5, "html_url": "https://github.com/acme/a/pull/5", "state": "open"}),
+                ),
+                ("GET", "/repos/acme/a/commits/abc/status") => Reply::json(
+                    200,
+                    serde_json::json!({"statuses": [
+                        {"context": "ci/jenkins", "state": "success"},
+                        {"context": "ci/lint", "state": "error"},
+                    ]}),
+                ),
+                ("GET", _) if p.starts_with("/repos/acme/a/commits/abc/check-runs") => Reply::json(
+                    200,
+                    serde_json::json!({"total_count": 2, "check_runs": [
+                        {"name": "build", "status": "completed", "conclusion": "skipped"},
+                        {"name": "test", "status": "in_progress", "conclusion": null},
+                    ]}),
+                ),
+                ("POST", "/repos/acme/a/keys") => Reply::json(201, serde_json::json!({"id": 42})),
+                _ => Reply::json(404, serde_json::json!({"message": "Not Found"})),
+            }
+        });
+        let gh = provider(ProviderKind::GitHub, &server);
+
+        let repos = gh
+            .list_repos(Some("acme"))
+            .unwrap_or_else(|e| panic!("{}", e.message()));
+        assert_eq!(names(&repos), ["acme/a", "acme/b"]);
+        assert!(repos[0].is_private);
+
+        let created = gh
+            .create_repo(Some("acme"), "new", true, Some("demo"))
+            .unwrap_or_else(|e| panic!("{}", e.message()));
+        assert_eq!(created.clone_url, "https://github.com/acme/new.git");
+
+        let pr = gh
+            .open_pull_request("acme/a", "feat", "main", "Merge feat", None)
+            .unwrap_or_else(|e| panic!("{}", e.message()));
+        assert_eq!((pr.number, pr.state.as_str()), (5, "open"));
+
+        let st = gh
+            .commit_status("acme/a", "abc")
+            .unwrap_or_else(|e| panic!("{}", e.message()));
+        assert_eq!(st.state, "failure");
+        assert_eq!(
+            states(&st),
+            [
+                ("ci/jenkins", "success"),
+                ("ci/lint", "failure"),
+                ("build", "success"),
+                ("test", "pending"),
+            ]
+        );
+
+        let id = gh
+            .add_deploy_key("acme/a", "gitshlc-app@host", "ssh-ed25519 AAAA x", true)
+            .unwrap_or_else(|e| panic!("{}", e.message()));
+        assert_eq!(id, 42);
+
+        let reqs = server.requests();
+        assert_eq!(reqs[0].path, "/orgs/acme/repos?per_page=100&type=all");
+        assert!(reqs[1].path.ends_with("&page=2"));
+        assert_eq!(
+            reqs[2].json(),
+            serde_json::json!({"name": "new", "private": true, "description": "demo"})
+        );
+        assert_eq!(
+            reqs[3].json(),
+            serde_json::json!({"head": "feat", "base": "main", "title": "Merge feat", "body": null})
+        );
+        let key = reqs.last().unwrap();
+        assert_eq!(key.header("authorization"), Some("Bearer tok"));
+        assert_eq!(key.header("accept"), Some("application/vnd.github+json"));
+        assert_eq!(
+            key.json(),
+            serde_json::json!({"title": "gitshlc-app@host", "key": "ssh-ed25519 AAAA x", "read_only": true})
+        );
+    }
+
+    #[test]
+    fn gitlab_provider() {
+        let server = MockServer::start(|req, base| {
+            let p = req.path.as_str();
+            match (req.method.as_str(), p) {
+                ("GET", _) if p.starts_with("/groups/acme%2Fteam/projects") => paged(
+                    req,
+                    base,
+                    serde_json::json!([{"path_with_namespace": "acme/team/a", "visibility": "private"}]),
+                    serde_json::json!([{"path_with_namespace": "acme/team/b", "visibility": "public"}]),
+                ),
+                ("GET", "/groups/acme%2Fteam") => Reply::json(200, serde_json::json!({"id": 9})),
+                ("POST", "/projects") => Reply::json(
+                    201,
+                    serde_json::json!({
+                        "path_with_namespace": "acme/team/new",
+                        "http_url_to_repo": "https://gitlab.com/acme/team/new.git",
+                        "ssh_url_to_repo": "git@gitlab.com:acme/team/new.git",
+                        "visibility": "private",
+                    }),
+                ),
+                ("POST", "/projects/acme%2Fteam%2Fa/merge_requests") => Reply::json(
+                    201,
+                    serde_json::json!({"iid": 3, "id": 1234, "web_url": "https://gitlab.com/acme/team/a/-/merge_requests/3", "state": "opened"}),
+                ),
+                ("GET", _)
+                    if p.starts_with(
+                        "/projects/acme%2Fteam%2Fa/repository/commits/abc/statuses",
+                    ) =>
+                {
+                    Reply::json(
+                        200,
+                        serde_json::json!([
+                            {"name": "build", "status": "success"},
+                            {"name": "deploy", "status": "manual"},
+                        ]),
+                    )
+                }
+                ("POST", "/projects/acme%2Fteam%2Fa/deploy_keys") => {
+                    Reply::json(201, serde_json::json!({"id": 7}))
+                }
+                _ => Reply::json(404, serde_json::json!({"message": "404 Not Found"})),
+            }
+        });
+        let gl = provider(ProviderKind::GitLab, &server);
+
+        let repos = gl
+            .list_repos(Some("acme/team"))
+            .unwrap_or_else(|e| panic!("{}", e.message()));
+        assert_eq!(names(&repos), ["acme/team/a", "acme/team/b"]);
+        assert!(repos[0].is_private && !repos[1].is_private);
+
+        let created = gl
+            .create_repo(Some("acme/team"), "new", true, None)
+            .unwrap_or_else(|e| panic!("{}", e.message()));
+        assert_eq!(created.clone_url, "https://gitlab.com/acme/team/new.git");
+
+        let mr = gl
+            .open_pull_request("acme/team/a", "feat", "main", "Merge feat", Some("body"))
+            .unwrap_or_else(|e| panic!("{}", e.message()));
+        assert_eq!((mr.number, mr.state.as_str()), (3, "opened"));
+
+        let st = gl
+            .commit_status("acme/team/a", "abc")
+            .unwrap_or_else(|e| panic!("{}", e.message()));
+        assert_eq!(st.state, "pending");
+        assert_eq!(states(&st), [("build", "success"), ("deploy", "pending")]);
+
+        let id = gl
+            .add_deploy_key("acme/team/a", "t", "ssh-ed25519 AAAA", true)
+            .unwrap_or_else(|e| panic!("{}", e.message()));
+        assert_eq!(id, 7);
+
+        let reqs = server.requests();
+        assert!(reqs[1].path.ends_with("&page=2"));
+        assert_eq!(reqs[2].path, "/groups/acme%2Fteam");
+        assert_eq!(
+            reqs[3].json(),
+            serde_json::json!({"name": "new", "path": "new", "visibility": "private", "namespace_id": 9})
+        );
+        assert_eq!(
+            reqs[4].json(),
+            serde_json::json!({"source_branch": "feat", "target_branch": "main", "title": "Merge feat", "description": "body"})
+        );
+        let key = reqs.last().unwrap();
+        assert_eq!(key.header("accept"), Some("application/json"));
+        assert_eq!(key.header("x-github-api-version"), None);
+        assert_eq!(
+            key.json(),
+            serde_json::json!({"title": "t", "key": "ssh-ed25519 AAAA", "can_push": false})
+        );
+    }
+
+    #[test]
+    fn gitea_provider() {
+        let server = MockServer::start(|req, base| {
+            let p = req.path.as_str();
+            match (req.method.as_str(), p) {
+                ("GET", _) if p.starts_with("/user/repos") => paged(
+                    req,
+                    base,
+                    serde_json::json!([{"full_name": "me/a"}]),
+                    serde_json::json!([{"full_name": "me/b"}]),
+                ),
+                ("POST", "/user/repos") => Reply::json(
+                    201,
+                    serde_json::json!({"full_name": "me/new", "clone_url": "https://codeberg.org/me/new.git"}),
+                ),
+                ("POST", "/repos/me/a/pulls") => Reply::json(
+                    201,
+                    serde_json::json!({"number": 2, "html_url": "https://codeberg.org/me/a/pulls/2", "state": "open"}),
+                ),
+                ("GET", "/repos/me/a/commits/abc/status") => Reply::json(
+                    200,
+                    serde_json::json!({"statuses": [
+                        {"context": "woodpecker", "status": "success"},
+                        {"context": "lint", "status": "warning"},
+                    ]}),
+                ),
+                ("POST", "/repos/me/a/keys") => Reply::json(201, serde_json::json!({"id": 11})),
+                _ => Reply::json(404, serde_json::json!({"message": "not found"})),
+            }
+        });
+        let gt = provider(ProviderKind::Gitea, &server);
+
+        let repos = gt
+            .list_repos(None)
+            .unwrap_or_else(|e| panic!("{}", e.message()));
+        assert_eq!(names(&repos), ["me/a", "me/b"]);
+
+        let created = gt
+            .create_repo(None, "new", false, None)
+            .unwrap_or_else(|e| panic!("{}", e.message()));
+        assert_eq!(created.full_name, "me/new");
+
+        let pr = gt
+            .open_pull_request("me/a", "feat", "main", "Merge feat", None)
+            .unwrap_or_else(|e| panic!("{}", e.message()));
+        assert_eq!(pr.number, 2);
+
+        // check runs は見に行かない
+        let st = gt
+            .commit_status("me/a", "abc")
+            .unwrap_or_else(|e| panic!("{}", e.message()));
+        assert_eq!(st.state, "success");
+        assert_eq!(
+            states(&st),
+            [("woodpecker", "success"), ("lint", "success")]
+        );
+
+        let id = gt
+            .add_deploy_key("me/a", "t", "ssh-ed25519 AAAA", false)
+            .unwrap_or_else(|e| panic!("{}", e.message()));
+        assert_eq!(id, 11);
+
+        let reqs = server.requests();
+        assert_eq!(reqs[0].path, "/user/repos?limit=50");
+        assert!(reqs[1].path.ends_with("&page=2"));
+        assert_eq!(
+            reqs[2].json(),
+            serde_json::json!({"name": "new", "private": false})
+        );
+        assert!(reqs.iter().all(|r| !r.path.contains("check-runs")));
+        assert_eq!(
+            reqs.last().unwrap().json(),
+            serde_json::json!({"title": "t", "key": "ssh-ed25519 AAAA", "read_only": false})
+        );
+    }
+
+    #[test]
+    fn bitbucket_provider() {
+        let server = MockServer::start(|req, base| {
+            let p = req.path.as_str();
+            match (req.method.as_str(), p) {
+                // 次のページは Link ではなく本文の next
+                ("GET", "/repositories?role=member&pagelen=100") => Reply::json(
+                    200,
+                    serde_json::json!({
+                        "values": [{
+                            "full_name": "me/a",
+                            "is_private": true,
+                            "mainbranch": {"name": "main"},
+                            "links": {
+                                "html": {"href": "https://bitbucket.org/me/a"},
+                                "clone": [
+                                    {"name": "https", "href": "https://bitbucket.org/me/a.git"},
+                                    {"name": "ssh", "href": "git@bitbucket.org:me/a.git"},
+                                ],
+                            },
+                        }],
+                        "next": format!("{}/repositories?role=member&pagelen=100&page=2", base),
+                    }),
+                ),
+                ("GET", _) if p.starts_with("/repositories?") => {
+                    Reply::json(200, serde_json::json!({"values": [{"full_name": "me/b"}]}))
+                }
+                ("GET", "/user") => Reply::json(200, serde_json::json!({"username": "me"})),
+                ("POST", "/repositories/me/new") => {
+                    Reply::json(200, serde_json::json!({"full_name": "me/new"}))
+                }
+                ("POST", "/repositories/me/a/pullrequests") => Reply::json(
+                    201,
+                    serde_json::json!({
+                        "id": 4,
+                        "state": "OPEN",
+                        "links": {"html": {"href": "https://bitbucket.org/me/a/pull-requests/4"}},
+                    }),
+                ),
+                ("GET", _) if p.starts_with("/repositories/me/a/commit/abc1234/statuses") => {
+                    Reply::json(
+                        200,
+                        serde_json::json!({"values": [
+                            {"key": "build", "name": "Pipeline", "state": "SUCCESSFUL"},
+                            {"key": "lint", "state": "INPROGRESS"},
+                            {"key": "e2e", "name": "e2e", "state": "STOPPED"},
+                        ]}),
+                    )
+                }
+                ("POST", "/repositories/me/a/deploy-keys") => {
+                    Reply::json(200, serde_json::json!({"id": 21}))
+                }
+                _ => Reply::json(
+                    404,
+                    serde_json::json!({"type": "error", "error": {"message": "not found"}}),
+                ),
+            }
+        });
+        let bb = provider(ProviderKind::Bitbucket, &server);
+
+        let repos = bb
+            .list_repos(None)
+            .unwrap_or_else(|e| panic!("{}", e.message()));
+        assert_eq!(names(&repos), ["me/a", "me/b"]);
+        assert_eq!(repos[0].clone_url, "https://bitbucket.org/me/a.git");
+        assert_eq!(repos[0].ssh_url, "git@bitbucket.org:me/a.git");
+        assert_eq!(repos[0].default_branch.as_deref(), Some("main"));
+        assert!(repos[0].is_private);
+
+        // workspace が無ければ token のユーザーの workspace に作る
+        let created = bb
+            .create_repo(None, "New", true, Some("d"))
+            .unwrap_or_else(|e| panic!("{}", e.message()));
+        assert_eq!(created.full_name, "me/new");
+
+        let pr = bb
+            .open_pull_request("me/a", "feat", "main", "Merge feat", None)
+            .unwrap_or_else(|e| panic!("{}", e.message()));
+        assert_eq!(pr.number, 4);
+        assert_eq!(pr.state, "open");
+
+        let st = bb
+            .commit_status("me/a", "abc1234")
+            .unwrap_or_else(|e| panic!("{}", e.message()));
+        assert_eq!(st.state, "failure");
+        assert_eq!(
+            states(&st),
+            [
+                ("Pipeline", "success"),
+                ("lint", "pending"),
+                ("e2e", "failure")
+            ]
+        );
+
+        let id = bb
+            .add_deploy_key("me/a", "t", "ssh-ed25519 AAAA", true)
+            .unwrap_or_else(|e| panic!("{}", e.message()));
+        assert_eq!(id, 21);
+
+        let e = bb.list_repos(Some("nope")).err().unwrap();
+        assert_eq!(e.message(), "HTTP 404: not found");
+
+        let reqs = server.requests();
+        assert!(reqs[1].path.ends_with("&page=2"));
+        assert_eq!(reqs[2].path, "/user");
+        assert_eq!(
+            reqs[3].json(),
+            serde_json::json!({"scm": "git", "name": "New", "is_private": true, "description": "d"})
+        );
+        assert_eq!(
+            reqs[4].json()["source"],
+            serde_json::json!({"branch": {"name": "feat"}})
+        );
+        assert_eq!(
+            reqs[6].json(),
+            serde_json::json!({"label": "t", "key": "ssh-ed25519 AAAA"})
+        );
+
+        // 書き込み可の deploy key は API を呼ぶ前に弾く
+        let req = HostingRequest {
+            provider: Some("bitbucket".into()),
+            api_base_url: Some(server.base.clone()),
+            token: "tok".into(),
+            repo: Some("me/a".into()),
+            ..Default::default()
+        };
+        let out = hosting_add_deploy_key(req, "t".into(), "ssh-ed25519 AAAA".into(), Some(false));
+        assert_eq!(out.error.unwrap().code, "CFG-0001");
+        assert_eq!(server.requests().len(), reqs.len());
+    }
+
+    #[test]
+    fn api_errors_are_host_codes_with_neutral_messages() {
+        let server = MockServer::start(|req, _| match req.path.as_str() {
+            p if p.ends_with("keys") => {
+                Reply::json(422, serde_json::json!({"message": "key is already in use"}))
+            }
+            _ => Reply::json(401, serde_json::json!({"message": "401 Unauthorized"})),
+        });
+        let req = |repo: &str| HostingRequest {
+            provider: Some("gitlab".into()),
+            api_base_url: Some(server.base.clone()),
+            token: "tok".into(),
+            repo: Some(repo.into()),
+            ..Default::default()
+        };
+
+        let out = hosting_list_repos(req("acme/a"), None);
+        let e = out.error.unwrap();
+        assert_eq!(e.code, "HOST-0103");
+        assert_eq!(e.message, "token rejected (401)");
+
+        let out =
+            hosting_add_deploy_key(req("acme/a"), "t".into(), "ssh-ed25519 AAAA".into(), None);
+        let e = out.error.unwrap();
+        assert_eq!(e.code, "HOST-0100");
+        assert_eq!(e.detail.as_deref(), Some("HTTP 422: key is already in use"));
+    }
+
+    #[test]
+    fn resolve_validates_repo() {
+        let req = |provider: &str, repo: &str| HostingRequest {
+            provider: Some(provider.into()),
+            api_base_url: Some("https://git.example.com/api".into()),
+            token: "tok".into(),
+            repo: Some(repo.into()),
+            ..Default::default()
+        };
+        for repo in [
+            "../..", "acme/..", "acme", "acme/a/b", "acme/a b", "/acme/a",
+        ] {
+            let e = resolve(&req("github", repo)).unwrap_err();
+            assert_eq!(e.code, "CFG-0401", "{}", repo);
+        }
+        assert_eq!(
+            resolve(&req("github", " acme/a.git "))
+                .unwrap()
+                .repo
+                .as_deref(),
+            Some("acme/a")
+        );
+        // GitLab はサブグループを許す
+        assert_eq!(
+            resolve(&req("gitlab", "acme/team/a"))
+                .unwrap()
+                .repo
+                .as_deref(),
+            Some("acme/team/a")
+        );
+        assert!(resolve(&req("gitlab", "acme/../a")).is_err());
+
+        let t = resolve(&HostingRequest {
+            remote_url: Some("git@gitlab.example.com:acme/team/a.git".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(t.provider, ProviderKind::GitLab);
+        assert_eq!(t.api_base_url, "https://gitlab.example.com/api/v4");
+        assert_eq!(t.repo.as_deref(), Some("acme/team/a"));
+
+        let t = resolve(&HostingRequest {
+            remote_url: Some("git@bitbucket.org:me/a.git".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(t.provider, ProviderKind::Bitbucket);
+        assert_eq!(t.api_base_url, "https://api.bitbucket.org/2.0");
+        assert_eq!(t.repo.as_deref(), Some("me/a"));
+
+        let t = resolve(&HostingRequest::github(
+            None,
+            None,
+            "tok",
+            Some("o/r".into()),
+        ))
+        .unwrap();
+        assert_eq!(t.api_base_url, github::DEFAULT_API_BASE);
+
+        let t = resolve(&HostingRequest::from_origin(
+            Some("git@gitlab.com:acme/a.git".into()),
+            None,
+            None,
+            "tok",
+            None,
+        ))
+        .unwrap();
+        assert_eq!(t.provider, ProviderKind::GitLab);
+        assert_eq!(t.repo.as_deref(), Some("acme/a"));
+
+        let t = resolve(&HostingRequest::from_origin(
+            Some(" ".into()),
+            None,
+            None,
+            "tok",
+            Some("o/r".into()),
+        ))
+        .unwrap();
+        assert_eq!(t.provider, ProviderKind::GitHub);
+        assert_eq!(t.api_base_url, github::DEFAULT_API_BASE);
+    }
+
+    #[test]
+    fn org_and_sha_are_checked_before_any_request() {
+        let server = MockServer::start(|_, _| Reply::json(200, serde_json::json!([])));
+        let req = |provider: &str| HostingRequest {
+            provider: Some(provider.into()),
+            api_base_url: Some(server.base.clone()),
+            token: "tok".into(),
+            repo: Some("acme/a".into()),
+            ..Default::default()
+        };
+
+        for org in ["../user", "acme/team", "a b", ".."] {
+            let out = hosting_list_repos(req("github"), Some(org.into()));
+            assert_eq!(out.error.unwrap().code, "CFG-0401", "{}", org);
+            let out = hosting_create_repo(req("gitea"), Some(org.into()), "x".into(), None, None);
+            assert_eq!(out.error.unwrap().code, "CFG-0401", "{}", org);
+        }
+        assert!(hosting_list_repos(req("gitlab"), Some("acme/../x".into()))
+            .error
+            .is_some());
+        for sha in ["../../x", "abc", "g123456", "1234567/status"] {
+            let out = hosting_commit_status(req("github"), sha.into());
+            assert_eq!(out.error.unwrap().code, "CFG-0001", "{}", sha);
+        }
+        assert!(server.requests().is_empty());
+
+        // GitLab のサブグループはそのまま通る
+        assert!(hosting_list_repos(req("gitlab"), Some("acme/team".into())).ok);
+        assert!(hosting_list_repos(req("github"), Some("acme".into())).ok);
+        assert_eq!(server.requests().len(), 2);
+    }
+}
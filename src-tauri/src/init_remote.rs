@@ -0,0 +1,397 @@
+// init_local_repo の SSH 版: remote_path に mkdir → git init → symbolic-ref HEAD → origin を ssh_run で行う
+use crate::{
+    classify_remote_failure, shell_escape_posix_single, ssh_exe, ssh_run, ssh_run_with,
+    ActionError, ActionOutcome, SshConfig, StepResult,
+};
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct PostReceiveConfig {
+    // hooks/post-receive の中身（そのまま書く）
+    script: Option<String>,
+    // script が無いとき: push されたら defaultBranch をこのディレクトリに checkout する
+    work_tree: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InitRemoteRequest {
+    #[serde(default)]
+    ssh_path: Option<String>,
+    ssh: SshConfig,
+    remote_path: String,
+    #[serde(default)]
+    repo_url: Option<String>,
+    #[serde(default)]
+    default_branch: Option<String>,
+    // git init --bare
+    #[serde(default)]
+    bare: bool,
+    // git init --shared=<value>（group / all / 0660 など）
+    #[serde(default)]
+    shared: Option<String>,
+    // bare のみ
+    #[serde(default)]
+    post_receive: Option<PostReceiveConfig>,
+    // chown -R <owner>（user / user:group）。root でなければ sudo -n で実行する
+    #[serde(default)]
+    owner: Option<String>,
+    // chmod -R <mode>（g+rwX / 750 など）
+    #[serde(default)]
+    mode: Option<String>,
+}
+
+fn init_err(severity: &str, code: &str, message: &str, detail: Option<String>) -> ActionError {
+    ActionError {
+        code: code.into(),
+        severity: severity.into(),
+        message: message.into(),
+        detail,
+    }
+}
+
+fn opt(s: &Option<String>) -> Option<&str> {
+    s.as_deref().map(str::trim).filter(|x| !x.is_empty())
+}
+
+fn valid_owner(s: &str) -> bool {
+    s.chars()
+        .all(|c| c.is_ascii_alphanumeric() || "_-.:".contains(c))
+        && !s.starts_with('-')
+}
+
+fn valid_mode(s: &str) -> bool {
+    s.chars().all(|c| "01234567ugoarwxXst+-=,".contains(c)) && !s.starts_with('-')
+}
+
+fn valid_shared(s: &str) -> bool {
+    s.chars().all(|c| c.is_ascii_alphanumeric()) && !s.is_empty()
+}
+
+// script が無ければ workTree に checkout するフックを作る（どちらも無ければ None）
+fn post_receive_script(pr: &PostReceiveConfig, branch: &str) -> Option<String> {
+    match (opt(&pr.script), opt(&pr.work_tree)) {
+        (Some(s), _) => Some(format!("{}\n", s.trim_end())),
+        (None, Some(wt)) => Some(format!(
+            "#!/bin/sh\n# gitshlc: check out pushed {b} into the work tree\nwhile read -r old new ref; do\n  if [ \"$ref\" = {r} ]; then\n    mkdir -p {wt} && git --work-tree={wt} --git-dir=\"$(pwd)\" checkout -f {b}\n  fi\ndone\n",
+            b = shell_escape_posix_single(branch),
+            r = shell_escape_posix_single(&format!("refs/heads/{}", branch)),
+            wt = shell_escape_posix_single(wt),
+        )),
+        (None, None) => None,
+    }
+}
+
+// 失敗した step の stderr から接続エラーか init の失敗かを分ける
+fn init_failure(failed: &StepResult) -> ActionError {
+    let conn = classify_remote_failure(failed);
+    if conn.code != "SSH-0200" {
+        return conn;
+    }
+    init_err(
+        "ERROR",
+        "GIT-0499",
+        "init failed (see steps)",
+        Some(failed.stderr.trim().to_string()),
+    )
+}
+
+fn push_ok(steps: &mut Vec<StepResult>, step: StepResult) -> bool {
+    let ok = step.ok;
+    steps.push(step);
+    ok
+}
+
+/// remote_path に repo を作る（既に初期化済みなら GIT-0403 / INFO で何もしない）
+#[tauri::command(async, rename_all = "camelCase")]
+pub(crate) fn init_remote_repo(req: InitRemoteRequest) -> ActionOutcome {
+    let mut steps: Vec<StepResult> = Vec::new();
+    let outcome = |ok: bool, steps: Vec<StepResult>, error: Option<ActionError>| ActionOutcome {
+        ok,
+        mode: "ssh".into(),
+        env_key: "init".into(),
+        action: "init".into(),
+        steps,
+        error,
+    };
+    let fail = |steps: Vec<StepResult>, e: ActionError| outcome(false, steps, Some(e));
+    // 途中で失敗したらそこで止める（作りかけの repo に chown -R / chmod -R をかけない）
+    let stopped = |steps: Vec<StepResult>| {
+        let e = steps.last().map(init_failure);
+        outcome(false, steps, e)
+    };
+
+    let Some(ssh) = ssh_exe(req.ssh_path.clone()) else {
+        return fail(
+            steps,
+            init_err(
+                "FATAL",
+                "SSH-0001",
+                "ssh not found. Run preflight and set sshPath if needed.",
+                None,
+            ),
+        );
+    };
+    let cfg = &req.ssh;
+    if cfg.host.trim().is_empty() || cfg.user.trim().is_empty() {
+        return fail(
+            steps,
+            init_err("ERROR", "CFG-0302", "ssh host/user is required", None),
+        );
+    }
+    let rp = req.remote_path.trim().to_string();
+    if rp.is_empty() {
+        return fail(
+            steps,
+            init_err("ERROR", "GIT-0401", "remotePath is required", None),
+        );
+    }
+
+    // 先に引数を検証しておく（途中まで作ってから失敗しないように）
+    for (name, value, ok) in [
+        ("owner", opt(&req.owner), opt(&req.owner).map(valid_owner)),
+        ("mode", opt(&req.mode), opt(&req.mode).map(valid_mode)),
+        (
+            "shared",
+            opt(&req.shared),
+            opt(&req.shared).map(valid_shared),
+        ),
+    ] {
+        if ok == Some(false) {
+            return fail(
+                steps,
+                init_err(
+                    "ERROR",
+                    "CFG-0001",
+                    &format!("invalid {}", name),
+                    value.map(str::to_string),
+                ),
+            );
+        }
+    }
+    if req.post_receive.is_some() && !req.bare {
+        return fail(
+            steps,
+            init_err("ERROR", "CFG-0001", "postReceive requires bare=true", None),
+        );
+    }
+    let branch = opt(&req.default_branch).unwrap_or("main").to_string();
+    let hook = match req
+        .post_receive
+        .as_ref()
+        .map(|pr| post_receive_script(pr, &branch))
+    {
+        Some(None) => {
+            return fail(
+                steps,
+                init_err(
+                    "ERROR",
+                    "CFG-0001",
+                    "postReceive.script or postReceive.workTree is required",
+                    None,
+                ),
+            )
+        }
+        h => h.flatten(),
+    };
+
+    let p = shell_escape_posix_single(&rp);
+    let in_repo = |body: &str| format!("cd {} && {}", p, body);
+
+    // mkdir -p
+    let mk = ssh_run(&ssh, cfg, &format!("mkdir -p {}", p));
+    if !mk.ok {
+        let conn = classify_remote_failure(&mk);
+        steps.push(mk);
+        if conn.code != "SSH-0200" {
+            return fail(steps, conn);
+        }
+        return fail(
+            steps,
+            init_err("ERROR", "GIT-0402", "failed to create directory", None),
+        );
+    }
+    steps.push(mk);
+
+    // 初期化済み？（bare は HEAD + objects/、それ以外は .git）
+    let check = if req.bare {
+        in_repo("test -f HEAD && test -d objects")
+    } else {
+        in_repo("test -e .git")
+    };
+    let exists = ssh_run(&ssh, cfg, &check);
+    if exists.ok {
+        steps.push(StepResult {
+            ok: true,
+            cmd: format!("[skip] already initialized: {}", rp),
+            cwd: Some(rp.clone()),
+            exit_code: 0,
+            stdout: "".into(),
+            stderr: "".into(),
+        });
+        return outcome(
+            true,
+            steps,
+            Some(init_err(
+                "INFO",
+                "GIT-0403",
+                if req.bare {
+                    "bare repository already exists (already initialized)"
+                } else {
+                    ".git already exists (already initialized)"
+                },
+                None,
+            )),
+        );
+    }
+
+    // git init
+    let mut init = String::from("git init");
+    if req.bare {
+        init.push_str(" --bare");
+    }
+    if let Some(s) = opt(&req.shared) {
+        init.push_str(&format!(" --shared={}", s));
+    }
+    if !push_ok(&mut steps, ssh_run(&ssh, cfg, &in_repo(&init))) {
+        return stopped(steps);
+    }
+
+    // default branch（init --initial-branch は古い git に無いので symbolic-ref）
+    let head = in_repo(&format!(
+        "git symbolic-ref HEAD {}",
+        shell_escape_posix_single(&format!("refs/heads/{}", branch))
+    ));
+    if !push_ok(&mut steps, ssh_run(&ssh, cfg, &head)) {
+        return stopped(steps);
+    }
+
+    // origin（repoUrl があるときだけ）
+    if let Some(url) = opt(&req.repo_url) {
+        let rem = ssh_run(&ssh, cfg, &in_repo("git remote"));
+        let has_origin = rem.stdout.lines().any(|l| l.trim() == "origin");
+        if !push_ok(&mut steps, rem) {
+            return stopped(steps);
+        }
+        let sub = if has_origin { "set-url" } else { "add" };
+        let set = in_repo(&format!(
+            "git remote {} origin {}",
+            sub,
+            shell_escape_posix_single(url)
+        ));
+        if !push_ok(&mut steps, ssh_run(&ssh, cfg, &set)) {
+            return stopped(steps);
+        }
+    }
+
+    // post-receive（bare のみ）
+    if let Some(script) = &hook {
+        let install = ssh_run_with(
+            &ssh,
+            cfg,
+            &in_repo("cat > hooks/post-receive && chmod 755 hooks/post-receive"),
+            false,
+            Some(script),
+        );
+        if !push_ok(&mut steps, install) {
+            return stopped(steps);
+        }
+    }
+
+    // 所有者・権限は最後に（init で作ったファイルも含めて）
+    if let Some(owner) = opt(&req.owner) {
+        let o = shell_escape_posix_single(owner);
+        let chown = format!(
+            "if [ \"$(id -u)\" = 0 ]; then chown -R {o} {p}; else sudo -n chown -R {o} {p}; fi",
+            o = o,
+            p = p
+        );
+        if !push_ok(&mut steps, ssh_run(&ssh, cfg, &chown)) {
+            return stopped(steps);
+        }
+    }
+    if let Some(mode) = opt(&req.mode) {
+        let chmod = format!("chmod -R {} {}", shell_escape_posix_single(mode), p);
+        if !push_ok(&mut steps, ssh_run(&ssh, cfg, &chmod)) {
+            return stopped(steps);
+        }
+    }
+
+    outcome(true, steps, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook(script: Option<&str>, work_tree: Option<&str>) -> Option<String> {
+        let pr = PostReceiveConfig {
+            script: script.map(String::from),
+            work_tree: work_tree.map(String::from),
+        };
+        post_receive_script(&pr, "main")
+    }
+
+    #[test]
+    fn post_receive_needs_script_or_work_tree() {
+        assert_eq!(hook(None, None), None);
+        assert_eq!(hook(Some("  "), Some("")), None);
+        assert_eq!(
+            hook(Some("#!/bin/sh\necho hi\n\n"), Some("/srv/www")).as_deref(),
+            Some("#!/bin/sh\necho hi\n")
+        );
+
+        let s = hook(None, Some("/srv/my www")).unwrap();
+        assert!(s.contains("if [ \"$ref\" = 'refs/heads/main' ]"));
+        assert!(s.contains("git --work-tree='/srv/my www'"));
+    }
+
+    // ssh の代わりに最後の引数（remote のコマンド）をローカルの sh で実行する。fail_on を含むコマンドは失敗させる
+    #[cfg(unix)]
+    fn fake_ssh(dir: &std::path::Path, name: &str, fail_on: &str) -> String {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join(name);
+        let script = format!(
+            "#!/bin/sh\nfor a; do cmd=$a; done\ncase \"$cmd\" in *{}*) echo boom >&2; exit 1;; esac\nexec sh -c \"$cmd\"\n",
+            shell_escape_posix_single(fail_on)
+        );
+        std::fs::write(&path, script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        crate::path_to_string(&path)
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn stops_at_the_first_failed_step() {
+        let root = std::env::temp_dir().join(format!("gitshlc-init-remote-{}", std::process::id()));
+        std::fs::remove_dir_all(&root).ok();
+        std::fs::create_dir_all(&root).unwrap();
+        let request = |ssh: &str, repo: &str| -> InitRemoteRequest {
+            serde_json::from_value(serde_json::json!({
+                "sshPath": ssh,
+                "ssh": { "host": "example.com", "user": "deploy" },
+                "remotePath": crate::path_to_string(&root.join(repo)),
+                "repoUrl": "git@example.com:acme/app.git",
+                "mode": "go-w",
+            }))
+            .unwrap()
+        };
+        let ran = |o: &ActionOutcome, what: &str| o.steps.iter().any(|s| s.cmd.contains(what));
+
+        let failing = fake_ssh(&root, "ssh-fail", "git remote add");
+        let o = init_remote_repo(request(&failing, "broken"));
+        assert!(!o.ok);
+        assert_eq!(o.error.as_ref().unwrap().code, "GIT-0499");
+        assert!(o.steps.last().unwrap().cmd.contains("git remote add"));
+        assert!(!ran(&o, "chmod -R"));
+
+        let ssh = fake_ssh(&root, "ssh-ok", "no such command");
+        let o = init_remote_repo(request(&ssh, "app"));
+        assert!(o.ok, "{:?}", o.error);
+        assert!(o.steps.last().unwrap().cmd.contains("chmod -R"));
+        let config = std::fs::read_to_string(root.join("app/.git/config")).unwrap();
+        assert!(config.contains("git@example.com:acme/app.git"));
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
@@ -1,23 +1,62 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     env,
+    io::{Read, Write},
     path::{Path, PathBuf},
     process::{Command, Stdio},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
+mod api;
+mod branch;
+mod ci_gate;
+mod clone;
+mod deploy_key;
+mod diagnostics;
+mod fanout;
+mod fix;
+mod git_version;
+mod gitdir;
+mod github;
+mod hosting;
+mod init_remote;
+mod ls_remote;
+#[cfg(test)]
+mod mock_http;
+mod pull_request;
+mod release;
+mod remote_url;
+mod repo_target;
+mod scaffold;
+mod scan;
+mod scan_cache;
+mod scan_profile;
+mod webhook;
+
 #[tauri::command]
 fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
-#[tauri::command(rename_all = "camelCase")]
+#[tauri::command(async, rename_all = "camelCase")]
 fn init_local_repo(
+    app: tauri::AppHandle,
     git_path: Option<String>,
     local_path: String,
     repo_url: Option<String>,
     default_branch: Option<String>,
+    scaffold: Option<scaffold::ScaffoldOptions>,
+    github_repo: Option<github::InitGitHubRepo>,
 ) -> ActionOutcome {
     let mut steps: Vec<StepResult> = Vec::new();
+    let fail = |steps: Vec<StepResult>, e: ActionError| ActionOutcome {
+        ok: false,
+        mode: "local".into(),
+        env_key: "init".into(),
+        action: "init".into(),
+        steps,
+        error: Some(e),
+    };
 
     let Some(git) = git_exe(git_path) else {
         return ActionOutcome {
@@ -52,6 +91,42 @@ fn init_local_repo(
         };
     }
 
+    // GitHub に作る場合は repoUrl と併用しない
+    let mut push_auth = ls_remote::GitAuth::default();
+    if let Some(gh) = &github_repo {
+        if let Err(e) = gh.validate() {
+            return fail(steps, e);
+        }
+        if repo_url.as_deref().is_some_and(|u| !u.trim().is_empty()) {
+            return fail(
+                steps,
+                ActionError {
+                    severity: "ERROR".into(),
+                    code: "CFG-0001".into(),
+                    message: "repoUrl and githubRepo are exclusive".into(),
+                    detail: None,
+                },
+            );
+        }
+        match gh.push_auth(|| ls_remote::askpass_path(&app)) {
+            Ok(a) => push_auth = a,
+            Err(e) => return fail(steps, e),
+        }
+    }
+
+    // 雛形の指定も mkdir / git init / GitHub の作成より前に確認する。GitHub に作った場合は scaffold が無くても commit して push する
+    let scaffold = match (scaffold, &github_repo) {
+        (s, Some(gh)) if gh.push() => Some(s.unwrap_or_default().with_push()),
+        (s, _) => s,
+    };
+    if let Some(opts) = &scaffold {
+        let has_origin =
+            github_repo.is_some() || repo_url.as_deref().is_some_and(|u| !u.trim().is_empty());
+        if let Err(e) = opts.validate(has_origin) {
+            return fail(steps, e);
+        }
+    }
+
     let dir = PathBuf::from(&lp);
 
     if !dir.exists() {
@@ -127,10 +202,36 @@ fn init_local_repo(
         ));
     }
 
+    // GitHub に repo を作ってその URL を origin にする（ローカルの init が通ってから）
+    let mut repo_url = repo_url;
+    if let Some(gh) = &github_repo {
+        if !steps.iter().all(|s| s.ok) {
+            return fail(
+                steps,
+                ActionError {
+                    severity: "ERROR".into(),
+                    code: "GIT-0499".into(),
+                    message: "init failed (see steps)".into(),
+                    detail: None,
+                },
+            );
+        }
+        let dir_name = dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match gh.create(&dir_name, &mut steps) {
+            Ok(url) => repo_url = Some(url),
+            Err(e) => return fail(steps, e),
+        }
+    }
+
     // origin設定（repoUrlがあるときだけ）
+    let mut origin_set = false;
     if let Some(url) = repo_url {
         let url = url.trim().to_string();
         if !url.is_empty() {
+            origin_set = true;
             let rem = run_capture(&git, &["remote"], Some(&dir));
             let has_origin = rem.ok && rem.stdout.lines().any(|l| l.trim() == "origin");
             steps.push(rem);
@@ -153,6 +254,15 @@ fn init_local_repo(
 
     let ok = steps.iter().all(|s| s.ok);
 
+    // 雛形と最初の commit（init が成功したときだけ）
+    if let (true, Some(opts)) = (ok, scaffold.as_ref()) {
+        if let Err(e) = scaffold::apply(
+            &git, &dir, &branch, origin_set, opts, &push_auth, &mut steps,
+        ) {
+            return fail(steps, e);
+        }
+    }
+
     ActionOutcome {
         ok,
         mode: "local".into(),
@@ -190,6 +300,10 @@ struct PreflightResult {
     platform: String,
     git: ToolCheck,
     ssh: ToolCheck,
+    // git --version から判定した capability（git が見つからなければ None）
+    git_capabilities: Option<git_version::GitCapabilities>,
+    // identity / credential helper / safe.directory / ssh-agent / CRLF
+    diagnostics: Option<diagnostics::DiagnosticsReport>,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -381,18 +495,52 @@ fn resolve_executable(
 }
 
 fn run_capture(exe: &Path, args: &[&str], cwd: Option<&Path>) -> StepResult {
+    run_capture_input(exe, args, cwd, None)
+}
+
+// input は stdin に流して閉じる（トークン等をコマンドラインに載せないため）
+fn run_capture_input(
+    exe: &Path,
+    args: &[&str],
+    cwd: Option<&Path>,
+    input: Option<&str>,
+) -> StepResult {
+    run_capture_env(exe, args, cwd, input, &[])
+}
+
+// envs はこのプロセスにだけ渡す（askpass 用のトークン等。StepResult の cmd には出ない）
+fn run_capture_env(
+    exe: &Path,
+    args: &[&str],
+    cwd: Option<&Path>,
+    input: Option<&str>,
+    envs: &[(&str, &str)],
+) -> StepResult {
     let mut cmd = Command::new(exe);
     cmd.args(args);
+    cmd.envs(envs.iter().copied());
 
     // 対話プロンプトで固まるのを防ぐ（Gitが認証を要求しても即失敗させる）
-    cmd.stdin(Stdio::null());
     cmd.env("GIT_TERMINAL_PROMPT", "0");
+    cmd.stdout(Stdio::piped());
+    cmd.stderr(Stdio::piped());
+    if input.is_some() {
+        cmd.stdin(Stdio::piped());
+    } else {
+        cmd.stdin(Stdio::null());
+    }
 
     if let Some(d) = cwd {
         cmd.current_dir(d);
     }
 
-    let output = cmd.output();
+    let output = cmd.spawn().and_then(|mut child| {
+        if let (Some(data), Some(mut stdin)) = (input, child.stdin.take()) {
+            // 読まれずに終了した場合の BrokenPipe は無視してよい
+            let _ = stdin.write_all(data.as_bytes());
+        }
+        child.wait_with_output()
+    });
     match output {
         Ok(o) => {
             let stdout = String::from_utf8_lossy(&o.stdout).to_string();
@@ -434,6 +582,119 @@ fn run_capture(exe: &Path, args: &[&str], cwd: Option<&Path>) -> StepResult {
     }
 }
 
+// git clone --progress などの stderr を 1 行ずつ（\r 区切りの進捗も）on_line に流す。
+// StepResult の stderr には \n で終わった行だけ残す（進捗の途中経過は捨てる）
+fn run_capture_stream(
+    exe: &Path,
+    args: &[&str],
+    cwd: Option<&Path>,
+    input: Option<&str>,
+    on_line: &mut dyn FnMut(&str),
+) -> StepResult {
+    let cmd_str = format!(
+        "{} {}",
+        exe.display(),
+        args.iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+
+    let mut cmd = Command::new(exe);
+    cmd.args(args);
+    cmd.env("GIT_TERMINAL_PROMPT", "0");
+    cmd.stdout(Stdio::piped());
+    cmd.stderr(Stdio::piped());
+    cmd.stdin(if input.is_some() {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    });
+    if let Some(d) = cwd {
+        cmd.current_dir(d);
+    }
+
+    let mut child = match cmd.spawn() {
+        Ok(c) => c,
+        Err(e) => {
+            return StepResult {
+                cmd: cmd_str,
+                cwd: cwd.map(path_to_string),
+                ok: false,
+                exit_code: -1,
+                stdout: "".into(),
+                stderr: e.to_string(),
+            }
+        }
+    };
+    if let (Some(data), Some(mut stdin)) = (input, child.stdin.take()) {
+        let _ = stdin.write_all(data.as_bytes());
+    }
+
+    let stdout_thread = child.stdout.take().map(|mut out| {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = out.read_to_end(&mut buf);
+            String::from_utf8_lossy(&buf).to_string()
+        })
+    });
+
+    let mut kept = String::new();
+    if let Some(mut err) = child.stderr.take() {
+        let mut pending: Vec<u8> = Vec::new();
+        let mut chunk = [0u8; 4096];
+        loop {
+            let n = match err.read(&mut chunk) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+            for &b in &chunk[..n] {
+                if b == b'\r' || b == b'\n' {
+                    let line = String::from_utf8_lossy(&pending).to_string();
+                    pending.clear();
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    on_line(line.trim_end());
+                    if b == b'\n' {
+                        kept.push_str(&line);
+                        kept.push('\n');
+                    }
+                } else {
+                    pending.push(b);
+                }
+            }
+        }
+        if !pending.is_empty() {
+            let line = String::from_utf8_lossy(&pending).to_string();
+            on_line(line.trim_end());
+            kept.push_str(&line);
+        }
+    }
+
+    let stdout = stdout_thread
+        .and_then(|t| t.join().ok())
+        .unwrap_or_default();
+    match child.wait() {
+        Ok(status) => StepResult {
+            cmd: cmd_str,
+            cwd: cwd.map(path_to_string),
+            ok: status.success(),
+            exit_code: status.code().unwrap_or(-1),
+            stdout,
+            stderr: kept,
+        },
+        Err(e) => StepResult {
+            cmd: cmd_str,
+            cwd: cwd.map(path_to_string),
+            ok: false,
+            exit_code: -1,
+            stdout,
+            stderr: e.to_string(),
+        },
+    }
+}
+
 fn run_version(exe: &Path, args: &[&str]) -> (bool, Option<String>, Option<String>) {
     let step = run_capture(exe, args, None);
     let has_stdout = !step.stdout.trim().is_empty();
@@ -488,8 +749,12 @@ fn check_tool(
     }
 }
 
-#[tauri::command(rename_all = "camelCase")]
-fn preflight(git_path: Option<String>, ssh_path: Option<String>) -> PreflightResult {
+#[tauri::command(async, rename_all = "camelCase")]
+fn preflight(
+    git_path: Option<String>,
+    ssh_path: Option<String>,
+    local_path: Option<String>,
+) -> PreflightResult {
     let git_known: Vec<&str> = if is_windows() {
         vec![
             r"C:\Program Files\Git\cmd\git.exe",
@@ -507,13 +772,24 @@ fn preflight(git_path: Option<String>, ssh_path: Option<String>) -> PreflightRes
         vec![]
     };
 
-    let git = check_tool(git_path, "git", &git_known, &["--version"]);
-    let ssh = check_tool(ssh_path, "ssh", &ssh_known, &["-V"]);
+    let git = check_tool(git_path.clone(), "git", &git_known, &["--version"]);
+    let ssh = check_tool(ssh_path.clone(), "ssh", &ssh_known, &["-V"]);
+
+    let diagnostics = git_exe(git_path).map(|g| {
+        diagnostics::local_report(&g, ssh_exe(ssh_path).as_deref(), local_path.as_deref())
+    });
+
+    let git_capabilities = git
+        .version
+        .as_deref()
+        .map(git_version::GitCapabilities::from_version_output);
 
     PreflightResult {
         platform: env::consts::OS.to_string(),
         git,
         ssh,
+        git_capabilities,
+        diagnostics,
     }
 }
 
@@ -569,18 +845,40 @@ fn shell_escape_posix_single(s: &str) -> String {
 }
 
 fn ssh_run(ssh: &Path, cfg: &SshConfig, remote_cmd: &str) -> StepResult {
+    ssh_run_with(ssh, cfg, remote_cmd, false, None)
+}
+
+// forward_agent: その1コマンドだけ -A を付ける（常時 ForwardAgent にはしない）
+fn ssh_run_with(
+    ssh: &Path,
+    cfg: &SshConfig,
+    remote_cmd: &str,
+    forward_agent: bool,
+    input: Option<&str>,
+) -> StepResult {
+    let args = ssh_args(cfg, remote_cmd, forward_agent);
+    let arg_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    run_capture_input(ssh, &arg_refs, None, input)
+}
+
+fn ssh_args(cfg: &SshConfig, remote_cmd: &str, forward_agent: bool) -> Vec<String> {
     let target = format!("{}@{}", cfg.user, cfg.host);
     let port = cfg.port.unwrap_or(22);
 
-    let mut args: Vec<String> = Vec::new();
-    args.push("-p".into());
-    args.push(port.to_string());
-    args.push("-o".into());
-    args.push("BatchMode=yes".into());
-    args.push("-o".into());
-    args.push("ConnectTimeout=5".into());
-    args.push("-o".into());
-    args.push("ConnectionAttempts=1".into());
+    let mut args: Vec<String> = vec![
+        "-p".into(),
+        port.to_string(),
+        "-o".into(),
+        "BatchMode=yes".into(),
+        "-o".into(),
+        "ConnectTimeout=5".into(),
+        "-o".into(),
+        "ConnectionAttempts=1".into(),
+    ];
+
+    if forward_agent {
+        args.push("-A".into());
+    }
 
     if let Some(k) = &cfg.key_path {
         if !k.trim().is_empty() {
@@ -592,85 +890,397 @@ fn ssh_run(ssh: &Path, cfg: &SshConfig, remote_cmd: &str) -> StepResult {
     args.push(target);
     args.push("--".into());
     args.push(remote_cmd.into());
-
-    let arg_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
-    run_capture(ssh, &arg_refs, None)
+    args
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
+// --- remote credential (server -> origin) ---
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct BranchListWire {
-    ok: bool,
-    branches: Vec<String>,
-    stderr: Option<String>,
+struct RemoteAuthConfig {
+    // none | agent | key | token
+    strategy: String,
+    // key: サーバー上の秘密鍵パス（~/ 可）
+    key_path: Option<String>,
+    // token: HTTPS 用。stdin 経由で渡すのでコマンドラインには残らない
+    token: Option<String>,
+    token_user: Option<String>,
 }
 
-#[tauri::command(rename_all = "camelCase")]
-fn list_branches(repo_url: String, git_path: Option<String>) -> BranchListWire {
-    let git = match git_exe(git_path) {
-        Some(p) => p,
-        None => {
-            return BranchListWire {
-                ok: false,
-                branches: vec![],
-                stderr: Some("git not found".into()),
+// origin と通信する git コマンド（fetch/pull/push）をどう組み立てるか
+#[derive(Debug, Clone, Default)]
+struct RemoteAuthPlan {
+    forward_agent: bool,
+    // `cd <path> && ` の直後に入るシェル断片
+    prelude: String,
+    // `git` の直後に入る -c オプション
+    git_opts: String,
+    stdin: Option<String>,
+}
+
+fn remote_auth_plan(auth: Option<&RemoteAuthConfig>) -> Result<RemoteAuthPlan, ActionError> {
+    let Some(a) = auth else {
+        return Ok(RemoteAuthPlan::default());
+    };
+
+    let cfg_err = |message: &str| ActionError {
+        code: "CFG-0310".into(),
+        severity: "ERROR".into(),
+        message: message.into(),
+        detail: Some(a.strategy.clone()),
+    };
+
+    match a.strategy.trim() {
+        "" | "none" => Ok(RemoteAuthPlan::default()),
+        "agent" => Ok(RemoteAuthPlan {
+            forward_agent: true,
+            ..Default::default()
+        }),
+        "key" => {
+            let key = a.key_path.clone().unwrap_or_default();
+            let key = key.trim();
+            if key.is_empty() {
+                return Err(cfg_err("remoteAuth.keyPath is required for strategy=key"));
             }
+            // GIT_SSH_COMMAND は git がシェル経由で実行するので、~/ は $HOME に置き換える
+            let key_arg = match key.strip_prefix("~/") {
+                Some(rest) => format!("\"$HOME\"/{}", shell_escape_posix_single(rest)),
+                None => shell_escape_posix_single(key),
+            };
+            let ssh_cmd = format!("ssh -i {} -o IdentitiesOnly=yes -o BatchMode=yes", key_arg);
+            Ok(RemoteAuthPlan {
+                prelude: format!("GIT_SSH_COMMAND={} ", shell_escape_posix_single(&ssh_cmd)),
+                ..Default::default()
+            })
+        }
+        "token" => {
+            let token = a.token.clone().unwrap_or_default();
+            let token = token.trim();
+            if token.is_empty() {
+                return Err(cfg_err("remoteAuth.token is required for strategy=token"));
+            }
+            let user = a
+                .token_user
+                .clone()
+                .filter(|u| !u.trim().is_empty())
+                .unwrap_or_else(|| "x-access-token".into());
+            let user = user.trim();
+            // helper はサーバー上で sh が解釈するので、シェルに意味のある文字は通さない
+            if !user
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "._@-".contains(c))
+            {
+                return Err(cfg_err(
+                    "remoteAuth.tokenUser may only contain letters, digits and ._@-",
+                ));
+            }
+            let helper = format!(
+                "!f() {{ echo username={}; echo \"password=$GITSHLC_TOKEN\"; }}; f",
+                user
+            );
+            Ok(RemoteAuthPlan {
+                prelude: "IFS= read -r GITSHLC_TOKEN && export GITSHLC_TOKEN && ".into(),
+                // 既存の credential.helper を一旦リセットしてから差し込む
+                git_opts: format!(
+                    "-c credential.helper= -c {} ",
+                    shell_escape_posix_single(&format!("credential.helper={}", helper))
+                ),
+                stdin: Some(format!("{}\n", token)),
+                ..Default::default()
+            })
         }
+        _ => Err(cfg_err(
+            "unknown remoteAuth.strategy (expected none|agent|key|token)",
+        )),
+    }
+}
+
+// --- run as another OS user (sudo -n -u / su) ---
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RunAsConfig {
+    user: String,
+    // sudo (default) | su
+    method: Option<String>,
+}
+
+fn validate_run_as(run_as: Option<&RunAsConfig>, plan: &RemoteAuthPlan) -> Result<(), ActionError> {
+    let Some(r) = run_as else {
+        return Ok(());
+    };
+    let cfg_err = |message: &str, detail: Option<String>| ActionError {
+        code: "CFG-0311".into(),
+        severity: "ERROR".into(),
+        message: message.into(),
+        detail,
     };
+    if r.user.trim().is_empty() {
+        return Err(cfg_err("runAs.user is required", None));
+    }
+    let method = r.method.as_deref().unwrap_or("sudo").trim();
+    if method != "sudo" && method != "su" {
+        return Err(cfg_err(
+            "unknown runAs.method (expected sudo|su)",
+            Some(method.to_string()),
+        ));
+    }
+    // 転送された agent のソケットはログインユーザーのものなので別ユーザーからは使えない
+    if plan.forward_agent {
+        return Err(cfg_err(
+            "remoteAuth.strategy=agent cannot be combined with runAs",
+            Some(r.user.clone()),
+        ));
+    }
+    Ok(())
+}
 
-    let step = run_capture(&git, &["ls-remote", "--heads", &repo_url], None);
+// --- wrapped mode (docker exec / kubectl exec / nsenter ...) ---
 
-    if !step.ok {
-        let msg = if !step.stderr.trim().is_empty() {
-            step.stderr
-        } else {
-            step.stdout
-        };
-        return BranchListWire {
-            ok: false,
-            branches: vec![],
-            stderr: Some(msg),
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WrapperConfig {
+    // e.g. "docker exec -i -w {path} app" / "kubectl exec -i deploy/app --"
+    // {path} は remotePath（エスケープ済み）に置換。token 認証は stdin を使うので -i が必要
+    template: String,
+    // true: ssh 先で wrapper を実行する / false: このマシンで実行する
+    #[serde(default)]
+    via_ssh: bool,
+}
+
+fn expand_wrapper(template: &str, path: &str) -> String {
+    template
+        .trim()
+        .replace("{path}", &shell_escape_posix_single(path))
+}
+
+enum Transport {
+    Ssh { exe: PathBuf, cfg: SshConfig },
+    Local { sh: PathBuf },
+}
+
+// remote_path で実行するシェル断片の組み立てと実行（経路・wrapper・run-as・origin 認証をまとめて持つ）
+struct RemoteRepo<'a> {
+    transport: Transport,
+    path: &'a str,
+    run_as: Option<&'a RunAsConfig>,
+    wrapper: Option<&'a str>,
+    auth: &'a RemoteAuthPlan,
+}
+
+impl RemoteRepo<'_> {
+    // 外側から: [wrapper] [sudo/su] cd <path> && <body>
+    fn command(&self, body: &str) -> String {
+        let inner = format!("cd {} && {}", shell_escape_posix_single(self.path), body);
+        self.wrap(inner)
+    }
+
+    // [wrapper] [sudo/su] <inner>（cd はしない）
+    fn wrap(&self, inner: String) -> String {
+        let inner = match self.run_as {
+            None => inner,
+            Some(r) if r.method.as_deref().map(str::trim) == Some("su") => format!(
+                "su -s /bin/sh {} -c {}",
+                shell_escape_posix_single(r.user.trim()),
+                shell_escape_posix_single(&inner)
+            ),
+            // -n: パスワードを求められたら待たずに失敗させる / -H: HOME も対象ユーザーにする
+            Some(r) => format!(
+                "sudo -n -H -u {} -- sh -c {}",
+                shell_escape_posix_single(r.user.trim()),
+                shell_escape_posix_single(&inner)
+            ),
         };
+        match self.wrapper {
+            None => inner,
+            Some(t) => format!(
+                "{} sh -c {}",
+                expand_wrapper(t, self.path),
+                shell_escape_posix_single(&inner)
+            ),
+        }
     }
 
-    let mut out: Vec<String> = Vec::new();
-    for line in step.stdout.lines() {
-        // <sha>\trefs/heads/<branch>
-        if let Some((_, r)) = line.split_once('\t') {
-            if let Some(b) = r.strip_prefix("refs/heads/") {
-                let name = b.trim().to_string();
-                if !name.is_empty() {
-                    out.push(name);
-                }
+    fn exec(&self, cmd: &str, forward_agent: bool, input: Option<&str>) -> StepResult {
+        match &self.transport {
+            Transport::Ssh { exe, cfg } => ssh_run_with(exe, cfg, cmd, forward_agent, input),
+            Transport::Local { sh } => run_capture_input(sh, &["-c", cmd], None, input),
+        }
+    }
+
+    fn run(&self, body: &str) -> StepResult {
+        self.exec(&self.command(body), false, None)
+    }
+
+    // ログイン直後のディレクトリで実行（remote_path がまだ無い / 関係ない処理用）
+    fn run_home(&self, body: &str) -> StepResult {
+        self.exec(&self.wrap(body.to_string()), false, None)
+    }
+
+    fn classify(&self, step: &StepResult) -> ActionError {
+        classify_failure(step, matches!(self.transport, Transport::Ssh { .. }))
+    }
+
+    // exec と同じだが stderr を逐次 on_line に流す（clone の進捗など）
+    fn exec_stream(
+        &self,
+        cmd: &str,
+        forward_agent: bool,
+        input: Option<&str>,
+        on_line: &mut dyn FnMut(&str),
+    ) -> StepResult {
+        match &self.transport {
+            Transport::Ssh { exe, cfg } => {
+                let args = ssh_args(cfg, cmd, forward_agent);
+                let refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+                run_capture_stream(exe, &refs, None, input, on_line)
             }
+            Transport::Local { sh } => run_capture_stream(sh, &["-c", cmd], None, input, on_line),
         }
     }
-    out.sort();
-    out.dedup();
 
-    BranchListWire {
-        ok: true,
-        branches: out,
-        stderr: None,
+    // origin と通信する git（認証方式はこのコマンドの間だけ有効）
+    fn git_origin(&self, git_args: &str) -> StepResult {
+        let body = format!(
+            "{}git {}{}",
+            self.auth.prelude, self.auth.git_opts, git_args
+        );
+        self.exec(
+            &self.command(&body),
+            self.auth.forward_agent,
+            self.auth.stdin.as_deref(),
+        )
+    }
+}
+
+fn looks_like_wrapper_failure(stderr: &str) -> bool {
+    let s = stderr.to_ascii_lowercase();
+    [
+        "error response from daemon",
+        "no such container",
+        "is not running",
+        "error from server",
+        "unable to upgrade connection",
+        "docker: not found",
+        "kubectl: not found",
+        "nsenter: not found",
+    ]
+    .iter()
+    .any(|p| s.contains(p))
+}
+
+fn looks_like_run_as_failure(stderr: &str) -> bool {
+    let s = stderr.to_ascii_lowercase();
+    [
+        "sudo: a password is required",
+        "sudo: a terminal is required",
+        "is not in the sudoers file",
+        "is not allowed to execute",
+        "sudo: unknown user",
+        "sudo: not found",
+        "sudo: command not found",
+        "su: authentication failure",
+        "su: must be run from a terminal",
+    ]
+    .iter()
+    .any(|p| s.contains(p))
+}
+
+fn looks_like_remote_auth_failure(stderr: &str) -> bool {
+    let s = stderr.to_ascii_lowercase();
+    [
+        "permission denied (publickey",
+        "could not read from remote repository",
+        "authentication failed",
+        "could not read username",
+        "could not read password",
+        "invalid username or password",
+        "repository not found",
+        "host key verification failed",
+        "the requested url returned error: 403",
+        "the requested url returned error: 401",
+    ]
+    .iter()
+    .any(|p| s.contains(p))
+}
+
+// ssh 自身の失敗は exit 255。それ以外で認証っぽい stderr なら server -> origin の認証失敗
+fn classify_remote_failure(step: &StepResult) -> ActionError {
+    classify_failure(step, true)
+}
+
+// via_ssh=false（wrapper をローカルで実行）なら 255 は wrapper 側の exit code なので接続失敗扱いしない
+fn classify_failure(step: &StepResult, via_ssh: bool) -> ActionError {
+    let detail = Some(format!("{}\n{}", step.cmd, step.stderr.trim()));
+    if via_ssh && step.exit_code == 255 {
+        return ActionError {
+            code: "SSH-0100".into(),
+            severity: "ERROR".into(),
+            message: "ssh connection failed".into(),
+            detail,
+        };
+    }
+    if looks_like_wrapper_failure(&step.stderr) {
+        return ActionError {
+            code: "WRAP-0100".into(),
+            severity: "ERROR".into(),
+            message: "wrapper command failed (container/pod not reachable?)".into(),
+            detail,
+        };
+    }
+    if looks_like_run_as_failure(&step.stderr) {
+        return ActionError {
+            code: "SUDO-0100".into(),
+            severity: "ERROR".into(),
+            message: "could not switch to runAs user non-interactively (check sudoers NOPASSWD)"
+                .into(),
+            detail,
+        };
+    }
+    if step.stderr.contains("dubious ownership") {
+        return ActionError {
+            code: "GIT-0110".into(),
+            severity: "ERROR".into(),
+            message: "repository is owned by another user (set runAs or safe.directory)".into(),
+            detail,
+        };
+    }
+    if looks_like_remote_auth_failure(&step.stderr) {
+        return ActionError {
+            code: "AUTH-0100".into(),
+            severity: "ERROR".into(),
+            message: "remote git could not authenticate to origin (check remoteAuth)".into(),
+            detail,
+        };
+    }
+    ActionError {
+        code: "SSH-0200".into(),
+        severity: "ERROR".into(),
+        message: "remote command failed".into(),
+        detail,
     }
 }
 
 // list_branches の直後に追加
 
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct DetectedRepo {
     path: String,
     origin_url: Option<String>,
     name: Option<String>,
-}
-
-fn should_skip_dir(name: &str) -> bool {
-    // speed / noise reduction
-    matches!(
-        name,
-        ".git" | "node_modules" | "target" | "dist" | "build" | ".venv" | ".idea" | ".vscode"
-    )
+    // normal | bare | worktree | submodule
+    #[serde(default)]
+    kind: String,
+    #[serde(default)]
+    remotes: Vec<gitdir::RemoteInfo>,
+    branch: Option<String>,
+    head_sha: Option<String>,
+    // committer date (ISO 8601)
+    head_date: Option<String>,
+    // None = 判定できなかった（bare / git 無し / 権限）
+    dirty: Option<bool>,
+    size_bytes: Option<u64>,
 }
 
 fn is_git_repo_dir(dir: &Path) -> bool {
@@ -701,111 +1311,27 @@ fn git_remote_origin_url(git: &Path, repo_dir: &Path) -> Option<String> {
     }
 }
 
-fn walk_detect_repos(dir: &Path, depth: u8, max_depth: u8, out: &mut Vec<PathBuf>) {
-    if depth > max_depth {
-        return;
-    }
-
-    let rd = match std::fs::read_dir(dir) {
-        Ok(v) => v,
-        Err(_) => return,
-    };
-
-    for entry in rd.flatten() {
-        let ft = match entry.file_type() {
-            Ok(v) => v,
-            Err(_) => continue,
-        };
-        if !ft.is_dir() {
-            continue;
-        }
-
-        let name = entry.file_name();
-        let name = name.to_string_lossy().to_string();
-        if should_skip_dir(&name) {
-            continue;
-        }
-
-        let p = entry.path();
-        if is_git_repo_dir(&p) {
-            out.push(p);
-            continue;
-        }
-
-        walk_detect_repos(&p, depth + 1, max_depth, out);
-    }
-}
-
-#[tauri::command(rename_all = "camelCase")]
+#[tauri::command(async, rename_all = "camelCase")]
 fn detect_local_repos(
+    app: tauri::AppHandle,
     root_path: String,
     max_depth: u8,
     git_path: Option<String>,
+    profile: Option<scan_profile::ScanProfile>,
 ) -> Result<Vec<DetectedRepo>, String> {
-    let p = normalize_path_input(&root_path);
-    let p = p.trim().to_string();
-    if p.is_empty() {
-        return Err("root_path is empty".to_string());
-    }
-
-    let root = PathBuf::from(&p);
-    if !root.exists() {
-        return Err(format!(
-            "root_path does not exist: {}",
-            path_to_string(&root)
-        ));
-    }
-    if !root.is_dir() {
-        return Err(format!(
-            "root_path is not a directory: {}",
-            path_to_string(&root)
-        ));
-    }
-
-    let md = max_depth.clamp(1, 50);
-    let git = git_exe(git_path);
-
-    let mut found: Vec<PathBuf> = Vec::new();
-
-    // root 自体が repo の場合も拾う
-    if is_git_repo_dir(&root) {
-        found.push(root.clone());
-    } else {
-        walk_detect_repos(&root, 0, md, &mut found);
-    }
-
-    found.sort();
-    found.dedup();
-
-    let mut out: Vec<DetectedRepo> = Vec::new();
-    for repo_dir in found {
-        let name = repo_dir
-            .file_name()
-            .and_then(|s| s.to_str())
-            .map(|s| s.to_string());
-
-        let origin_url = match &git {
-            Some(g) => git_remote_origin_url(g, &repo_dir),
-            None => None,
-        };
-
-        out.push(DetectedRepo {
-            path: path_to_string(&repo_dir),
-            origin_url,
-            name,
-        });
-    }
-
-    Ok(out)
+    // キャッシュ経由の差分スキャン（初回はフルスキャン）
+    scan_cache::refresh_local(&app, &root_path, max_depth, git_exe(git_path), profile)
 }
 
-#[tauri::command(rename_all = "camelCase")]
+#[tauri::command(async, rename_all = "camelCase")]
 fn detect_remote_repos(
+    app: tauri::AppHandle,
     ssh_path: Option<String>,
     ssh: SshConfig,
     root_path: String,
     max_depth: u8,
     max_repos: u16,
+    profile: Option<scan_profile::ScanProfile>,
 ) -> Result<Vec<DetectedRepo>, String> {
     let Some(ssh_exe) = ssh_exe(ssh_path) else {
         return Err("ssh not found. Run preflight and set sshPath if needed.".into());
@@ -815,18 +1341,116 @@ fn detect_remote_repos(
         return Err("ssh.host / ssh.user is required".into());
     }
 
-    let md = max_depth.clamp(1, 30);
-    let mr = max_repos.clamp(1, 5000);
+    scan_cache::refresh_remote(
+        &app, &ssh_exe, &ssh, &root_path, max_depth, max_repos, profile,
+    )
+}
+
+struct RemoteIncremental {
+    // remote の ~/.cache/gitshlc/<stamp_name>.stamp
+    stamp_name: String,
+    // 前回取得済みの repo（これに含まれ、stamp 以降に変化がなければ U 行だけ返る）
+    known: Vec<String>,
+    // 前回の完全走査で見たディレクトリ。stamp の中身が memo_token のときだけ使う
+    dirs: Vec<String>,
+    memo_token: String,
+    // 今回の走査が完了したら stamp に書く値
+    token: String,
+}
+
+struct RemoteDetect {
+    repos: Vec<DetectedRepo>,
+    // 変化なしの既知 repo と、取り直した dirty
+    unchanged: Vec<(String, Option<bool>)>,
+    // 今回見たディレクトリ（差分モードのときだけ）
+    dirs: Vec<String>,
+    // MAXR で打ち切らずに最後まで走査できたか
+    complete: bool,
+}
+
+// remote スクリプトの dirty 列（1 / 0 / 空）
+fn parse_dirty(s: Option<&str>) -> Option<bool> {
+    match s.map(str::trim) {
+        Some("1") => Some(true),
+        Some("0") => Some(false),
+        _ => None,
+    }
+}
+
+fn remote_detect(
+    ssh_exe: &Path,
+    ssh: &SshConfig,
+    root_path: &str,
+    max_depth: u8,
+    max_repos: u16,
+    profile: &scan_profile::ScanProfile,
+    incremental: Option<&RemoteIncremental>,
+) -> Result<RemoteDetect, String> {
+    let (remote_script, input) =
+        remote_detect_script(root_path, max_depth, max_repos, profile, incremental)?;
+    let remote_cmd = format!("sh -c {}", shell_escape_posix_single(&remote_script));
+    let step = ssh_run_with(ssh_exe, ssh, &remote_cmd, false, Some(&input));
+
+    if !step.ok {
+        let msg = format!(
+            "remote detect failed: exit={} stderr={}",
+            step.exit_code, step.stderr
+        );
+        return Err(msg);
+    }
+    Ok(parse_remote_detect(&step.stdout))
+}
+
+// remote で実行するスクリプトと stdin に流す一覧
+fn remote_detect_script(
+    root_path: &str,
+    max_depth: u8,
+    max_repos: u16,
+    profile: &scan_profile::ScanProfile,
+    incremental: Option<&RemoteIncremental>,
+) -> Result<(String, String), String> {
+    let prelude = scan_profile::remote_script_prelude(profile)?;
+    let md = profile.max_depth.unwrap_or(max_depth).clamp(1, 30);
+    let mr = profile
+        .max_repos
+        .map(|n| n.min(u16::MAX as u32) as u16)
+        .unwrap_or(max_repos)
+        .clamp(1, 5000);
 
     // root_path が空なら remote の $HOME を使う（SSH先で解決）
     let rp = root_path.trim().to_string();
 
-    // remote側で find して .git を列挙し、repo と origin を拾って TSV で返す
+    // 差分モード: stamp より後に .git 側が変わっていない既知 repo はメタデータを取らない。
+    // stamp より後に mtime が変わっていないディレクトリは前回の一覧で済ませて readdir しない
+    let (stamp_name, token, memo_token, input) = match incremental {
+        Some(i) => (
+            i.stamp_name.clone(),
+            i.token.clone(),
+            i.memo_token.clone(),
+            i.known
+                .iter()
+                .map(|k| format!("K {}\n", k))
+                .chain(i.dirs.iter().map(|d| format!("D {}\n", d)))
+                .collect::<String>(),
+        ),
+        None => (String::new(), String::new(), String::new(), String::new()),
+    };
+
+    // remote側で find して .git（dir / gitfile）と bare repo を列挙し、メタデータを TSV で返す
+    //   R <path> <kind> <name> <branch> <sha> <date> <dirty> <sizeKiB>
+    //   M <path> remote.<name>.url|pushurl <url>
+    //   U <path> <dirty>    差分モードで変化なし（dirty だけ取り直す）
+    //   D <path>            差分モードで見たディレクトリ（次回の一覧）
+    //   T                   MAXR で打ち切り
+    // 既知 repo（K 行）と前回のディレクトリ（D 行）の一覧は stdin で渡す
     let remote_script = format!(
         r#"
 ROOT={root};
 MAXD={md};
 MAXR={mr};
+STAMP_NAME={stamp};
+TOKEN={token};
+MEMO_TOKEN={memo_token};
 
 if [ -z "$ROOT" ]; then
   ROOT="$HOME";
@@ -847,71 +1471,250 @@ if [ -z "$GIT_BIN" ]; then
   exit 5
 fi
 
+NL='
+'
+INPUT="$(cat)"
+KNOWN="$NL$(printf '%s\n' "$INPUT" | sed -n 's/^K //p')$NL"
+MEMO="$(printf '%s\n' "$INPUT" | sed -n 's/^D //p')"
+STAMP=""
+NEWSTAMP=""
+if [ -n "$STAMP_NAME" ] && mkdir -p "$HOME/.cache/gitshlc" 2>/dev/null; then
+  STAMP="$HOME/.cache/gitshlc/$STAMP_NAME.stamp"
+  NEWSTAMP="$STAMP.new"
+  printf '%s\n' "$TOKEN" > "$NEWSTAMP" 2>/dev/null || NEWSTAMP=""
+fi
+# 前回の一覧は、stamp がそのとき書いたもの（= 一覧と同じ走査の完了時刻）のときだけ使える
+USE_MEMO=""
+if [ -n "$MEMO" ] && [ -n "$MEMO_TOKEN" ] && [ -f "$STAMP" ] && IFS= read -r cur < "$STAMP" && [ "$cur" = "$MEMO_TOKEN" ]; then
+  USE_MEMO=1
+  DIRS="$NL$MEMO$NL"
+fi
+
+{prelude}
 count=0
-find "$ROOT" -maxdepth "$MAXD" -type d -name .git 2>/dev/null | while IFS= read -r g; do
-  repo="${{g%/.git}}"
-  name="$(basename "$repo")"
-  origin="$("$GIT_BIN" -C "$repo" remote get-url origin 2>/dev/null || true)"
-  printf '%s\t%s\t%s\n' "$repo" "$origin" "$name"
+{{
+if [ -z "$USE_MEMO" ]; then
+  find $FIND_PRE "$ROOT" -maxdepth "$MAXD" $FIND_XDEV \( -name .git -print -prune \) -o \( -type d \( "$@" \) -prune \) -o \( -type f -name HEAD -print \) -o \( -type d -print \) 2>/dev/null
+else
+  # 前回のディレクトリを順に見る。mtime が stamp より新しいものだけ中身を列挙し直し、
+  # 前回無かった子ディレクトリはその下を find で走査する
+  printf '%s\n' "$MEMO" | while IFS= read -r d; do
+    [ -d "$d" ] || continue
+    if [ -z "$FIND_PRE" ] && [ -L "$d" ]; then continue; fi
+    printf '%s\n' "$d"
+    rel="${{d#"$RP"}}"
+    dep=0
+    while [ -n "$rel" ]; do
+      rel="${{rel#/}}"
+      dep=$((dep+1))
+      case "$rel" in */*) rel="${{rel#*/}}" ;; *) rel="" ;; esac
+    done
+    [ "$dep" -lt "$MAXD" ] || continue
+    # 同じ秒の変更を取りこぼさないよう、stamp の方が新しいときだけ変化なしとみなす
+    if [ "$STAMP" -nt "$d" ]; then
+      [ -e "$d/.git" ] && printf '%s\n' "$d/.git"
+      [ -f "$d/HEAD" ] && printf '%s\n' "$d/HEAD"
+    else
+      find $FIND_PRE "$d" -mindepth 1 -maxdepth 1 $FIND_XDEV \( -name .git -print -prune \) -o \( -type d \( "$@" \) -prune \) -o \( -type f -name HEAD -print \) -o \( -type d -print \) 2>/dev/null | while IFS= read -r e; do
+        case "$e" in
+          */.git) printf '%s\n' "$e" ;;
+          *)
+            if [ -d "$e" ]; then
+              case "$DIRS" in
+                *"$NL$e$NL"*) ;;
+                *) find $FIND_PRE "$e" -maxdepth "$((MAXD-dep-1))" $FIND_XDEV \( -name .git -print -prune \) -o \( -type d \( "$@" \) -prune \) -o \( -type f -name HEAD -print \) -o \( -type d -print \) 2>/dev/null ;;
+              esac
+            else
+              printf '%s\n' "$e"
+            fi
+            ;;
+        esac
+      done
+    fi
+  done
+fi
+}} | while IFS= read -r g; do
+  case "$g" in
+    */.git) ;;
+    *)
+      if [ -d "$g" ]; then
+        [ -n "$NEWSTAMP" ] && printf 'D\t%s\n' "$g"
+        continue
+      fi
+      ;;
+  esac
+  case "$g" in
+    */.git)
+      repo="${{g%/.git}}"
+      kind=normal
+      gdir="$g"
+      if [ -f "$g" ]; then
+        gd="$(sed -n 's/^gitdir: *//p' "$g" 2>/dev/null | head -n 1)"
+        case "$gd" in
+          /*) gdir="$gd" ;;
+          *) gdir="$repo/$gd" ;;
+        esac
+        if [ -f "$repo/$gd/commondir" ] || [ -f "$gd/commondir" ]; then
+          kind=worktree
+        else
+          case "$gd" in
+            *.git/modules/*) kind=submodule ;;
+          esac
+        fi
+      fi
+      ;;
+    */HEAD)
+      repo="${{g%/HEAD}}"
+      [ -d "$repo/objects" ] && [ -d "$repo/refs" ] || continue
+      kind=bare
+      gdir="$repo"
+      ;;
+    *)
+      continue
+      ;;
+  esac
+
+  rel="${{repo#"$RP"}}"
+  inc_match "${{rel#/}}" || continue
+
   count=$((count+1))
-  if [ "$count" -ge "$MAXR" ]; then
-    break
+  if [ "$count" -gt "$MAXR" ]; then
+    echo T
+    exit 3
   fi
+
+  # 作業ツリーの変更は stamp に出ないので dirty は毎回取る
+  dirty=""
+  if [ "$kind" != bare ]; then
+    # index を書き換えない（次回の差分判定が狂うため）
+    if st="$(GIT_OPTIONAL_LOCKS=0 "$GIT_BIN" -C "$repo" status --porcelain --ignore-submodules 2>/dev/null)"; then
+      if [ -n "$st" ]; then dirty=1; else dirty=0; fi
+    fi
+  fi
+
+  if [ -f "$STAMP" ]; then
+    case "$KNOWN" in
+      *"$NL$repo$NL"*)
+        if [ -z "$(find "$gdir" "$gdir/HEAD" "$gdir/index" "$gdir/logs/HEAD" "$gdir/FETCH_HEAD" -prune -newer "$STAMP" 2>/dev/null)" ]; then
+          printf 'U\t%s\t%s\n' "$repo" "$dirty"
+          continue
+        fi
+        ;;
+    esac
+  fi
+
+  name="$(basename "$repo")"
+  branch="$("$GIT_BIN" -C "$repo" symbolic-ref --short -q HEAD 2>/dev/null || true)"
+  head="$("$GIT_BIN" -C "$repo" log -1 --format='%H %cI' 2>/dev/null || true)"
+  sha="${{head%% *}}"
+  date="${{head#* }}"
+  size="$(du -sk "$repo" 2>/dev/null | cut -f1)"
+  printf 'R\t%s\t%s\t%s\t%s\t%s\t%s\t%s\t%s\n' "$repo" "$kind" "$name" "$branch" "$sha" "$date" "$dirty" "$size"
+  "$GIT_BIN" -C "$repo" config --get-regexp '^remote\..*\.(url|pushurl)$' 2>/dev/null | while read -r k v; do
+    printf 'M\t%s\t%s\t%s\n' "$repo" "$k" "$v"
+  done
 done
+rc=$?
+
+# 最後まで走査できたときだけ stamp を進める（打ち切ると未確認の repo が残るため）
+if [ -n "$NEWSTAMP" ]; then
+  if [ "$rc" -eq 0 ]; then
+    mv -f "$NEWSTAMP" "$STAMP" 2>/dev/null
+  else
+    rm -f "$NEWSTAMP"
+  fi
+fi
+exit 0
 "#,
         root = shell_escape_posix_single(&rp),
         md = md,
-        mr = mr
+        mr = mr,
+        stamp = shell_escape_posix_single(&stamp_name),
+        token = shell_escape_posix_single(&token),
+        memo_token = shell_escape_posix_single(&memo_token),
+        prelude = prelude
     );
 
-    let remote_cmd = format!("sh -c {}", shell_escape_posix_single(&remote_script));
-    let step = ssh_run(&ssh_exe, &ssh, &remote_cmd);
-
-    if !step.ok {
-        let msg = format!(
-            "remote detect failed: exit={} stderr={}",
-            step.exit_code, step.stderr
-        );
-        return Err(msg);
-    }
+    Ok((remote_script, input))
+}
 
+fn parse_remote_detect(stdout: &str) -> RemoteDetect {
     let mut seen = HashSet::<String>::new();
     let mut out = Vec::<DetectedRepo>::new();
-
-    for line in step.stdout.lines() {
-        let ln = line.trim();
-        if ln.is_empty() {
-            continue;
+    let mut unchanged = Vec::<(String, Option<bool>)>::new();
+    let mut dirs = Vec::<String>::new();
+    let mut complete = true;
+    let mut remote_pairs: HashMap<String, Vec<(String, String, String)>> = HashMap::new();
+
+    let opt = |s: &str| {
+        let s = s.trim();
+        if s.is_empty() {
+            None
+        } else {
+            Some(s.to_string())
         }
+    };
 
-        let mut parts = ln.splitn(3, '\t');
-        let path = parts.next().unwrap_or("").trim().to_string();
-        if path.is_empty() {
-            continue;
-        }
-        if !seen.insert(path.clone()) {
-            continue;
+    for line in stdout.lines() {
+        let ln = line.trim_end_matches('\r');
+        let parts: Vec<&str> = ln.split('\t').collect();
+
+        match parts.first().copied() {
+            Some("T") => complete = false,
+            Some("D") if parts.len() >= 2 => dirs.push(parts[1].to_string()),
+            Some("U") if parts.len() >= 2 => {
+                let path = parts[1].trim().to_string();
+                if !path.is_empty() && seen.insert(path.clone()) {
+                    unchanged.push((path, parse_dirty(parts.get(2).copied())));
+                }
+            }
+            Some("M") if parts.len() >= 4 => {
+                if let Some(pair) = gitdir::remote_from_config_line(parts[2], parts[3]) {
+                    remote_pairs
+                        .entry(parts[1].to_string())
+                        .or_default()
+                        .push(pair);
+                }
+            }
+            Some("R") if parts.len() >= 9 => {
+                let path = parts[1].trim().to_string();
+                if path.is_empty() || !seen.insert(path.clone()) {
+                    continue;
+                }
+                out.push(DetectedRepo {
+                    path,
+                    origin_url: None,
+                    name: opt(parts[3]),
+                    kind: opt(parts[2]).unwrap_or_else(|| "normal".into()),
+                    remotes: Vec::new(),
+                    branch: opt(parts[4]),
+                    head_sha: opt(parts[5]),
+                    head_date: opt(parts[6]),
+                    dirty: parse_dirty(Some(parts[7])),
+                    size_bytes: parts[8].trim().parse::<u64>().ok().map(|k| k * 1024),
+                });
+            }
+            _ => {}
         }
+    }
 
-        let origin = parts.next().unwrap_or("").trim();
-        let name = parts.next().unwrap_or("").trim();
-
-        out.push(DetectedRepo {
-            path,
-            origin_url: if origin.is_empty() {
-                None
-            } else {
-                Some(origin.to_string())
-            },
-            name: if name.is_empty() {
-                None
-            } else {
-                Some(name.to_string())
-            },
-        });
+    for repo in out.iter_mut() {
+        if let Some(pairs) = remote_pairs.get(&repo.path) {
+            repo.remotes = gitdir::remotes_from_pairs(pairs);
+        }
+        repo.origin_url = repo
+            .remotes
+            .iter()
+            .find(|r| r.name == "origin")
+            .and_then(|r| r.fetch_url.clone());
     }
 
-    Ok(out)
+    RemoteDetect {
+        repos: out,
+        unchanged,
+        dirs,
+        complete,
+    }
 }
 
 fn step_error(cmd: String, stderr: String) -> StepResult {
@@ -925,6 +1728,28 @@ fn step_error(cmd: String, stderr: String) -> StepResult {
     }
 }
 
+// 1970-01-01 からの日数 → (年, 月, 日)（UTC）
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    (y, m, d)
+}
+
+fn today_utc() -> (i64, u32, u32) {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    civil_from_days(secs.div_euclid(86_400))
+}
+
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 struct SshConnectWire {
@@ -934,6 +1759,7 @@ struct SshConnectWire {
     ssh_ok: bool,
     stderr: Option<String>,
     remote_git: ToolCheck,
+    remote_git_capabilities: Option<git_version::GitCapabilities>,
 }
 
 fn ssh_detect_remote_git(ssh_exe: &Path, cfg: &SshConfig) -> ToolCheck {
@@ -1017,6 +1843,7 @@ fn ssh_connect(ssh_path: Option<String>, ssh: SshConfig) -> SshConnectWire {
                 ok: false,
                 error: Some("remote git not checked".into()),
             },
+            remote_git_capabilities: None,
         };
     };
 
@@ -1032,6 +1859,7 @@ fn ssh_connect(ssh_path: Option<String>, ssh: SshConfig) -> SshConnectWire {
                 ok: false,
                 error: Some("remote git not checked".into()),
             },
+            remote_git_capabilities: None,
         };
     }
 
@@ -1057,6 +1885,7 @@ fn ssh_connect(ssh_path: Option<String>, ssh: SshConfig) -> SshConnectWire {
                 ok: false,
                 error: Some("remote git not checked".into()),
             },
+            remote_git_capabilities: None,
         };
     }
 
@@ -1067,11 +1896,17 @@ fn ssh_connect(ssh_path: Option<String>, ssh: SshConfig) -> SshConnectWire {
     let ok = ssh_ok && remote_git.ok;
     let stderr = if ok { None } else { remote_git.error.clone() };
 
+    let remote_git_capabilities = remote_git
+        .version
+        .as_deref()
+        .map(git_version::GitCapabilities::from_version_output);
+
     SshConnectWire {
         ok,
         ssh_ok,
         stderr,
         remote_git,
+        remote_git_capabilities,
     }
 }
 
@@ -1089,9 +1924,30 @@ struct RunActionRequest {
     ssh: SshConfig,
     merge_from_branch: Option<String>,
     commit_message: Option<String>,
+    // ssh のみ: サーバーが origin に対して使う認証
+    #[serde(default)]
+    remote_auth: Option<RemoteAuthConfig>,
+    // ssh のみ: remote_path の持ち主として git を実行する
+    #[serde(default)]
+    run_as: Option<RunAsConfig>,
+    // mode=wrapped のみ
+    #[serde(default)]
+    wrapper: Option<WrapperConfig>,
+    // run_action_hosts のみ: 複数ホスト（空なら ssh / remotePath の 1 台）
+    #[serde(default)]
+    hosts: Vec<fanout::HostTarget>,
+    #[serde(default)]
+    fanout: Option<fanout::FanoutConfig>,
+    // action=deploy のみ: tag / SHA に固定して detached HEAD で checkout する
+    #[serde(default)]
+    pin_ref: Option<String>,
+    // ssh / wrapped の pull・merge のみ: 反映するコミットの CI が通っていなければ止める
+    #[serde(default)]
+    ci_gate: Option<ci_gate::CiGateConfig>,
 }
 
-#[tauri::command(rename_all = "camelCase")]
+// ciGate の待ち（poll）があるので async（メインスレッドを止めない）
+#[tauri::command(async, rename_all = "camelCase")]
 fn run_action(req: RunActionRequest) -> ActionOutcome {
     let mut steps: Vec<StepResult> = Vec::new();
 
@@ -1116,6 +1972,10 @@ fn run_action(req: RunActionRequest) -> ActionOutcome {
         }
     };
 
+    if req.action == "deploy" {
+        return release::deploy_pinned(&req);
+    }
+
     if req.action != "pull" && req.action != "push" && req.action != "merge" {
         return fail(
             "CFG-0001",
@@ -1139,6 +1999,15 @@ fn run_action(req: RunActionRequest) -> ActionOutcome {
             }
         };
 
+        // 古い git では使えないコマンドがあるので、先にバージョンを見ておく
+        let caps = git_version::local_capabilities(&git);
+        if let Some(Err(e)) = caps
+            .as_ref()
+            .map(|c| c.require(git_version::MIN_ACTION_VERSION, "run_action"))
+        {
+            return fail(&e.code, &e.severity, &e.message, e.detail, steps, &req);
+        }
+
         let lp = req.local_path.trim();
         if lp.is_empty() {
             return fail(
@@ -1421,6 +2290,54 @@ fn run_action(req: RunActionRequest) -> ActionOutcome {
                 None,
             ));
 
+            // merge-tree --write-tree が使えれば、作業ツリーに触る前に衝突を検出する
+            if caps
+                .as_ref()
+                .map(|c| c.supports(git_version::CAP_MERGE_TREE_WRITE_TREE))
+                .unwrap_or(false)
+            {
+                let dry = run_capture(
+                    &git,
+                    &[
+                        "-C",
+                        &path_to_string(&local_path),
+                        "merge-tree",
+                        "--write-tree",
+                        "--name-only",
+                        "HEAD",
+                        &from,
+                    ],
+                    None,
+                );
+                // 衝突なら exit 1 で stdout に tree と衝突ファイル（存在しない ref でも exit 1 だが stdout は空）
+                if dry.exit_code == 1 && !dry.stdout.trim().is_empty() {
+                    let detail = Some(dry.stdout.clone());
+                    steps.push(dry);
+                    return fail(
+                        "GIT-0111",
+                        "ERROR",
+                        "merge would conflict",
+                        detail,
+                        steps,
+                        &req,
+                    );
+                }
+                // 衝突（exit 1）以外の失敗は merge に進まずにそのまま返す
+                if !dry.ok {
+                    let detail = Some(dry.stderr.trim().to_string());
+                    steps.push(dry);
+                    return fail(
+                        "GIT-0112",
+                        "ERROR",
+                        "merge dry run failed",
+                        detail,
+                        steps,
+                        &req,
+                    );
+                }
+                steps.push(dry);
+            }
+
             steps.push(run_capture(
                 &git,
                 &[
@@ -1466,324 +2383,751 @@ fn run_action(req: RunActionRequest) -> ActionOutcome {
         };
     }
 
-    if req.mode == "ssh" {
-        let ssh = match ssh_exe(if req.ssh_path.trim().is_empty() {
+    if req.mode == "ssh" || req.mode == "wrapped" {
+        let remote_path = req.remote_path.trim().to_string();
+        let auth_plan = match remote_auth_plan(req.remote_auth.as_ref()) {
+            Ok(p) => p,
+            Err(e) => {
+                return fail(&e.code, &e.severity, &e.message, e.detail, steps, &req);
+            }
+        };
+
+        let repo = match remote_repo_for(&req, &remote_path, &auth_plan) {
+            Ok(r) => r,
+            Err(e) => {
+                return fail(&e.code, &e.severity, &e.message, e.detail, steps, &req);
+            }
+        };
+
+        return run_remote_action(&req, &repo);
+    }
+
+    fail(
+        "CFG-0002",
+        "ERROR",
+        "unknown mode (expected local|ssh|wrapped)",
+        Some(req.mode.clone()),
+        steps,
+        &req,
+    )
+}
+
+// ssh / wrapped の設定から RemoteRepo を組み立てる（検証エラーは ActionError で返す）
+fn remote_repo_for<'a>(
+    req: &'a RunActionRequest,
+    remote_path: &'a str,
+    auth_plan: &'a RemoteAuthPlan,
+) -> Result<RemoteRepo<'a>, ActionError> {
+    let err = |code: &str, severity: &str, message: &str| ActionError {
+        code: code.into(),
+        severity: severity.into(),
+        message: message.into(),
+        detail: None,
+    };
+
+    let wrapped = req.mode == "wrapped";
+    let wrapper = req.wrapper.as_ref();
+    let template = wrapper.map(|w| w.template.trim()).unwrap_or("");
+    if wrapped && template.is_empty() {
+        return Err(err(
+            "CFG-0320",
+            "ERROR",
+            "wrapper.template is required for mode=wrapped",
+        ));
+    }
+
+    if remote_path.is_empty() {
+        return Err(err("CFG-0303", "ERROR", "remotePath is required"));
+    }
+
+    validate_run_as(req.run_as.as_ref(), auth_plan)?;
+
+    let via_ssh = !wrapped || wrapper.map(|w| w.via_ssh).unwrap_or(false);
+    // ローカル実行では転送する ssh 接続が無い
+    if !via_ssh && auth_plan.forward_agent {
+        return Err(ActionError {
+            code: "CFG-0311".into(),
+            severity: "ERROR".into(),
+            message: "remoteAuth.strategy=agent requires wrapper.viaSsh in wrapped mode".into(),
+            detail: Some(template.to_string()),
+        });
+    }
+
+    let transport = if via_ssh {
+        let ssh = ssh_exe(if req.ssh_path.trim().is_empty() {
             None
         } else {
             Some(req.ssh_path.clone())
-        }) {
-            Some(p) => p,
-            None => return fail("SSH-0001", "FATAL", "ssh not found", None, steps, &req),
-        };
+        })
+        .ok_or_else(|| err("SSH-0001", "FATAL", "ssh not found"))?;
 
         let cfg = req.ssh.clone();
         if cfg.host.trim().is_empty() || cfg.user.trim().is_empty() {
+            return Err(err("CFG-0302", "ERROR", "ssh host/user is required"));
+        }
+        Transport::Ssh { exe: ssh, cfg }
+    } else {
+        // wrapper をこのマシンで実行する（docker / kubectl がローカルにある場合）
+        let sh = resolve_executable(None, "sh", &[]).ok_or_else(|| {
+            err(
+                "CFG-0321",
+                "ERROR",
+                "sh not found (required to run wrapper locally)",
+            )
+        })?;
+        Transport::Local { sh }
+    };
+
+    Ok(RemoteRepo {
+        transport,
+        path: remote_path,
+        run_as: req.run_as.as_ref(),
+        wrapper: if wrapped { Some(template) } else { None },
+        auth: auth_plan,
+    })
+}
+
+// ssh / wrapped 共通: RemoteRepo 経由で pull/push/merge を実行する
+fn run_remote_action(req: &RunActionRequest, repo: &RemoteRepo) -> ActionOutcome {
+    let mut steps: Vec<StepResult> = Vec::new();
+
+    let fail = |code: &str,
+                severity: &str,
+                message: &str,
+                detail: Option<String>,
+                steps: Vec<StepResult>,
+                req: &RunActionRequest| {
+        ActionOutcome {
+            ok: false,
+            mode: req.mode.clone(),
+            action: req.action.clone(),
+            env_key: req.env_key.clone(),
+            steps,
+            error: Some(ActionError {
+                code: code.into(),
+                severity: severity.into(),
+                message: message.into(),
+                detail,
+            }),
+        }
+    };
+
+    // current branch（unborn/detachedにも少し強くする）
+    let br_step =
+        repo.run("(git symbolic-ref --short HEAD 2>/dev/null || git rev-parse --abbrev-ref HEAD)");
+    let current_branch = br_step.stdout.trim().to_string();
+    if !br_step.ok {
+        let detail = Some(br_step.stderr.clone());
+        let conn = repo.classify(&br_step);
+        steps.push(br_step);
+        // 接続・sudo・所有者の問題はそちらを優先して報告する
+        if conn.code != "SSH-0200" {
+            return fail(
+                &conn.code,
+                &conn.severity,
+                &conn.message,
+                detail,
+                steps,
+                req,
+            );
+        }
+        return fail(
+            "SSH-0201",
+            "ERROR",
+            "failed to get remote branch",
+            detail,
+            steps,
+            req,
+        );
+    }
+    steps.push(br_step);
+
+    // remote の git バージョン（古い git では使えないコマンドがある）
+    let ver_step = repo.run("git --version");
+    let caps = if ver_step.ok {
+        Some(git_version::GitCapabilities::from_version_output(
+            &ver_step.stdout,
+        ))
+    } else {
+        None
+    };
+    if let Some(Err(e)) = caps
+        .as_ref()
+        .map(|c| c.require(git_version::MIN_ACTION_VERSION, "run_action"))
+    {
+        steps.push(ver_step);
+        return fail(&e.code, &e.severity, &e.message, e.detail, steps, req);
+    }
+
+    // CI gate（作業ツリーに触る前に判定する）
+    if let (true, Some(gate)) = (req.action != "push", req.ci_gate.as_ref()) {
+        if let Err(e) = ci_gate::check(repo, req, gate, &mut steps) {
+            return fail(&e.code, &e.severity, &e.message, e.detail, steps, req);
+        }
+    }
+
+    // HEAD exists?（初回pushのrefspec事故回避）
+    let head_step = repo.run("git rev-parse --verify HEAD");
+    let mut has_commits = head_step.ok;
+    steps.push(head_step);
+
+    // status (ignore submodules to avoid false positives from nested repos)
+    let st_step = repo.run("git status --porcelain --ignore-submodules");
+    if !st_step.ok {
+        let detail = Some(st_step.stderr.clone());
+        steps.push(st_step);
+        return fail(
+            "SSH-0201",
+            "ERROR",
+            "git status failed on remote",
+            detail,
+            steps,
+            req,
+        );
+    }
+    let clean = st_step.stdout.trim().is_empty();
+    steps.push(st_step);
+
+    // For pull/merge: auto-stash if dirty (ignore local changes)
+    if req.action != "push" && !clean {
+        let mut stash_step = repo.run("git stash --include-untracked");
+        // Mark stash as OK if it saved changes, but not when files could not be removed
+        // (permission errors mean the tree is still dirty: use runAs instead)
+        if stash_step.stdout.contains("Saved working directory")
+            && !stash_step
+                .stderr
+                .to_ascii_lowercase()
+                .contains("permission denied")
+        {
+            stash_step.ok = true;
+        }
+        steps.push(stash_step);
+    }
+
+    // push & dirty => commitMessage必須で add+commit
+    if req.action == "push" && !clean {
+        if current_branch != req.branch {
             return fail(
-                "CFG-0302",
+                "GIT-0104",
                 "ERROR",
-                "ssh host/user is required",
-                None,
+                "working tree is dirty on a non-target branch (checkout target branch first)",
+                Some(format!("current={}, target={}", current_branch, req.branch)),
                 steps,
-                &req,
+                req,
             );
         }
 
-        let remote_path = req.remote_path.trim().to_string();
-        if remote_path.is_empty() {
+        let msg = req.commit_message.clone().unwrap_or_default();
+        let msg = msg.trim().to_string();
+        if msg.is_empty() {
             return fail(
-                "CFG-0303",
+                "GIT-0103",
                 "ERROR",
-                "remotePath is required",
+                "commitMessage is required for push when there are uncommitted changes",
                 None,
                 steps,
-                &req,
+                req,
             );
         }
 
-        // current branch（unborn/detachedにも少し強くする）
-        let br_cmd = format!(
-            "cd {} && (git symbolic-ref --short HEAD 2>/dev/null || git rev-parse --abbrev-ref HEAD)",
-            shell_escape_posix_single(&remote_path)
-        );
-        let br_step = ssh_run(&ssh, &cfg, &br_cmd);
-        let current_branch = br_step.stdout.trim().to_string();
-        if !br_step.ok {
-            let detail = Some(br_step.stderr.clone());
-            steps.push(br_step);
+        let add_step = repo.run("git add -A");
+        if !add_step.ok {
+            let detail = Some(add_step.stderr.clone());
+            steps.push(add_step);
             return fail(
                 "SSH-0201",
                 "ERROR",
-                "failed to get remote branch",
+                "git add failed on remote",
                 detail,
                 steps,
-                &req,
+                req,
             );
         }
-        steps.push(br_step);
-
-        // HEAD exists?（初回pushのrefspec事故回避）
-        let head_cmd = format!(
-            "cd {} && git rev-parse --verify HEAD",
-            shell_escape_posix_single(&remote_path)
-        );
-        let head_step = ssh_run(&ssh, &cfg, &head_cmd);
-        let mut has_commits = head_step.ok;
-        steps.push(head_step);
+        steps.push(add_step);
 
-        // status (ignore submodules to avoid false positives from nested repos)
-        let status_cmd = format!(
-            "cd {} && git status --porcelain --ignore-submodules",
-            shell_escape_posix_single(&remote_path)
-        );
-        let st_step = ssh_run(&ssh, &cfg, &status_cmd);
-        if !st_step.ok {
-            let detail = Some(st_step.stderr.clone());
-            steps.push(st_step);
+        let commit_step = repo.run(&format!(
+            "git commit -m {}",
+            shell_escape_posix_single(&msg)
+        ));
+        if !commit_step.ok {
+            let detail = Some(commit_step.stderr.clone());
+            steps.push(commit_step);
             return fail(
                 "SSH-0201",
                 "ERROR",
-                "git status failed on remote",
+                "git commit failed on remote",
                 detail,
                 steps,
-                &req,
+                req,
             );
         }
-        let clean = st_step.stdout.trim().is_empty();
-        steps.push(st_step);
+        steps.push(commit_step);
+        has_commits = true;
+    }
 
-        // For pull/merge: auto-stash if dirty (ignore local changes)
-        if req.action != "push" && !clean {
-            let stash_cmd = format!(
-                "cd {} && git stash --include-untracked",
-                shell_escape_posix_single(&remote_path)
+    // 初回 push（コミット 0 件）なら allow-empty で 1件作る
+    if req.action == "push" && !has_commits {
+        let msg = req.commit_message.clone().unwrap_or_default();
+        let msg = msg.trim().to_string();
+        if msg.is_empty() {
+            return fail(
+                "GIT-0107",
+                "ERROR",
+                "push requires commitMessage when repository has no commits",
+                None,
+                steps,
+                req,
             );
-            let mut stash_step = ssh_run(&ssh, &cfg, &stash_cmd);
-            // Mark stash as OK if it saved changes (even with permission errors)
-            if stash_step.stdout.contains("Saved working directory") {
-                stash_step.ok = true;
-            }
-            steps.push(stash_step);
         }
 
-        // push & dirty => commitMessage必須で add+commit
-        if req.action == "push" && !clean {
-            if current_branch != req.branch {
-                return fail(
-                    "GIT-0104",
-                    "ERROR",
-                    "working tree is dirty on a non-target branch (checkout target branch first)",
-                    Some(format!("current={}, target={}", current_branch, req.branch)),
-                    steps,
-                    &req,
-                );
-            }
-
-            let msg = req.commit_message.clone().unwrap_or_default();
-            let msg = msg.trim().to_string();
-            if msg.is_empty() {
-                return fail(
-                    "GIT-0103",
-                    "ERROR",
-                    "commitMessage is required for push when there are uncommitted changes",
-                    None,
-                    steps,
-                    &req,
-                );
-            }
-
-            let add_cmd = format!(
-                "cd {} && git add -A",
-                shell_escape_posix_single(&remote_path)
-            );
-            let add_step = ssh_run(&ssh, &cfg, &add_cmd);
-            if !add_step.ok {
-                let detail = Some(add_step.stderr.clone());
-                steps.push(add_step);
-                return fail(
-                    "SSH-0201",
-                    "ERROR",
-                    "git add failed on remote",
-                    detail,
-                    steps,
-                    &req,
-                );
-            }
-            steps.push(add_step);
-
-            let commit_cmd = format!(
-                "cd {} && git commit -m {}",
-                shell_escape_posix_single(&remote_path),
-                shell_escape_posix_single(&msg)
+        let empty_commit_step = repo.run(&format!(
+            "git commit --allow-empty -m {}",
+            shell_escape_posix_single(&msg)
+        ));
+        if !empty_commit_step.ok {
+            let detail = Some(empty_commit_step.stderr.clone());
+            steps.push(empty_commit_step);
+            return fail(
+                "SSH-0201",
+                "ERROR",
+                "git commit --allow-empty failed on remote",
+                detail,
+                steps,
+                req,
             );
-            let commit_step = ssh_run(&ssh, &cfg, &commit_cmd);
-            if !commit_step.ok {
-                let detail = Some(commit_step.stderr.clone());
-                steps.push(commit_step);
-                return fail(
-                    "SSH-0201",
-                    "ERROR",
-                    "git commit failed on remote",
-                    detail,
-                    steps,
-                    &req,
-                );
-            }
-            steps.push(commit_step);
-            has_commits = true;
         }
+        steps.push(empty_commit_step);
+    }
 
-        // 初回 push（コミット 0 件）なら allow-empty で 1件作る
-        if req.action == "push" && !has_commits {
-            let msg = req.commit_message.clone().unwrap_or_default();
-            let msg = msg.trim().to_string();
-            if msg.is_empty() {
-                return fail(
-                    "GIT-0107",
-                    "ERROR",
-                    "push requires commitMessage when repository has no commits",
-                    None,
-                    steps,
-                    &req,
-                );
-            }
-
-            let empty_commit_cmd = format!(
-                "cd {} && git commit --allow-empty -m {}",
-                shell_escape_posix_single(&remote_path),
-                shell_escape_posix_single(&msg)
-            );
-            let empty_commit_step = ssh_run(&ssh, &cfg, &empty_commit_cmd);
-            if !empty_commit_step.ok {
-                let detail = Some(empty_commit_step.stderr.clone());
-                steps.push(empty_commit_step);
-                return fail(
-                    "SSH-0201",
-                    "ERROR",
-                    "git commit --allow-empty failed on remote",
-                    detail,
-                    steps,
-                    &req,
-                );
-            }
-            steps.push(empty_commit_step);
-            has_commits = true;
-        }
+    // fetch
+    steps.push(repo.git_origin("fetch origin"));
 
-        // fetch
-        let fetch_cmd = format!(
-            "cd {} && git fetch origin",
-            shell_escape_posix_single(&remote_path)
-        );
-        steps.push(ssh_run(&ssh, &cfg, &fetch_cmd));
+    // checkout
+    steps.push(repo.run(&format!(
+        "git checkout {}",
+        shell_escape_posix_single(&req.branch)
+    )));
 
-        // checkout
-        let checkout_cmd = format!(
-            "cd {} && git checkout {}",
-            shell_escape_posix_single(&remote_path),
+    if req.action == "pull" {
+        let pull_args = format!(
+            "pull --ff-only origin {}",
             shell_escape_posix_single(&req.branch)
         );
-        steps.push(ssh_run(&ssh, &cfg, &checkout_cmd));
+        steps.push(repo.git_origin(&pull_args));
+    }
 
-        if req.action == "pull" {
-            let pull_cmd = format!(
-                "cd {} && git pull --ff-only origin {}",
-                shell_escape_posix_single(&remote_path),
-                shell_escape_posix_single(&req.branch)
-            );
-            steps.push(ssh_run(&ssh, &cfg, &pull_cmd));
-        }
+    if req.action == "push" {
+        let push_args = format!("push origin {}", shell_escape_posix_single(&req.branch));
+        steps.push(repo.git_origin(&push_args));
+    }
 
-        if req.action == "push" {
-            let push_cmd = format!(
-                "cd {} && git push origin {}",
-                shell_escape_posix_single(&remote_path),
-                shell_escape_posix_single(&req.branch)
+    if req.action == "merge" {
+        let from = req.merge_from_branch.clone().unwrap_or_default();
+        let from = from.trim().to_string();
+        if from.is_empty() {
+            return fail(
+                "CFG-0201",
+                "ERROR",
+                "mergeFromBranch is required for merge",
+                None,
+                steps,
+                req,
             );
-            steps.push(ssh_run(&ssh, &cfg, &push_cmd));
         }
 
-        if req.action == "merge" {
-            let from = req.merge_from_branch.clone().unwrap_or_default();
-            let from = from.trim().to_string();
-            if from.is_empty() {
+        let fetch_from_args = format!("fetch origin {}", shell_escape_posix_single(&from));
+        steps.push(repo.git_origin(&fetch_from_args));
+
+        let origin_from = format!("origin/{}", from);
+
+        // merge-tree --write-tree が使えれば、作業ツリーに触る前に衝突を検出する
+        if caps
+            .as_ref()
+            .map(|c| c.supports(git_version::CAP_MERGE_TREE_WRITE_TREE))
+            .unwrap_or(false)
+        {
+            let dry = repo.run(&format!(
+                "git merge-tree --write-tree --name-only HEAD {}",
+                shell_escape_posix_single(&origin_from)
+            ));
+            // 衝突なら exit 1 で stdout に tree と衝突ファイル（存在しない ref でも exit 1 だが stdout は空）
+            if dry.exit_code == 1 && !dry.stdout.trim().is_empty() {
+                let detail = Some(dry.stdout.clone());
+                steps.push(dry);
                 return fail(
-                    "CFG-0201",
+                    "GIT-0111",
                     "ERROR",
-                    "mergeFromBranch is required for merge",
-                    None,
+                    "merge would conflict",
+                    detail,
                     steps,
-                    &req,
+                    req,
                 );
             }
-
-            let fetch_from_cmd = format!(
-                "cd {} && git fetch origin {}",
-                shell_escape_posix_single(&remote_path),
-                shell_escape_posix_single(&from)
-            );
-            steps.push(ssh_run(&ssh, &cfg, &fetch_from_cmd));
-
-            let origin_from = format!("origin/{}", from);
-            let merge_cmd = format!(
-                "cd {} && git merge --no-ff {}",
-                shell_escape_posix_single(&remote_path),
-                shell_escape_posix_single(&origin_from)
-            );
-            steps.push(ssh_run(&ssh, &cfg, &merge_cmd));
-
-            let push_after_merge_cmd = format!(
-                "cd {} && git push origin {}",
-                shell_escape_posix_single(&remote_path),
-                shell_escape_posix_single(&req.branch)
-            );
-            steps.push(ssh_run(&ssh, &cfg, &push_after_merge_cmd));
+            // 衝突（exit 1）以外の失敗は merge に進まずにそのまま返す（接続エラーなどはその分類で）
+            if !dry.ok {
+                let conn = repo.classify(&dry);
+                let e = if conn.code != "SSH-0200" {
+                    conn
+                } else {
+                    ActionError {
+                        code: "GIT-0112".into(),
+                        severity: "ERROR".into(),
+                        message: "merge dry run failed".into(),
+                        detail: Some(dry.stderr.trim().to_string()),
+                    }
+                };
+                steps.push(dry);
+                return fail(&e.code, &e.severity, &e.message, e.detail, steps, req);
+            }
+            steps.push(dry);
         }
 
-        let ok = steps.iter().all(|s| s.ok);
-        return ActionOutcome {
-            ok,
-            mode: req.mode.clone(),
-            action: req.action.clone(),
-            env_key: req.env_key.clone(),
-            steps,
-            error: if ok {
-                None
-            } else {
-                Some(ActionError {
-                    code: "SSH-0200".into(),
-                    severity: "ERROR".into(),
-                    message: "remote command failed".into(),
-                    detail: None,
-                })
-            },
-        };
+        steps.push(repo.run(&format!(
+            "git merge --no-ff {}",
+            shell_escape_posix_single(&origin_from)
+        )));
+
+        let push_after_merge_args =
+            format!("push origin {}", shell_escape_posix_single(&req.branch));
+        steps.push(repo.git_origin(&push_after_merge_args));
     }
 
-    fail(
-        "CFG-0002",
-        "ERROR",
-        "unknown mode (expected local|ssh)",
-        Some(req.mode.clone()),
+    // 最初に失敗したステップで分類（接続 / origin 認証 / その他）
+    let error = steps.iter().find(|s| !s.ok).map(|s| repo.classify(s));
+    ActionOutcome {
+        ok: error.is_none(),
+        mode: req.mode.clone(),
+        action: req.action.clone(),
+        env_key: req.env_key.clone(),
         steps,
-        &req,
-    )
+        error,
+    }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .manage(scan::ScanRegistry::default())
+        .manage(scan_cache::CacheRegistry::default())
+        .manage(ls_remote::BranchCache::default())
+        .manage(webhook::WebhookRegistry::default())
         .invoke_handler(tauri::generate_handler![
             greet,
             preflight,
-            list_branches,
+            ls_remote::list_branches,
             ssh_connect,
             default_detect_root,
             detect_local_repos,
             detect_remote_repos,
             init_local_repo,
-            run_action
+            run_action,
+            deploy_key::provision_deploy_key,
+            fanout::run_action_hosts,
+            scan::start_local_scan,
+            scan::cancel_scan,
+            scan_profile::get_scan_profile,
+            scan_profile::save_scan_profile,
+            scan_profile::delete_scan_profile,
+            scan_cache::get_cached_repos,
+            remote_url::parse_remote_url,
+            remote_url::match_repos_to_projects,
+            diagnostics::remote_diagnostics,
+            fix::apply_fix,
+            fix::undo_fix,
+            branch::branch_action,
+            release::release,
+            release::repo_status,
+            clone::clone_repo,
+            init_remote::init_remote_repo,
+            github::github_list_repos,
+            github::github_list_branches,
+            github::github_default_branch,
+            pull_request::open_pull_request,
+            pull_request::pull_request_status,
+            pull_request::merge_pull_request,
+            hosting::hosting_resolve,
+            hosting::hosting_list_repos,
+            hosting::hosting_create_repo,
+            hosting::hosting_open_pull_request,
+            hosting::hosting_commit_status,
+            hosting::hosting_add_deploy_key,
+            webhook::webhook_start,
+            webhook::webhook_stop,
+            webhook::webhook_status,
+            webhook::webhook_deliveries,
+            scaffold::list_scaffold_templates
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wrapped_request(auth: &str, via_ssh: bool) -> RunActionRequest {
+        serde_json::from_value(serde_json::json!({
+            "mode": "wrapped",
+            "envKey": "test",
+            "action": "pull",
+            "localPath": "",
+            "remotePath": "/srv/app",
+            "branch": "main",
+            "gitPath": "",
+            "sshPath": "",
+            "ssh": { "host": "", "user": "" },
+            "remoteAuth": { "strategy": auth },
+            "wrapper": { "template": "docker exec -i app", "viaSsh": via_ssh },
+        }))
+        .unwrap()
+    }
+
+    // {path} を受け取って cd し、残りの引数を実行するだけの wrapper
+    fn fake_wrapper(dir: &Path) -> PathBuf {
+        let script = dir.join("fake-wrap");
+        std::fs::write(
+            &script,
+            "#!/bin/sh\necho \"fake-wrap $1\" >&2\ncd \"$1\" || exit 9\nshift\nexec \"$@\"\n",
+        )
+        .unwrap();
+        script
+    }
+
+    #[test]
+    fn civil_from_days_matches_known_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert_eq!(civil_from_days(20_744), (2026, 10, 18));
+    }
+
+    #[test]
+    fn local_wrapper_runs_body_in_remote_path() {
+        let dir = env::temp_dir().join(format!("gitshlc-wrap-{}", std::process::id()));
+        let work = dir.join("work dir");
+        std::fs::create_dir_all(&work).unwrap();
+        let script = fake_wrapper(&dir);
+        let template = format!(
+            "sh {} {{path}}",
+            shell_escape_posix_single(&path_to_string(&script))
+        );
+        let path = path_to_string(&work);
+        let auth = RemoteAuthPlan::default();
+        let repo = RemoteRepo {
+            transport: Transport::Local {
+                sh: PathBuf::from("sh"),
+            },
+            path: &path,
+            run_as: None,
+            wrapper: Some(&template),
+            auth: &auth,
+        };
+
+        let step = repo.run("pwd");
+        assert!(step.ok, "{}", step.stderr);
+        assert_eq!(step.stdout.trim(), path);
+        assert!(step.stderr.contains("fake-wrap"));
+
+        // ローカル wrapper の 255 は ssh の接続失敗ではない
+        let step = repo.run("exit 255");
+        assert_eq!(step.exit_code, 255);
+        assert_eq!(repo.classify(&step).code, "SSH-0200");
+        assert_eq!(classify_remote_failure(&step).code, "SSH-0100");
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn token_user_is_checked_before_it_reaches_the_helper() {
+        let auth = |user: &str| RemoteAuthConfig {
+            strategy: "token".into(),
+            token: Some("secret".into()),
+            token_user: Some(user.into()),
+            ..Default::default()
+        };
+
+        let plan = remote_auth_plan(Some(&auth("deploy-bot@example.com"))).unwrap();
+        assert!(plan.git_opts.contains("username=deploy-bot@example.com;"));
+        assert!(!plan.git_opts.contains("secret"));
+
+        for bad in ["x; rm -rf ~", "$(id)", "a`id`", "a b", "a'b"] {
+            let err = remote_auth_plan(Some(&auth(bad))).err().unwrap();
+            assert_eq!(err.code, "CFG-0310", "{}", bad);
+        }
+    }
+
+    #[test]
+    fn remote_detect_relists_only_changed_dirs() {
+        let base = env::temp_dir().join(format!("gitshlc-rdetect-{}", std::process::id()));
+        std::fs::remove_dir_all(&base).ok();
+        let root = base.join("root");
+        let home = base.join("home");
+        std::fs::create_dir_all(root.join("b/c")).unwrap();
+        std::fs::create_dir_all(&home).unwrap();
+        let git = PathBuf::from("git");
+        let init = |p: &Path| {
+            let s = run_capture(&git, &["init", "-q", &path_to_string(p)], None);
+            assert!(s.ok, "{}", s.stderr);
+        };
+        // stamp より確実に古くする
+        let age = |p: &Path| {
+            let s = run_capture(
+                Path::new("touch"),
+                &["-d", "2000-01-01", &path_to_string(p)],
+                None,
+            );
+            assert!(s.ok, "{}", s.stderr);
+        };
+        init(&root.join("a"));
+        let p = |rel: &str| path_to_string(&root.join(rel));
+        let rp = path_to_string(&root);
+
+        let profile = scan_profile::ScanProfile::default();
+        let run = |known: Vec<String>, dirs: Vec<String>, memo: &str, token: &str| {
+            let inc = RemoteIncremental {
+                stamp_name: "test".into(),
+                known,
+                dirs,
+                memo_token: memo.into(),
+                token: token.into(),
+            };
+            let (script, input) = remote_detect_script(&rp, 5, 50, &profile, Some(&inc)).unwrap();
+            let home = path_to_string(&home);
+            let step = run_capture_env(
+                Path::new("sh"),
+                &["-c", &script],
+                None,
+                Some(&input),
+                &[("HOME", &home)],
+            );
+            assert!(step.ok, "{}", step.stderr);
+            parse_remote_detect(&step.stdout)
+        };
+        let paths = |r: &RemoteDetect| {
+            let mut v: Vec<String> = r.repos.iter().map(|r| r.path.clone()).collect();
+            v.sort();
+            v
+        };
+
+        let first = run(vec![], vec![], "", "t1");
+        assert!(first.complete);
+        assert_eq!(paths(&first), [p("a")]);
+        let mut dirs = first.dirs.clone();
+        dirs.sort();
+        assert_eq!(dirs, [rp.clone(), p("a"), p("b"), p("b/c")]);
+        for d in &dirs {
+            age(Path::new(d));
+        }
+
+        // b/c は mtime が変わるので列挙し直す。b は mtime を戻すので新しい repo に気づかない
+        init(&root.join("b/c/new"));
+        init(&root.join("b/hidden"));
+        age(&root.join("b"));
+
+        let second = run(vec![p("a")], first.dirs.clone(), "t1", "t2");
+        assert_eq!(paths(&second), [p("b/c/new")]);
+        let unchanged: Vec<&str> = second.unchanged.iter().map(|u| u.0.as_str()).collect();
+        assert_eq!(unchanged, [p("a")]);
+        assert!(second.dirs.contains(&p("b/c/new")));
+        assert!(!second.dirs.contains(&p("b/hidden")));
+
+        // stamp が別の走査のものなら一覧は使わずに全部たどる
+        let third = run(vec![], second.dirs.clone(), "t1", "t3");
+        assert_eq!(paths(&third), [p("a"), p("b/c/new"), p("b/hidden")]);
+
+        std::fs::remove_dir_all(&base).ok();
+    }
+
+    #[test]
+    fn agent_auth_needs_ssh_in_wrapped_mode() {
+        let req = wrapped_request("agent", false);
+        let plan = remote_auth_plan(req.remote_auth.as_ref()).unwrap();
+        let err = remote_repo_for(&req, "/srv/app", &plan).err().unwrap();
+        assert_eq!(err.code, "CFG-0311");
+
+        let req = wrapped_request("none", false);
+        let plan = remote_auth_plan(req.remote_auth.as_ref()).unwrap();
+        assert!(remote_repo_for(&req, "/srv/app", &plan).is_ok());
+    }
+
+    #[test]
+    fn local_merge_surfaces_a_failed_dry_run() {
+        let git_path = git_exe(None).unwrap();
+        if !git_version::local_capabilities(&git_path)
+            .is_some_and(|c| c.supports(git_version::CAP_MERGE_TREE_WRITE_TREE))
+        {
+            return;
+        }
+        let git = |dir: &Path, args: &[&str]| {
+            let d = path_to_string(dir);
+            let mut full = vec!["-C", d.as_str()];
+            full.extend_from_slice(args);
+            let s = run_capture(Path::new("git"), &full, None);
+            assert!(s.ok, "git {:?}: {}", args, s.stderr);
+        };
+        let base = env::temp_dir().join(format!("gitshlc-merge-dry-{}", std::process::id()));
+        std::fs::remove_dir_all(&base).ok();
+        let origin = base.join("origin.git");
+        let work = base.join("work");
+        std::fs::create_dir_all(&origin).unwrap();
+        std::fs::create_dir_all(&work).unwrap();
+        git(&origin, &["init", "-q", "--bare"]);
+        git(&work, &["init", "-q"]);
+        git(&work, &["symbolic-ref", "HEAD", "refs/heads/main"]);
+        git(&work, &["config", "user.name", "Tester"]);
+        git(&work, &["config", "user.email", "tester@example.com"]);
+        git(&work, &["commit", "-q", "--allow-empty", "-m", "init"]);
+        let origin_url = path_to_string(&origin);
+        git(&work, &["remote", "add", "origin", &origin_url]);
+        git(&work, &["push", "-q", "-u", "origin", "main"]);
+        // feat は origin にだけある（ローカルの feat が無いので merge-tree は失敗する）
+        git(&work, &["checkout", "-q", "-b", "feat"]);
+        git(&work, &["commit", "-q", "--allow-empty", "-m", "feat"]);
+        git(&work, &["push", "-q", "origin", "feat"]);
+        git(&work, &["checkout", "-q", "main"]);
+        git(&work, &["branch", "-q", "-D", "feat"]);
+
+        let merge = |from: &str| {
+            run_action(
+                serde_json::from_value(serde_json::json!({
+                    "mode": "local",
+                    "envKey": "test",
+                    "action": "merge",
+                    "localPath": path_to_string(&work),
+                    "remotePath": "",
+                    "branch": "main",
+                    "gitPath": "",
+                    "sshPath": "",
+                    "ssh": { "host": "", "user": "" },
+                    "mergeFromBranch": from,
+                }))
+                .unwrap(),
+            )
+        };
+        let out = merge("feat");
+        let e = out.error.unwrap();
+        assert_eq!(e.code, "GIT-0112");
+        assert!(!e.detail.unwrap_or_default().is_empty());
+        let last = out.steps.last().unwrap();
+        assert!(last.cmd.contains("merge-tree") && !last.ok);
+        assert!(!out.steps.iter().any(|s| s.cmd.contains("--no-ff")));
+
+        // 本当に衝突するときは従来どおり GIT-0111
+        git(&work, &["checkout", "-q", "-b", "clash"]);
+        std::fs::write(work.join("f.txt"), "clash\n").unwrap();
+        git(&work, &["add", "f.txt"]);
+        git(&work, &["commit", "-q", "-m", "clash"]);
+        git(&work, &["checkout", "-q", "main"]);
+        std::fs::write(work.join("f.txt"), "main\n").unwrap();
+        git(&work, &["add", "f.txt"]);
+        git(&work, &["commit", "-q", "-m", "main"]);
+        let out = merge("clash");
+        let e = out.error.unwrap();
+        assert_eq!(e.code, "GIT-0111");
+        assert!(e.detail.unwrap_or_default().contains("f.txt"));
+
+        std::fs::remove_dir_all(&base).ok();
+    }
+}
@@ -0,0 +1,459 @@
+// `git ls-remote` で remote の ref 一覧（SHA / tag / default branch）を取る。
+// private な HTTPS repo は askpass 経由で token を渡し、結果は URL ごとに TTL 付きでキャッシュする
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager, State};
+
+use crate::{git_exe, git_version, remote_url, run_capture_env};
+
+const DEFAULT_TTL_SECS: u64 = 60;
+const ASKPASS_FILE: &str = "askpass.sh";
+
+// git が "Username for ..." / "Password for ..." を引数にして呼ぶ
+const ASKPASS_SCRIPT: &str = r#"#!/bin/sh
+case "$1" in
+  Username*|username*) printf '%s\n' "$GITSHLC_ASKPASS_USER" ;;
+  *) printf '%s\n' "$GITSHLC_ASKPASS_TOKEN" ;;
+esac
+"#;
+
+#[derive(Default)]
+pub(crate) struct BranchCache {
+    entries: Mutex<HashMap<String, (Instant, BranchListWire)>>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RemoteRef {
+    // refs/heads/ / refs/tags/ を除いた名前
+    name: String,
+    // branch | tag
+    kind: String,
+    sha: String,
+    // annotated tag が指す commit（^{} の行）
+    peeled: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BranchListWire {
+    ok: bool,
+    // 従来どおりブランチ名だけ（ソート済み）
+    branches: Vec<String>,
+    stderr: Option<String>,
+    refs: Vec<RemoteRef>,
+    // ls-remote --symref HEAD の参照先（git が古い / HEAD が無ければ None）
+    default_branch: Option<String>,
+    head_sha: Option<String>,
+    // unix millis
+    fetched_at: Option<u64>,
+    // キャッシュから返したか
+    cached: bool,
+}
+
+impl BranchListWire {
+    fn failed(stderr: String) -> Self {
+        BranchListWire {
+            ok: false,
+            branches: vec![],
+            stderr: Some(stderr),
+            refs: vec![],
+            default_branch: None,
+            head_sha: None,
+            fetched_at: None,
+            cached: false,
+        }
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// 同じ repo の別表記（scp / https / .git の有無）は同じキーにする。
+// token ごとに見える ref が違うので token のハッシュも含める（token 自体は持たない）
+fn cache_key(repo_url: &str, include_tags: bool, token: Option<&str>) -> String {
+    let url = remote_url::parse(repo_url)
+        .map(|u| u.normalized())
+        .unwrap_or_else(|| repo_url.trim().to_string());
+    let auth = match token {
+        Some(t) => hex::encode(&Sha256::digest(t.as_bytes())[..8]),
+        None => "anon".into(),
+    };
+    format!(
+        "{}#{}#{}",
+        url,
+        if include_tags { "tags" } else { "heads" },
+        auth
+    )
+}
+
+// askpass スクリプトは秘密を含まない（token は環境変数で渡す）。app cache dir に置く
+pub(crate) fn askpass_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_cache_dir().map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(ASKPASS_FILE);
+    if std::fs::read_to_string(&path).ok().as_deref() != Some(ASKPASS_SCRIPT) {
+        std::fs::write(&path, ASKPASS_SCRIPT).map_err(|e| e.to_string())?;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o700))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(path)
+}
+
+// token を askpass 経由で git に渡すための -c と環境変数（token 無しなら空）
+#[derive(Debug, Default)]
+pub(crate) struct GitAuth {
+    args: Vec<String>,
+    envs: Vec<(&'static str, String)>,
+}
+
+impl GitAuth {
+    pub(crate) fn token(askpass: &Path, user: &str, token: &str) -> Self {
+        GitAuth {
+            // 保存済みの credential.helper より token を優先させる
+            args: vec!["-c".into(), "credential.helper=".into()],
+            envs: vec![
+                ("GIT_ASKPASS", crate::path_to_string(askpass)),
+                ("GITSHLC_ASKPASS_USER", user.to_string()),
+                ("GITSHLC_ASKPASS_TOKEN", token.to_string()),
+            ],
+        }
+    }
+
+    /// git のサブコマンドより前に置く引数
+    pub(crate) fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    pub(crate) fn envs(&self) -> Vec<(&str, &str)> {
+        self.envs.iter().map(|(k, v)| (*k, v.as_str())).collect()
+    }
+}
+
+fn parse_ls_remote(stdout: &str, out: &mut BranchListWire) {
+    let mut refs: Vec<RemoteRef> = Vec::new();
+    for line in stdout.lines() {
+        // ref: refs/heads/main\tHEAD
+        if let Some(rest) = line.strip_prefix("ref: ") {
+            if let Some((target, "HEAD")) = rest.split_once('\t') {
+                out.default_branch = target
+                    .strip_prefix("refs/heads/")
+                    .map(|b| b.trim().to_string());
+            }
+            continue;
+        }
+        // <sha>\t<ref>
+        let Some((sha, r)) = line.split_once('\t') else {
+            continue;
+        };
+        let sha = sha.trim().to_string();
+        let r = r.trim();
+        if r == "HEAD" {
+            out.head_sha = Some(sha);
+        } else if let Some(b) = r.strip_prefix("refs/heads/") {
+            refs.push(RemoteRef {
+                name: b.to_string(),
+                kind: "branch".into(),
+                sha,
+                peeled: None,
+            });
+        } else if let Some(t) = r.strip_prefix("refs/tags/") {
+            // v1.0^{} は直前の v1.0 の peeled
+            if let Some(base) = t.strip_suffix("^{}") {
+                if let Some(tag) = refs
+                    .iter_mut()
+                    .rev()
+                    .find(|x| x.kind == "tag" && x.name == base)
+                {
+                    tag.peeled = Some(sha);
+                }
+                continue;
+            }
+            refs.push(RemoteRef {
+                name: t.to_string(),
+                kind: "tag".into(),
+                sha,
+                peeled: None,
+            });
+        }
+    }
+
+    let mut branches: Vec<String> = refs
+        .iter()
+        .filter(|r| r.kind == "branch" && !r.name.is_empty())
+        .map(|r| r.name.clone())
+        .collect();
+    branches.sort();
+    branches.dedup();
+    out.branches = branches;
+    out.refs = refs;
+}
+
+fn ls_remote(git: &Path, auth: &GitAuth, repo_url: &str, include_tags: bool) -> BranchListWire {
+    let symref = git_version::local_capabilities(git)
+        .map(|c| c.supports(git_version::CAP_LS_REMOTE_SYMREF))
+        .unwrap_or(false);
+
+    let mut args: Vec<String> = auth.args().to_vec();
+    args.push("ls-remote".into());
+    if symref {
+        args.push("--symref".into());
+    }
+    args.push(repo_url.to_string());
+    args.push("HEAD".into());
+    args.push("refs/heads/*".into());
+    if include_tags {
+        args.push("refs/tags/*".into());
+    }
+
+    let refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    let step = run_capture_env(git, &refs, None, None, &auth.envs());
+    if !step.ok {
+        let msg = if !step.stderr.trim().is_empty() {
+            step.stderr
+        } else {
+            step.stdout
+        };
+        return BranchListWire::failed(msg);
+    }
+
+    let mut out = BranchListWire {
+        ok: true,
+        branches: vec![],
+        stderr: None,
+        refs: vec![],
+        default_branch: None,
+        head_sha: None,
+        fetched_at: Some(now_millis()),
+        cached: false,
+    };
+    parse_ls_remote(&step.stdout, &mut out);
+    out
+}
+
+/// remote のブランチ（と tag）を SHA 付きで返す。成功した結果は ttlSecs の間キャッシュする
+#[tauri::command(async, rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn list_branches(
+    app: AppHandle,
+    cache: State<'_, BranchCache>,
+    repo_url: String,
+    git_path: Option<String>,
+    include_tags: Option<bool>,
+    token: Option<String>,
+    token_user: Option<String>,
+    ttl_secs: Option<u64>,
+    refresh: Option<bool>,
+) -> BranchListWire {
+    let Some(git) = git_exe(git_path) else {
+        return BranchListWire::failed("git not found".into());
+    };
+    let repo_url = repo_url.trim().to_string();
+    let include_tags = include_tags.unwrap_or(false);
+    let token = token
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty());
+    let key = cache_key(&repo_url, include_tags, token.as_deref());
+    let ttl = Duration::from_secs(ttl_secs.unwrap_or(DEFAULT_TTL_SECS));
+
+    if !refresh.unwrap_or(false) {
+        let entries = cache.entries.lock().unwrap();
+        if let Some((at, hit)) = entries.get(&key) {
+            if at.elapsed() < ttl {
+                let mut hit = hit.clone();
+                hit.cached = true;
+                return hit;
+            }
+        }
+    }
+
+    let token_user = token_user
+        .map(|u| u.trim().to_string())
+        .filter(|u| !u.is_empty())
+        .unwrap_or_else(|| "x-access-token".into());
+    let auth = match &token {
+        Some(t) => match askpass_path(&app) {
+            Ok(p) => GitAuth::token(&p, &token_user, t),
+            Err(e) => return BranchListWire::failed(format!("askpass setup failed: {}", e)),
+        },
+        None => GitAuth::default(),
+    };
+
+    let out = ls_remote(&git, &auth, &repo_url, include_tags);
+    if out.ok {
+        cache
+            .entries
+            .lock()
+            .unwrap()
+            .insert(key, (Instant::now(), out.clone()));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_key_separates_tokens() {
+        let anon = cache_key("git@github.com:Octo/App.git", false, None);
+        assert_eq!(anon, cache_key("https://github.com/octo/app", false, None));
+        assert!(anon.ends_with("#heads#anon"));
+
+        let a = cache_key("https://github.com/octo/app", false, Some("tok-a"));
+        let b = cache_key("https://github.com/octo/app", false, Some("tok-b"));
+        assert_ne!(a, anon);
+        assert_ne!(a, b);
+        assert!(!a.contains("tok-a"));
+        assert_ne!(
+            a,
+            cache_key("https://github.com/octo/app", true, Some("tok-a"))
+        );
+    }
+
+    fn parsed(stdout: &str) -> BranchListWire {
+        let mut out = BranchListWire::failed(String::new());
+        parse_ls_remote(stdout, &mut out);
+        out
+    }
+
+    // (name, kind, sha, peeled)
+    fn refs(out: &BranchListWire) -> Vec<(&str, &str, &str, Option<&str>)> {
+        out.refs
+            .iter()
+            .map(|r| {
+                (
+                    r.name.as_str(),
+                    r.kind.as_str(),
+                    r.sha.as_str(),
+                    r.peeled.as_deref(),
+                )
+            })
+            .collect()
+    }
+
+    const MAIN: &str = "5f3c2a9d1b7e4c6a8f0e2d4b6a8c0e2f4a6b8d0c";
+    const DEV: &str = "0a1b2c3d4e5f60718293a4b5c6d7e8f901234567";
+    const TAG: &str = "9e8d7c6b5a49382716f5e4d3c2b1a09f8e7d6c5b";
+
+    #[test]
+    fn parse_ls_remote_reads_real_output() {
+        // git ls-remote --symref <url> HEAD 'refs/heads/*' 'refs/tags/*'
+        let symref = format!(
+            "ref: refs/heads/main\tHEAD\n{m}\tHEAD\n{d}\trefs/heads/dev\n{m}\trefs/heads/main\n\
+             {t}\trefs/tags/v1.0\n{m}\trefs/tags/v1.0^{{}}\n{d}\trefs/tags/light\n",
+            m = MAIN,
+            d = DEV,
+            t = TAG,
+        );
+        let out = parsed(&symref);
+        assert_eq!(out.default_branch.as_deref(), Some("main"));
+        assert_eq!(out.head_sha.as_deref(), Some(MAIN));
+        assert_eq!(out.branches, ["dev", "main"]);
+        assert_eq!(
+            refs(&out),
+            [
+                ("dev", "branch", DEV, None),
+                ("main", "branch", MAIN, None),
+                // annotated tag は tag object の SHA と ^{} の commit
+                ("v1.0", "tag", TAG, Some(MAIN)),
+                ("light", "tag", DEV, None),
+            ]
+        );
+
+        // --symref が無い古い git: HEAD の SHA だけ
+        let out = parsed(&format!("{m}\tHEAD\n{m}\trefs/heads/main\n", m = MAIN));
+        assert_eq!(out.default_branch, None);
+        assert_eq!(out.head_sha.as_deref(), Some(MAIN));
+        assert_eq!(out.branches, ["main"]);
+
+        // 空の repo は何も出さない
+        let out = parsed("");
+        assert!(out.branches.is_empty() && out.refs.is_empty() && out.head_sha.is_none());
+
+        // default branch の名前に / を含む、対になる tag が無い ^{} は無視
+        let out = parsed(&format!(
+            "ref: refs/heads/release/2.x\tHEAD\n{d}\tHEAD\n{d}\trefs/heads/release/2.x\n\
+             {m}\trefs/tags/orphan^{{}}\n",
+            m = MAIN,
+            d = DEV,
+        ));
+        assert_eq!(out.default_branch.as_deref(), Some("release/2.x"));
+        assert_eq!(refs(&out), [("release/2.x", "branch", DEV, None)]);
+    }
+
+    #[test]
+    fn ls_remote_reads_a_local_repo() {
+        use crate::{path_to_string, run_capture};
+
+        let git = |dir: &Path, args: &[&str]| {
+            let d = path_to_string(dir);
+            let mut full = vec!["-C", d.as_str()];
+            full.extend_from_slice(args);
+            let s = run_capture(Path::new("git"), &full, None);
+            assert!(s.ok, "git {:?}: {}", args, s.stderr);
+            s.stdout.trim().to_string()
+        };
+        let root = std::env::temp_dir().join(format!("gitshlc-ls-remote-{}", std::process::id()));
+        std::fs::remove_dir_all(&root).ok();
+        std::fs::create_dir_all(&root).unwrap();
+        git(&root, &["init", "-q"]);
+        git(&root, &["symbolic-ref", "HEAD", "refs/heads/trunk"]);
+        git(&root, &["config", "user.name", "Tester"]);
+        git(&root, &["config", "user.email", "tester@example.com"]);
+        git(&root, &["commit", "-q", "--allow-empty", "-m", "init"]);
+        git(&root, &["tag", "-a", "v1", "-m", "v1"]);
+        git(&root, &["branch", "topic"]);
+        let head = git(&root, &["rev-parse", "HEAD"]);
+        let tag = git(&root, &["rev-parse", "v1"]);
+
+        let exe = git_exe(None).unwrap();
+        let out = ls_remote(&exe, &GitAuth::default(), &path_to_string(&root), true);
+        assert!(out.ok, "{:?}", out.stderr);
+        assert_eq!(out.branches, ["topic", "trunk"]);
+        assert_eq!(out.head_sha.as_deref(), Some(head.as_str()));
+        if git_version::local_capabilities(&exe)
+            .is_some_and(|c| c.supports(git_version::CAP_LS_REMOTE_SYMREF))
+        {
+            assert_eq!(out.default_branch.as_deref(), Some("trunk"));
+        }
+        let v1 = out.refs.iter().find(|r| r.name == "v1").unwrap();
+        assert_eq!(v1.sha, tag);
+        assert_eq!(v1.peeled.as_deref(), Some(head.as_str()));
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn askpass_script_answers_from_env() {
+        let ask = |prompt: &str| {
+            let out = std::process::Command::new("sh")
+                .args(["-c", ASKPASS_SCRIPT, "askpass", prompt])
+                .env("GITSHLC_ASKPASS_USER", "x-access-token")
+                .env("GITSHLC_ASKPASS_TOKEN", "tok")
+                .output()
+                .unwrap();
+            String::from_utf8_lossy(&out.stdout).into_owned()
+        };
+        assert_eq!(
+            ask("Username for 'https://github.com': "),
+            "x-access-token\n"
+        );
+        assert_eq!(ask("Password for 'https://x@github.com': "), "tok\n");
+    }
+}
@@ -0,0 +1,849 @@
+// protected branch 向け: merge --no-ff + push の代わりに GitHub の pull request を作って API で merge する
+use std::time::Duration;
+
+use crate::api::{ApiClient, ApiError};
+use crate::github;
+use crate::hosting::{self, HostingRequest, HostingTarget, ProviderKind};
+use crate::repo_target::RepoTarget;
+use crate::{remote_auth_plan, ActionError, ActionOutcome, RunActionRequest, StepResult};
+
+// mergeable は GitHub 側で非同期に計算されるので、null の間は少し待って取り直す
+const MERGEABLE_POLLS: u32 = 3;
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PullRequestGitHub {
+    token: String,
+    // 無ければ origin の host から（GHE の自前ホストなら "github" を指定）
+    #[serde(default)]
+    provider: Option<String>,
+    #[serde(default)]
+    api_base_url: Option<String>,
+    // owner/name（無ければ origin の URL から取る）
+    #[serde(default)]
+    repo: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct OpenPullRequestOptions {
+    // default: "Merge <head> into <base>"
+    title: Option<String>,
+    body: Option<String>,
+    draft: bool,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct MergePullRequestOptions {
+    // merge | squash | rebase（default merge）
+    method: Option<String>,
+    commit_title: Option<String>,
+    commit_message: Option<String>,
+    // merge 後に環境で pull する（default true）
+    pull: Option<bool>,
+    // 承認が無くても merge する（changes requested は常に止める）
+    allow_unreviewed: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PullRequestInfo {
+    number: u64,
+    url: String,
+    // open | closed
+    state: String,
+    draft: bool,
+    merged: bool,
+    // null = GitHub 側でまだ計算中
+    mergeable: Option<bool>,
+    // clean / blocked / behind / dirty / unstable / unknown など
+    mergeable_state: Option<String>,
+    head: String,
+    base: String,
+    head_sha: String,
+    // combined status（success / pending / failure / error）
+    status_state: Option<String>,
+    // approved / changes_requested / none
+    review_decision: Option<String>,
+    approvals: u32,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PullRequestOutcome {
+    ok: bool,
+    repo: Option<String>,
+    pr: Option<PullRequestInfo>,
+    // false = 同じ head/base の open な PR を再利用した
+    created: bool,
+    merge_sha: Option<String>,
+    // merge 後の pull（run_action action=pull と同じ）
+    pull: Option<ActionOutcome>,
+    steps: Vec<StepResult>,
+    error: Option<ActionError>,
+}
+
+fn pr_err(code: &str, message: &str, detail: Option<String>) -> ActionError {
+    ActionError {
+        code: code.into(),
+        severity: "ERROR".into(),
+        message: message.into(),
+        detail,
+    }
+}
+
+fn opt(s: &Option<String>) -> Option<&str> {
+    s.as_deref().map(str::trim).filter(|x| !x.is_empty())
+}
+
+struct Pr<'a> {
+    client: ApiClient,
+    repo: String,
+    out: &'a mut PullRequestOutcome,
+}
+
+impl Pr<'_> {
+    // API 呼び出しも steps に残す（token は出さない）
+    fn call(
+        &mut self,
+        method: &str,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value, ApiError> {
+        let path = format!("/repos/{}{}", self.repo, path);
+        let cmd = format!("{} {}", method, self.client.url(&path));
+        let res = match body {
+            Some(b) => self.client.send_json(method, &path, b),
+            None => self.client.get_json(&path),
+        };
+        self.out.steps.push(StepResult {
+            cmd,
+            cwd: None,
+            ok: res.is_ok(),
+            exit_code: match &res {
+                Ok(_) => 0,
+                Err(e) => e.status().map(i32::from).unwrap_or(1),
+            },
+            stdout: "".into(),
+            stderr: res
+                .as_ref()
+                .err()
+                .map(|e| e.message().to_string())
+                .unwrap_or_default(),
+        });
+        res
+    }
+
+    fn get(&mut self, number: u64) -> Result<PullRequestInfo, ApiError> {
+        let mut v = self.call("GET", &format!("/pulls/{}", number), None)?;
+        for _ in 0..MERGEABLE_POLLS {
+            let open = v.get("state").and_then(|x| x.as_str()) == Some("open");
+            if !open || !v.get("mergeable").is_some_and(|m| m.is_null()) {
+                break;
+            }
+            std::thread::sleep(Duration::from_secs(1));
+            v = self.call("GET", &format!("/pulls/{}", number), None)?;
+        }
+        Ok(pr_info(&v))
+    }
+
+    // combined status とレビューを埋める（取れなくても PR 自体は返す）
+    fn fill_status(&mut self, pr: &mut PullRequestInfo) {
+        if !pr.head_sha.is_empty() {
+            if let Ok(v) = self.call("GET", &format!("/commits/{}/status", pr.head_sha), None) {
+                pr.status_state = v.get("state").and_then(|x| x.as_str()).map(String::from);
+            }
+        }
+        let path = format!(
+            "/repos/{}/pulls/{}/reviews?per_page=100",
+            self.repo, pr.number
+        );
+        if let Ok((reviews, _)) = self.client.get_all(&path, None) {
+            let (decision, approvals) = review_decision(&reviews);
+            pr.review_decision = Some(decision.into());
+            pr.approvals = approvals;
+        }
+    }
+}
+
+fn pr_info(v: &serde_json::Value) -> PullRequestInfo {
+    let s = |p: &str| {
+        v.pointer(p)
+            .and_then(|x| x.as_str())
+            .unwrap_or("")
+            .to_string()
+    };
+    PullRequestInfo {
+        number: v.get("number").and_then(|x| x.as_u64()).unwrap_or(0),
+        url: s("/html_url"),
+        state: s("/state"),
+        draft: v.get("draft").and_then(|x| x.as_bool()).unwrap_or(false),
+        merged: v.get("merged").and_then(|x| x.as_bool()).unwrap_or(false),
+        mergeable: v.get("mergeable").and_then(|x| x.as_bool()),
+        mergeable_state: v
+            .get("mergeable_state")
+            .and_then(|x| x.as_str())
+            .map(String::from),
+        head: s("/head/ref"),
+        base: s("/base/ref"),
+        head_sha: s("/head/sha"),
+        status_state: None,
+        review_decision: None,
+        approvals: 0,
+    }
+}
+
+// レビュアーごとに最後の APPROVED / CHANGES_REQUESTED だけを見る（COMMENTED は数えない）
+fn review_decision(reviews: &[serde_json::Value]) -> (&'static str, u32) {
+    let mut latest: Vec<(String, String)> = Vec::new();
+    for r in reviews {
+        let user = r
+            .pointer("/user/login")
+            .and_then(|x| x.as_str())
+            .unwrap_or("");
+        let state = r.get("state").and_then(|x| x.as_str()).unwrap_or("");
+        if !matches!(state, "APPROVED" | "CHANGES_REQUESTED" | "DISMISSED") {
+            continue;
+        }
+        match latest.iter_mut().find(|(u, _)| u == user) {
+            Some(e) => e.1 = state.into(),
+            None => latest.push((user.into(), state.into())),
+        }
+    }
+    let approvals = latest.iter().filter(|(_, s)| s == "APPROVED").count() as u32;
+    if latest.iter().any(|(_, s)| s == "CHANGES_REQUESTED") {
+        ("changes_requested", approvals)
+    } else if approvals > 0 {
+        ("approved", approvals)
+    } else {
+        ("none", approvals)
+    }
+}
+
+fn outcome() -> PullRequestOutcome {
+    PullRequestOutcome {
+        ok: false,
+        repo: None,
+        pr: None,
+        created: false,
+        merge_sha: None,
+        pull: None,
+        steps: Vec::new(),
+        error: None,
+    }
+}
+
+// github.repo が無ければ環境の origin URL から provider と owner/name（と GHE の API base）を取る
+fn resolve_target(
+    req: &RunActionRequest,
+    gh: &PullRequestGitHub,
+    steps: &mut Vec<StepResult>,
+) -> Result<HostingTarget, ActionError> {
+    let origin = match opt(&gh.repo) {
+        Some(_) => None,
+        None => {
+            let remote_path = req.remote_path.trim().to_string();
+            let auth = remote_auth_plan(req.remote_auth.as_ref())?;
+            let target = RepoTarget::open(req, &remote_path, &auth)?;
+            let step = target.git(&["remote", "get-url", "origin"]);
+            let ok = step.ok;
+            let url = step.stdout.trim().to_string();
+            if !ok {
+                let e = target.failure(&step, "PR-0002", "failed to read origin url");
+                steps.push(step);
+                return Err(e);
+            }
+            steps.push(step);
+            Some(url)
+        }
+    };
+
+    let target = hosting::resolve(&HostingRequest::from_origin(
+        origin.clone(),
+        gh.provider.clone(),
+        gh.api_base_url.clone(),
+        &gh.token,
+        gh.repo.clone(),
+    ))?;
+    // review / mergeable の判定が GitHub の API 前提なので他のホスティングは受けない
+    if target.provider != ProviderKind::GitHub {
+        return Err(pr_err(
+            "PR-0003",
+            "pull request flow supports GitHub only",
+            Some(format!("{:?} ({})", target.provider, target.api_base_url)),
+        ));
+    }
+    if target.repo.is_none() {
+        return Err(pr_err(
+            "PR-0002",
+            "cannot derive owner/name from origin (set github.repo)",
+            origin,
+        ));
+    }
+    Ok(target)
+}
+
+fn begin<'a>(
+    req: &RunActionRequest,
+    gh: &PullRequestGitHub,
+    out: &'a mut PullRequestOutcome,
+) -> Result<Pr<'a>, ActionError> {
+    if gh.token.trim().is_empty() {
+        return Err(pr_err("CFG-0401", "github.token is required", None));
+    }
+    let target = resolve_target(req, gh, &mut out.steps)?;
+    let repo = target.repo.clone().unwrap_or_default();
+    out.repo = Some(repo.clone());
+    Ok(Pr {
+        client: hosting::client_for(&target, &gh.token),
+        repo,
+        out,
+    })
+}
+
+/// mergeFromBranch → branch の pull request を作る（同じ組み合わせの open な PR があればそれを返す）
+#[tauri::command(async, rename_all = "camelCase")]
+pub(crate) fn open_pull_request(
+    req: RunActionRequest,
+    github: PullRequestGitHub,
+    options: Option<OpenPullRequestOptions>,
+) -> PullRequestOutcome {
+    let options = options.unwrap_or_default();
+    let mut out = outcome();
+
+    let head = req
+        .merge_from_branch
+        .as_deref()
+        .unwrap_or("")
+        .trim()
+        .to_string();
+    let base = req.branch.trim().to_string();
+    if head.is_empty() || base.is_empty() {
+        out.error = Some(pr_err(
+            "PR-0001",
+            "mergeFromBranch and branch are required",
+            None,
+        ));
+        return out;
+    }
+    if head == base {
+        out.error = Some(pr_err(
+            "PR-0001",
+            "mergeFromBranch must differ from branch",
+            Some(head),
+        ));
+        return out;
+    }
+
+    let mut pr = match begin(&req, &github, &mut out) {
+        Ok(p) => p,
+        Err(e) => {
+            out.error = Some(e);
+            return out;
+        }
+    };
+
+    let title = opt(&options.title)
+        .map(String::from)
+        .unwrap_or_else(|| format!("Merge {} into {}", head, base));
+    let mut body = serde_json::json!({
+        "title": title,
+        "head": head,
+        "base": base,
+        "draft": options.draft,
+    });
+    if let Some(b) = opt(&options.body) {
+        body["body"] = b.into();
+    }
+
+    let created = pr.call("POST", "/pulls", Some(body));
+    let res = match created {
+        Ok(v) => Ok((v, true)),
+        // 422 は既に open な PR がある場合もある → 探して返す
+        Err(e) if e.status() == Some(422) => {
+            let owner = pr.repo.split('/').next().unwrap_or("").to_string();
+            match pr.call(
+                "GET",
+                &format!(
+                    "/pulls?state=open&head={}&base={}",
+                    github::url_encode(&format!("{}:{}", owner, head)),
+                    github::url_encode(&base)
+                ),
+                None,
+            ) {
+                Ok(serde_json::Value::Array(list)) if !list.is_empty() => {
+                    Ok((list[0].clone(), false))
+                }
+                _ => Err(e),
+            }
+        }
+        Err(e) => Err(e),
+    };
+
+    let (v, created) = match res {
+        Ok(x) => x,
+        Err(e) => {
+            out.error = Some(e.into_action("GH", "failed to create pull request"));
+            return out;
+        }
+    };
+    let mut info = pr_info(&v);
+    pr.fill_status(&mut info);
+    out.pr = Some(info);
+    out.created = created;
+    out.ok = true;
+    out
+}
+
+/// PR の mergeable / combined status / レビュー状況
+#[tauri::command(async, rename_all = "camelCase")]
+pub(crate) fn pull_request_status(
+    req: RunActionRequest,
+    github: PullRequestGitHub,
+    number: u64,
+) -> PullRequestOutcome {
+    let mut out = outcome();
+    let mut pr = match begin(&req, &github, &mut out) {
+        Ok(p) => p,
+        Err(e) => {
+            out.error = Some(e);
+            return out;
+        }
+    };
+    match pr.get(number) {
+        Ok(mut info) => {
+            pr.fill_status(&mut info);
+            out.pr = Some(info);
+            out.ok = true;
+        }
+        Err(e) => out.error = Some(e.into_action("GH", "failed to get pull request")),
+    }
+    out
+}
+
+/// PR を merge（merge / squash / rebase）して、そのあと環境で通常の pull を行う
+#[tauri::command(async, rename_all = "camelCase")]
+pub(crate) fn merge_pull_request(
+    req: RunActionRequest,
+    github: PullRequestGitHub,
+    number: u64,
+    options: Option<MergePullRequestOptions>,
+) -> PullRequestOutcome {
+    let options = options.unwrap_or_default();
+    let mut out = outcome();
+
+    let method = opt(&options.method).unwrap_or("merge").to_string();
+    if !matches!(method.as_str(), "merge" | "squash" | "rebase") {
+        out.error = Some(pr_err(
+            "PR-0001",
+            "method must be merge | squash | rebase",
+            Some(method),
+        ));
+        return out;
+    }
+
+    let mut pr = match begin(&req, &github, &mut out) {
+        Ok(p) => p,
+        Err(e) => {
+            out.error = Some(e);
+            return out;
+        }
+    };
+
+    // 先に状態を見る（merge 済み・draft・conflict・未承認は API に投げる前に止める）
+    let mut info = match pr.get(number) {
+        Ok(i) => i,
+        Err(e) => {
+            out.error = Some(e.into_action("GH", "failed to get pull request"));
+            return out;
+        }
+    };
+    pr.fill_status(&mut info);
+    let blocked = if info.merged {
+        Some(("PR-0103", "pull request is already merged"))
+    } else if info.state != "open" {
+        Some(("PR-0103", "pull request is closed"))
+    } else if info.draft {
+        Some(("PR-0101", "pull request is a draft"))
+    } else if info.mergeable == Some(false) {
+        Some(("PR-0101", "pull request is not mergeable (conflicts)"))
+    } else {
+        None
+    };
+    if let Some((code, message)) = blocked {
+        let detail = info.mergeable_state.clone();
+        out.pr = Some(info);
+        out.error = Some(pr_err(code, message, detail));
+        return out;
+    }
+
+    // 承認が無ければ止める（レビューが取れなかった場合も同じ。allowUnreviewed でも changes requested は止める）
+    let review = info.review_decision.clone();
+    let unreviewed = match review.as_deref() {
+        Some("approved") => None,
+        Some("changes_requested") => Some("changes were requested on the pull request"),
+        _ if options.allow_unreviewed => None,
+        _ => Some("pull request is not approved"),
+    };
+    if let Some(message) = unreviewed {
+        out.pr = Some(info);
+        out.error = Some(pr_err("PR-0101", message, review));
+        return out;
+    }
+
+    // sha を付けて、確認したあとに head が動いていたら merge させない
+    let mut body = serde_json::json!({
+        "merge_method": method,
+        "sha": info.head_sha,
+    });
+    if let Some(t) = opt(&options.commit_title) {
+        body["commit_title"] = t.into();
+    }
+    if let Some(m) = opt(&options.commit_message) {
+        body["commit_message"] = m.into();
+    }
+    let merged = pr.call("PUT", &format!("/pulls/{}/merge", number), Some(body));
+    let merge_sha = match merged {
+        Ok(v) if v.get("merged").and_then(|x| x.as_bool()) == Some(true) => {
+            v.get("sha").and_then(|x| x.as_str()).map(String::from)
+        }
+        Ok(v) => {
+            out.pr = Some(info);
+            out.error = Some(pr_err(
+                "PR-0102",
+                "pull request was not merged",
+                v.get("message").and_then(|x| x.as_str()).map(String::from),
+            ));
+            return out;
+        }
+        Err(e) => {
+            out.pr = Some(info);
+            // 405 = branch protection（レビュー・必須チェック）で merge できない、409 = head が動いた
+            out.error = Some(match e.status() {
+                Some(405) | Some(409) => pr_err(
+                    "PR-0102",
+                    "GitHub refused to merge the pull request",
+                    Some(e.message().to_string()),
+                ),
+                _ => e.into_action("GH", "failed to merge pull request"),
+            });
+            return out;
+        }
+    };
+    info.merged = true;
+    info.state = "closed".into();
+    out.pr = Some(info);
+    out.merge_sha = merge_sha;
+
+    // 環境を base に追従させる（通常の pull）
+    if options.pull.unwrap_or(true) {
+        let mut pull_req = req.clone();
+        pull_req.action = "pull".into();
+        let pulled = crate::run_action(pull_req);
+        if !pulled.ok {
+            out.error = Some(pr_err(
+                "PR-0104",
+                "merged on GitHub, but pull on the environment failed",
+                pulled.error.as_ref().map(|e| e.code.clone()),
+            ));
+            out.pull = Some(pulled);
+            return out;
+        }
+        out.pull = Some(pulled);
+    }
+    out.ok = true;
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_http::{MockServer, Reply};
+
+    fn request(head: &str, base: &str) -> RunActionRequest {
+        serde_json::from_value(serde_json::json!({
+            "mode": "local",
+            "envKey": "prod",
+            "action": "merge",
+            "localPath": "",
+            "remotePath": "",
+            "branch": base,
+            "gitPath": "",
+            "sshPath": "",
+            "ssh": {"host": "", "user": "", "port": null, "keyPath": null},
+            "mergeFromBranch": head,
+            "commitMessage": null,
+        }))
+        .unwrap()
+    }
+
+    fn github(base: &str, repo: &str) -> PullRequestGitHub {
+        serde_json::from_value(serde_json::json!({
+            "token": "tok",
+            "apiBaseUrl": base,
+            "repo": repo,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn github_repo_is_validated() {
+        let server = MockServer::start(|_, _| Reply::json(500, serde_json::json!({})));
+        for repo in ["../..", "octo", "octo/app/extra", "oc to/app"] {
+            let out = open_pull_request(request("feat", "main"), github(&server.base, repo), None);
+            assert_eq!(out.error.unwrap().code, "CFG-0401", "{}", repo);
+        }
+        assert!(server.requests().is_empty());
+    }
+
+    #[test]
+    fn provider_comes_from_origin_and_non_github_is_rejected() {
+        let dir = std::env::temp_dir().join(format!("gitshlc-pr-origin-{}", std::process::id()));
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        let d = dir.to_string_lossy().to_string();
+        let git = |args: &[&str]| {
+            let mut a = vec!["-C", d.as_str()];
+            a.extend_from_slice(args);
+            crate::run_capture(std::path::Path::new("git"), &a, None)
+        };
+        git(&["init", "-q"]);
+        git(&["remote", "add", "origin", "git@gitlab.com:acme/app.git"]);
+
+        let server = MockServer::start(|_, _| Reply::json(500, serde_json::json!({})));
+        let mut req = request("feat", "main");
+        req.local_path = d.clone();
+        let gh: PullRequestGitHub = serde_json::from_value(serde_json::json!({
+            "token": "tok",
+            "apiBaseUrl": server.base,
+        }))
+        .unwrap();
+        let out = open_pull_request(req, gh, None);
+        let e = out.error.unwrap();
+        assert_eq!(e.code, "PR-0003");
+        assert!(server.requests().is_empty());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn existing_pull_request_is_looked_up_with_encoded_query() {
+        let server = MockServer::start(|req, _| match req.method.as_str() {
+            "POST" => Reply::json(
+                422,
+                serde_json::json!({"message": "A pull request already exists"}),
+            ),
+            _ if req.path.contains("/pulls?") => Reply::json(
+                200,
+                serde_json::json!([{
+                    "number": 7,
+                    "html_url": "https://github.com/octo/app/pull/7",
+                    "state": "open",
+                    "head": {"ref": "feat/a&b", "sha": ""},
+                    "base": {"ref": "release/1.0"},
+                }]),
+            ),
+            _ => Reply::json(200, serde_json::json!([])),
+        });
+        let out = open_pull_request(
+            request("feat/a&b", "release/1.0"),
+            github(&server.base, "octo/app.git"),
+            None,
+        );
+        assert!(out.ok, "{:?}", out.error);
+        assert!(!out.created);
+        assert_eq!(out.repo.as_deref(), Some("octo/app"));
+        assert_eq!(out.pr.unwrap().number, 7);
+
+        let reqs = server.requests();
+        assert_eq!(reqs[0].path, "/repos/octo/app/pulls");
+        assert_eq!(
+            reqs[1].path,
+            "/repos/octo/app/pulls?state=open&head=octo%3Afeat%2Fa%26b&base=release%2F1.0"
+        );
+    }
+
+    #[test]
+    fn review_decision_uses_each_reviewers_latest_verdict() {
+        let review =
+            |user: &str, state: &str| serde_json::json!({"user": {"login": user}, "state": state});
+        assert_eq!(review_decision(&[]), ("none", 0));
+        assert_eq!(
+            review_decision(&[review("a", "COMMENTED"), review("b", "PENDING")]),
+            ("none", 0)
+        );
+        assert_eq!(
+            review_decision(&[
+                review("a", "APPROVED"),
+                review("b", "APPROVED"),
+                review("a", "COMMENTED"),
+            ]),
+            ("approved", 2)
+        );
+        // 後から承認すれば changes requested は消える
+        assert_eq!(
+            review_decision(&[review("a", "CHANGES_REQUESTED"), review("a", "APPROVED")]),
+            ("approved", 1)
+        );
+        assert_eq!(
+            review_decision(&[review("a", "APPROVED"), review("b", "CHANGES_REQUESTED")]),
+            ("changes_requested", 1)
+        );
+        // dismiss された承認は数えない
+        assert_eq!(
+            review_decision(&[review("a", "APPROVED"), review("a", "DISMISSED")]),
+            ("none", 0)
+        );
+    }
+
+    // PR #5（open・conflict なし・CI success）。reviews が None ならレビュー取得を 500 にする
+    fn merge_server(reviews: Option<serde_json::Value>) -> MockServer {
+        MockServer::start(
+            move |req, _| match (req.method.as_str(), req.path.as_str()) {
+                ("GET", "/repos/octo/app/pulls/5") => Reply::json(
+                    200,
+                    serde_json::json!({
+                        "number": 5,
+                        "state": "open",
+                        "mergeable": true,
+                        "mergeable_state": "clean",
+                        "head": {"ref": "feat", "sha": "abc1234"},
+                        "base": {"ref": "main"},
+                    }),
+                ),
+                ("GET", "/repos/octo/app/commits/abc1234/status") => {
+                    Reply::json(200, serde_json::json!({"state": "success"}))
+                }
+                ("GET", p) if p.starts_with("/repos/octo/app/pulls/5/reviews") => match &reviews {
+                    Some(r) => Reply::json(200, r.clone()),
+                    None => Reply::json(500, serde_json::json!({"message": "boom"})),
+                },
+                ("PUT", "/repos/octo/app/pulls/5/merge") => {
+                    Reply::json(200, serde_json::json!({"merged": true, "sha": "def5678"}))
+                }
+                _ => Reply::json(404, serde_json::json!({"message": "Not Found"})),
+            },
+        )
+    }
+
+    fn merge_options(v: serde_json::Value) -> Option<MergePullRequestOptions> {
+        Some(serde_json::from_value(v).unwrap())
+    }
+
+    fn merged(server: &MockServer) -> bool {
+        server.requests().iter().any(|r| r.method == "PUT")
+    }
+
+    #[test]
+    fn merge_is_blocked_without_an_approving_review() {
+        let review = |state: &str| serde_json::json!([{"user": {"login": "a"}, "state": state}]);
+        let cases = [
+            (
+                Some(serde_json::json!([])),
+                false,
+                "pull request is not approved",
+                Some("none"),
+            ),
+            (None, false, "pull request is not approved", None),
+            (
+                Some(review("CHANGES_REQUESTED")),
+                true,
+                "changes were requested on the pull request",
+                Some("changes_requested"),
+            ),
+        ];
+        for (reviews, allow, message, detail) in cases {
+            let server = merge_server(reviews);
+            let out = merge_pull_request(
+                request("feat", "main"),
+                github(&server.base, "octo/app"),
+                5,
+                merge_options(serde_json::json!({"allowUnreviewed": allow})),
+            );
+            let e = out.error.unwrap();
+            assert_eq!(e.code, "PR-0101");
+            assert_eq!(e.message, message);
+            assert_eq!(e.detail.as_deref(), detail);
+            assert!(!merged(&server), "{}", message);
+        }
+
+        // 明示的に許可すれば承認なしでも merge する
+        let server = merge_server(Some(serde_json::json!([])));
+        let out = merge_pull_request(
+            request("feat", "main"),
+            github(&server.base, "octo/app"),
+            5,
+            merge_options(serde_json::json!({"allowUnreviewed": true, "pull": false})),
+        );
+        assert!(out.ok, "{:?}", out.error);
+        assert!(merged(&server));
+    }
+
+    #[test]
+    fn approved_merge_pulls_the_environment() {
+        use crate::{path_to_string, run_capture};
+        use std::path::Path;
+
+        let git = |dir: &Path, args: &[&str]| {
+            let d = path_to_string(dir);
+            let mut full = vec!["-C", d.as_str()];
+            full.extend_from_slice(args);
+            let s = run_capture(Path::new("git"), &full, None);
+            assert!(s.ok, "git {:?}: {}", args, s.stderr);
+            s.stdout.trim().to_string()
+        };
+        let root = std::env::temp_dir().join(format!("gitshlc-pr-merge-{}", std::process::id()));
+        std::fs::remove_dir_all(&root).ok();
+        let origin = root.join("origin.git");
+        let work = root.join("work");
+        std::fs::create_dir_all(&origin).unwrap();
+        std::fs::create_dir_all(&work).unwrap();
+        git(&origin, &["init", "-q", "--bare"]);
+        git(&work, &["init", "-q"]);
+        git(&work, &["symbolic-ref", "HEAD", "refs/heads/main"]);
+        git(&work, &["config", "user.name", "Tester"]);
+        git(&work, &["config", "user.email", "tester@example.com"]);
+        git(&work, &["commit", "-q", "--allow-empty", "-m", "init"]);
+        git(
+            &work,
+            &["remote", "add", "origin", &path_to_string(&origin)],
+        );
+        git(
+            &work,
+            &["commit", "-q", "--allow-empty", "-m", "merged on GitHub"],
+        );
+        git(&work, &["push", "-q", "-u", "origin", "main"]);
+        let merged_head = git(&work, &["rev-parse", "HEAD"]);
+        // 環境はまだ merge 前
+        git(&work, &["reset", "-q", "--hard", "HEAD~1"]);
+
+        let approved = serde_json::json!([{"user": {"login": "a"}, "state": "APPROVED"}]);
+        let server = merge_server(Some(approved.clone()));
+        let mut req = request("feat", "main");
+        req.local_path = path_to_string(&work);
+        let out = merge_pull_request(req, github(&server.base, "octo/app"), 5, None);
+        assert!(out.ok, "{:?}", out.error);
+        assert_eq!(out.merge_sha.as_deref(), Some("def5678"));
+        assert_eq!(
+            server.requests().last().unwrap().json(),
+            serde_json::json!({"merge_method": "merge", "sha": "abc1234"})
+        );
+        assert!(out.pull.unwrap().ok);
+        assert_eq!(git(&work, &["rev-parse", "HEAD"]), merged_head);
+
+        // merge はできたが pull に失敗した
+        let server = merge_server(Some(approved));
+        let out = merge_pull_request(
+            request("feat", "main"),
+            github(&server.base, "octo/app"),
+            5,
+            None,
+        );
+        assert_eq!(out.error.unwrap().code, "PR-0104");
+        assert_eq!(out.merge_sha.as_deref(), Some("def5678"));
+        assert!(!out.pull.unwrap().ok);
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
@@ -0,0 +1,480 @@
+// tag ベースのリリース: 版を決めて annotated tag を作って push する / 環境を tag・SHA に固定して detached HEAD で deploy する
+
+use crate::repo_target::RepoTarget;
+use crate::{
+    remote_auth_plan, today_utc, ActionError, ActionOutcome, RunActionRequest, StepResult,
+};
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct ReleaseOptions {
+    // semver (default) | date
+    scheme: Option<String>,
+    // semver: major | minor | patch (default)
+    bump: Option<String>,
+    // tag 名の接頭辞（default "v"）
+    prefix: Option<String>,
+    // 明示した版（scheme / bump より優先）
+    version: Option<String>,
+    // tag を打つ commit（default origin/<branch>、branch が空なら HEAD）
+    target: Option<String>,
+    // git tag -s（gpg / ssh 署名の設定はサーバー側）
+    sign: bool,
+    message: Option<String>,
+    // default true
+    push: Option<bool>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ReleaseOutcome {
+    #[serde(flatten)]
+    outcome: ActionOutcome,
+    tag: Option<String>,
+    version: Option<String>,
+    sha: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RepoStatus {
+    ok: bool,
+    mode: String,
+    env_key: String,
+    head_sha: Option<String>,
+    // detached なら None
+    branch: Option<String>,
+    detached: bool,
+    // HEAD にちょうど付いている tag（= いま動いているリリース）
+    release: Option<String>,
+    // git describe --tags --always（tag が無ければ短縮 SHA）
+    describe: Option<String>,
+    // 追跡ファイルに変更がある
+    dirty: bool,
+    steps: Vec<StepResult>,
+    error: Option<ActionError>,
+}
+
+fn rel_err(code: &str, message: &str, detail: Option<String>) -> ActionError {
+    ActionError {
+        code: code.into(),
+        severity: "ERROR".into(),
+        message: message.into(),
+        detail,
+    }
+}
+
+fn opt(s: &Option<String>) -> Option<&str> {
+    s.as_deref().map(str::trim).filter(|x| !x.is_empty())
+}
+
+// 既存 tag（prefix 付き）から次の版を決める
+fn next_version(
+    opts: &ReleaseOptions,
+    prefix: &str,
+    tags: &[String],
+) -> Result<String, ActionError> {
+    let exists = |v: &str| tags.iter().any(|t| *t == format!("{}{}", prefix, v));
+
+    if let Some(v) = opt(&opts.version) {
+        return Ok(v.to_string());
+    }
+
+    match opt(&opts.scheme).unwrap_or("semver") {
+        "semver" => {
+            let latest = tags
+                .iter()
+                .filter_map(|t| t.strip_prefix(prefix))
+                .filter_map(|t| semver::Version::parse(t).ok())
+                .filter(|v| v.pre.is_empty())
+                .max();
+            let bump = opt(&opts.bump).unwrap_or("patch");
+            let next = match (latest, bump) {
+                (None, "major") => semver::Version::new(1, 0, 0),
+                (None, "minor" | "patch") => semver::Version::new(0, 1, 0),
+                (Some(v), "major") => semver::Version::new(v.major + 1, 0, 0),
+                (Some(v), "minor") => semver::Version::new(v.major, v.minor + 1, 0),
+                (Some(v), "patch") => semver::Version::new(v.major, v.minor, v.patch + 1),
+                (_, other) => {
+                    return Err(rel_err(
+                        "REL-0001",
+                        "unknown bump (expected major|minor|patch)",
+                        Some(other.to_string()),
+                    ))
+                }
+            };
+            Ok(next.to_string())
+        }
+        "date" => {
+            // 2026.10.18 / 同じ日の 2 回目以降は 2026.10.18.1, .2 ...
+            let (y, m, d) = today_utc();
+            let base = format!("{}.{:02}.{:02}", y, m, d);
+            if !exists(&base) {
+                return Ok(base);
+            }
+            let n = (1..)
+                .find(|n| !exists(&format!("{}.{}", base, n)))
+                .unwrap_or(1);
+            Ok(format!("{}.{}", base, n))
+        }
+        other => Err(rel_err(
+            "REL-0001",
+            "unknown scheme (expected semver|date)",
+            Some(other.to_string()),
+        )),
+    }
+}
+
+struct Run<'a> {
+    target: &'a RepoTarget<'a>,
+    steps: Vec<StepResult>,
+}
+
+impl Run<'_> {
+    fn git(&mut self, args: &[&str]) -> StepResult {
+        let step = self.target.git(args);
+        self.steps.push(step.clone());
+        step
+    }
+
+    fn must(&mut self, args: &[&str], what: &str) -> Result<StepResult, ActionError> {
+        let step = self.git(args);
+        if step.ok {
+            Ok(step)
+        } else {
+            Err(self.target.failure(&step, "REL-0200", what))
+        }
+    }
+
+    fn must_origin(&mut self, args: &[&str], what: &str) -> Result<StepResult, ActionError> {
+        let step = self.target.git_origin(args);
+        self.steps.push(step.clone());
+        if step.ok {
+            Ok(step)
+        } else {
+            Err(self.target.failure(&step, "REL-0200", what))
+        }
+    }
+
+    fn resolve_commit(&mut self, r: &str) -> Result<String, ActionError> {
+        let step = self.git(&[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", r),
+        ]);
+        let sha = step.stdout.trim().to_string();
+        if step.ok && !sha.is_empty() {
+            return Ok(sha);
+        }
+        let e = self.target.failure(&step, "REL-0101", "ref not found");
+        Err(ActionError {
+            detail: Some(r.to_string()),
+            ..e
+        })
+    }
+}
+
+// オプションとして解釈されないように（- 始まりは拒否）
+fn check_ref_arg(r: &str) -> Result<(), ActionError> {
+    if r.starts_with('-') || r.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(rel_err("REL-0001", "invalid ref", Some(r.to_string())));
+    }
+    Ok(())
+}
+
+fn do_release(
+    run: &mut Run,
+    req: &RunActionRequest,
+    opts: &ReleaseOptions,
+) -> Result<(String, String, String), ActionError> {
+    let prefix = opts.prefix.as_deref().unwrap_or("v").trim();
+
+    // 既存 tag と origin のブランチを最新にしてから版を決める
+    run.must_origin(&["fetch", "--tags", "origin"], "git fetch --tags failed")?;
+
+    let target = match (opt(&opts.target), req.branch.trim()) {
+        (Some(t), _) => t.to_string(),
+        (None, "") => "HEAD".to_string(),
+        (None, b) => format!("origin/{}", b),
+    };
+    check_ref_arg(&target)?;
+    let sha = run.resolve_commit(&target)?;
+
+    let list = run.must(
+        &["tag", "--list", &format!("{}*", prefix)],
+        "git tag --list failed",
+    )?;
+    let tags: Vec<String> = list
+        .stdout
+        .lines()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    let version = next_version(opts, prefix, &tags)?;
+    let tag = format!("{}{}", prefix, version);
+    let fmt = run.git(&["check-ref-format", &format!("refs/tags/{}", tag)]);
+    if !fmt.ok {
+        return Err(rel_err("REL-0001", "invalid tag name", Some(tag)));
+    }
+    if tags.contains(&tag) {
+        return Err(rel_err("REL-0100", "tag already exists", Some(tag)));
+    }
+
+    let message = opt(&opts.message)
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("Release {}", tag));
+    let mode = if opts.sign { "-s" } else { "-a" };
+    run.must(
+        &["tag", mode, "-m", &message, &tag, &sha],
+        "git tag failed (identity / signing key configured?)",
+    )?;
+
+    if opts.push.unwrap_or(true) {
+        run.must_origin(
+            &["push", "origin", &format!("refs/tags/{}", tag)],
+            "git push (tag) failed",
+        )?;
+    }
+    Ok((tag, version, sha))
+}
+
+/// 次の版を決めて annotated tag を打ち、origin に push する
+#[tauri::command(async, rename_all = "camelCase")]
+pub(crate) fn release(req: RunActionRequest, options: Option<ReleaseOptions>) -> ReleaseOutcome {
+    let opts = options.unwrap_or_default();
+    let done = |steps: Vec<StepResult>, res: Result<(String, String, String), ActionError>| {
+        let (tag, version, sha, error) = match res {
+            Ok((t, v, s)) => (Some(t), Some(v), Some(s), None),
+            Err(e) => (None, None, None, Some(e)),
+        };
+        ReleaseOutcome {
+            outcome: ActionOutcome {
+                ok: error.is_none(),
+                mode: req.mode.clone(),
+                action: "release".into(),
+                env_key: req.env_key.clone(),
+                steps,
+                error,
+            },
+            tag,
+            version,
+            sha,
+        }
+    };
+
+    let remote_path = req.remote_path.trim().to_string();
+    let auth = match remote_auth_plan(req.remote_auth.as_ref()) {
+        Ok(a) => a,
+        Err(e) => return done(vec![], Err(e)),
+    };
+    let target = match RepoTarget::open(&req, &remote_path, &auth) {
+        Ok(t) => t,
+        Err(e) => return done(vec![], Err(e)),
+    };
+    let mut run = Run {
+        target: &target,
+        steps: Vec::new(),
+    };
+    let res = do_release(&mut run, &req, &opts);
+    done(run.steps, res)
+}
+
+fn deploy(run: &mut Run, pin: &str) -> Result<(), ActionError> {
+    check_ref_arg(pin)?;
+
+    // 追跡ファイルの変更があると checkout で消える / 失敗するので先に止める
+    let status = run.must(
+        &["status", "--porcelain", "--untracked-files=no"],
+        "git status failed",
+    )?;
+    if !status.stdout.trim().is_empty() {
+        return Err(rel_err(
+            "REL-0102",
+            "working tree has local changes",
+            Some(status.stdout.trim().to_string()),
+        ));
+    }
+
+    run.must_origin(&["fetch", "--tags", "origin"], "git fetch failed")?;
+    let sha = run.resolve_commit(pin)?;
+    run.must(
+        &[
+            "-c",
+            "advice.detachedHead=false",
+            "checkout",
+            "--detach",
+            &sha,
+        ],
+        "git checkout --detach failed",
+    )?;
+
+    let head = run.must(&["rev-parse", "HEAD"], "git rev-parse failed")?;
+    if head.stdout.trim() != sha {
+        return Err(rel_err(
+            "REL-0200",
+            "HEAD does not match the pinned commit after checkout",
+            Some(format!("{} != {}", head.stdout.trim(), sha)),
+        ));
+    }
+    Ok(())
+}
+
+/// run_action(action=deploy): 環境を pinRef（tag / SHA）に固定して detached HEAD で checkout する
+pub(crate) fn deploy_pinned(req: &RunActionRequest) -> ActionOutcome {
+    let outcome = |steps: Vec<StepResult>, error: Option<ActionError>| ActionOutcome {
+        ok: error.is_none(),
+        mode: req.mode.clone(),
+        action: req.action.clone(),
+        env_key: req.env_key.clone(),
+        steps,
+        error,
+    };
+
+    let Some(pin) = opt(&req.pin_ref) else {
+        return outcome(
+            vec![],
+            Some(rel_err(
+                "REL-0001",
+                "pinRef (tag or SHA) is required for action=deploy",
+                None,
+            )),
+        );
+    };
+
+    let remote_path = req.remote_path.trim().to_string();
+    let auth = match remote_auth_plan(req.remote_auth.as_ref()) {
+        Ok(a) => a,
+        Err(e) => return outcome(vec![], Some(e)),
+    };
+    let target = match RepoTarget::open(req, &remote_path, &auth) {
+        Ok(t) => t,
+        Err(e) => return outcome(vec![], Some(e)),
+    };
+    let mut run = Run {
+        target: &target,
+        steps: Vec::new(),
+    };
+    let res = deploy(&mut run, pin);
+    outcome(run.steps, res.err())
+}
+
+/// いまの HEAD・ブランチ / detached・動いているリリース（HEAD の tag）を返す
+#[tauri::command(async, rename_all = "camelCase")]
+pub(crate) fn repo_status(req: RunActionRequest) -> RepoStatus {
+    let mut out = RepoStatus {
+        ok: false,
+        mode: req.mode.clone(),
+        env_key: req.env_key.clone(),
+        head_sha: None,
+        branch: None,
+        detached: false,
+        release: None,
+        describe: None,
+        dirty: false,
+        steps: Vec::new(),
+        error: None,
+    };
+
+    let remote_path = req.remote_path.trim().to_string();
+    let auth = match remote_auth_plan(req.remote_auth.as_ref()) {
+        Ok(a) => a,
+        Err(e) => {
+            out.error = Some(e);
+            return out;
+        }
+    };
+    let target = match RepoTarget::open(&req, &remote_path, &auth) {
+        Ok(t) => t,
+        Err(e) => {
+            out.error = Some(e);
+            return out;
+        }
+    };
+    let mut run = Run {
+        target: &target,
+        steps: Vec::new(),
+    };
+
+    let head = match run.must(&["rev-parse", "--verify", "HEAD"], "git rev-parse failed") {
+        Ok(s) => s,
+        Err(e) => {
+            out.steps = run.steps;
+            out.error = Some(e);
+            return out;
+        }
+    };
+    out.head_sha = Some(head.stdout.trim().to_string());
+
+    let br = run.git(&["symbolic-ref", "--quiet", "--short", "HEAD"]);
+    out.detached = !br.ok;
+    out.branch = Some(br.stdout.trim().to_string()).filter(|b| br.ok && !b.is_empty());
+
+    // annotated tag を優先（無ければ lightweight）
+    let exact = run.git(&["describe", "--tags", "--exact-match", "HEAD"]);
+    out.release = Some(exact.stdout.trim().to_string()).filter(|t| exact.ok && !t.is_empty());
+
+    let desc = run.git(&["describe", "--tags", "--always"]);
+    out.describe = Some(desc.stdout.trim().to_string()).filter(|d| desc.ok && !d.is_empty());
+
+    let status = run.git(&["status", "--porcelain", "--untracked-files=no"]);
+    out.dirty = status.ok && !status.stdout.trim().is_empty();
+
+    out.ok = true;
+    out.steps = run.steps;
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opts(scheme: &str, bump: &str) -> ReleaseOptions {
+        ReleaseOptions {
+            scheme: Some(scheme.into()),
+            bump: Some(bump.into()),
+            ..ReleaseOptions::default()
+        }
+    }
+
+    #[test]
+    fn next_version_bumps_latest_release_tag() {
+        let tags: Vec<String> = ["v1.2.3", "v1.10.0", "v2.0.0-rc.1", "x9.9.9", "v-bad"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let next = |scheme, bump| next_version(&opts(scheme, bump), "v", &tags);
+        // pre-release と別 prefix は無視する
+        assert_eq!(next("semver", "patch").unwrap(), "1.10.1");
+        assert_eq!(next("semver", "minor").unwrap(), "1.11.0");
+        assert_eq!(next("semver", "major").unwrap(), "2.0.0");
+        assert_eq!(next("semver", "huge").unwrap_err().code, "REL-0001");
+        assert_eq!(next("calver", "patch").unwrap_err().code, "REL-0001");
+
+        assert_eq!(
+            next_version(&opts("semver", "patch"), "v", &[]).unwrap(),
+            "0.1.0"
+        );
+        assert_eq!(
+            next_version(&opts("semver", "major"), "v", &[]).unwrap(),
+            "1.0.0"
+        );
+        let explicit = ReleaseOptions {
+            version: Some(" 3.0.0 ".into()),
+            ..opts("date", "huge")
+        };
+        assert_eq!(next_version(&explicit, "v", &tags).unwrap(), "3.0.0");
+    }
+
+    #[test]
+    fn date_versions_get_a_counter_on_the_same_day() {
+        let (y, m, d) = today_utc();
+        let base = format!("{}.{:02}.{:02}", y, m, d);
+        let o = opts("date", "");
+        assert_eq!(next_version(&o, "r", &[]).unwrap(), base);
+        let tags = vec![format!("r{}", base), format!("r{}.1", base)];
+        assert_eq!(next_version(&o, "r", &tags).unwrap(), format!("{}.2", base));
+    }
+}
@@ -0,0 +1,134 @@
+// run_action 以外の操作（branch / release など）用: local_path と remote_path のどちらでも同じ形で git を実行する
+use std::path::PathBuf;
+
+use crate::{
+    git_exe, git_version, path_to_string, remote_repo_for, repo_is_git_dir, run_capture,
+    shell_escape_posix_single, ActionError, RemoteAuthPlan, RemoteRepo, RunActionRequest,
+    StepResult,
+};
+
+pub(crate) enum RepoTarget<'a> {
+    Local { git: PathBuf, path: String },
+    Remote(RemoteRepo<'a>),
+}
+
+fn err(code: &str, message: &str, detail: Option<String>) -> ActionError {
+    ActionError {
+        code: code.into(),
+        severity: "ERROR".into(),
+        message: message.into(),
+        detail,
+    }
+}
+
+impl<'a> RepoTarget<'a> {
+    /// req.mode（local | ssh | wrapped）から対象を組み立てる。検証は run_action と同じコード
+    pub(crate) fn open(
+        req: &'a RunActionRequest,
+        remote_path: &'a str,
+        auth: &'a RemoteAuthPlan,
+    ) -> Result<Self, ActionError> {
+        match req.mode.as_str() {
+            "local" => {
+                let git = git_exe(if req.git_path.trim().is_empty() {
+                    None
+                } else {
+                    Some(req.git_path.clone())
+                })
+                .ok_or_else(|| ActionError {
+                    code: "GIT-0001".into(),
+                    severity: "FATAL".into(),
+                    message: "git not found".into(),
+                    detail: None,
+                })?;
+                let lp = req.local_path.trim();
+                if lp.is_empty() {
+                    return Err(err("FS-0100", "localPath is required", None));
+                }
+                let p = PathBuf::from(lp);
+                if !p.is_dir() {
+                    return Err(err(
+                        "FS-0101",
+                        "localPath is not a directory",
+                        Some(path_to_string(&p)),
+                    ));
+                }
+                if !repo_is_git_dir(&p) {
+                    return Err(err(
+                        "FS-0102",
+                        "localPath is not a git repository",
+                        Some(path_to_string(&p)),
+                    ));
+                }
+                Ok(RepoTarget::Local {
+                    git,
+                    path: path_to_string(&p),
+                })
+            }
+            "ssh" | "wrapped" => Ok(RepoTarget::Remote(remote_repo_for(req, remote_path, auth)?)),
+            other => Err(err(
+                "CFG-0002",
+                "unknown mode (expected local|ssh|wrapped)",
+                Some(other.to_string()),
+            )),
+        }
+    }
+
+    fn remote_body(args: &[&str]) -> String {
+        std::iter::once("git".to_string())
+            .chain(args.iter().map(|a| shell_escape_posix_single(a)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// repo 内で git を実行する（origin とは通信しないもの）
+    pub(crate) fn git(&self, args: &[&str]) -> StepResult {
+        match self {
+            RepoTarget::Local { git, path } => {
+                let mut full = vec!["-C", path.as_str()];
+                full.extend_from_slice(args);
+                run_capture(git, &full, None)
+            }
+            RepoTarget::Remote(repo) => repo.run(&Self::remote_body(args)),
+        }
+    }
+
+    /// origin と通信する git（remote は remoteAuth を使う）
+    pub(crate) fn git_origin(&self, args: &[&str]) -> StepResult {
+        match self {
+            RepoTarget::Local { .. } => self.git(args),
+            RepoTarget::Remote(repo) => {
+                let quoted: Vec<String> =
+                    args.iter().map(|a| shell_escape_posix_single(a)).collect();
+                repo.git_origin(&quoted.join(" "))
+            }
+        }
+    }
+
+    pub(crate) fn capabilities(&self) -> Option<git_version::GitCapabilities> {
+        match self {
+            RepoTarget::Local { git, .. } => git_version::local_capabilities(git),
+            RepoTarget::Remote(repo) => {
+                let step = repo.run("git --version");
+                if step.ok {
+                    Some(git_version::GitCapabilities::from_version_output(
+                        &step.stdout,
+                    ))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// 失敗した step を ActionError にする。remote の接続・sudo・所有者の問題はそちらを優先
+    pub(crate) fn failure(&self, step: &StepResult, code: &str, message: &str) -> ActionError {
+        if let RepoTarget::Remote(repo) = self {
+            let conn = repo.classify(step);
+            if conn.code != "SSH-0200" {
+                return conn;
+            }
+        }
+        err(code, message, Some(step.stderr.trim().to_string()))
+    }
+}
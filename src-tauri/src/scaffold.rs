@@ -0,0 +1,447 @@
+// init_local_repo の雛形: .gitignore / README / LICENSE を置いて最初の commit を作り、必要なら push -u する
+use std::{fs, path::Path};
+
+use crate::{
+    ls_remote::GitAuth, path_to_string, run_capture, run_capture_env, step_error, today_utc,
+    ActionError, StepResult,
+};
+
+// 同梱テンプレート（templates/ 以下）
+const GITIGNORE_TEMPLATES: &[(&str, &str)] = &[
+    (
+        "rust",
+        include_str!("../templates/gitignore/rust.gitignore"),
+    ),
+    (
+        "node",
+        include_str!("../templates/gitignore/node.gitignore"),
+    ),
+    ("php", include_str!("../templates/gitignore/php.gitignore")),
+    (
+        "python",
+        include_str!("../templates/gitignore/python.gitignore"),
+    ),
+    ("go", include_str!("../templates/gitignore/go.gitignore")),
+    (
+        "java",
+        include_str!("../templates/gitignore/java.gitignore"),
+    ),
+    ("os", include_str!("../templates/gitignore/os.gitignore")),
+];
+
+// {year} / {holder} を置き換える
+const LICENSE_TEMPLATES: &[(&str, &str)] = &[
+    ("MIT", include_str!("../templates/license/MIT.txt")),
+    ("ISC", include_str!("../templates/license/ISC.txt")),
+    (
+        "BSD-3-Clause",
+        include_str!("../templates/license/BSD-3-Clause.txt"),
+    ),
+    (
+        "Unlicense",
+        include_str!("../templates/license/Unlicense.txt"),
+    ),
+];
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct ScaffoldOptions {
+    // rust / node / php / python / go / java / os（複数なら順に連結）
+    gitignore: Vec<String>,
+    readme: bool,
+    // README の見出し（default: ディレクトリ名）
+    readme_title: Option<String>,
+    // MIT / ISC / BSD-3-Clause / Unlicense
+    license: Option<String>,
+    // default: authorName
+    license_holder: Option<String>,
+    // 最初の commit（default true）
+    commit: Option<bool>,
+    commit_message: Option<String>,
+    // 指定すればこの identity で commit する（無ければ git config の設定）
+    author_name: Option<String>,
+    author_email: Option<String>,
+    // push -u origin <branch>（repoUrl が必要）
+    push: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ScaffoldTemplates {
+    gitignore: Vec<String>,
+    licenses: Vec<String>,
+}
+
+fn opt(s: &Option<String>) -> Option<&str> {
+    s.as_deref().map(str::trim).filter(|x| !x.is_empty())
+}
+
+fn scaffold_err(code: &str, message: &str, detail: Option<String>) -> ActionError {
+    ActionError {
+        code: code.into(),
+        severity: "ERROR".into(),
+        message: message.into(),
+        detail,
+    }
+}
+
+fn find_template<'a>(set: &[(&str, &'a str)], name: &str) -> Option<&'a str> {
+    set.iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, t)| *t)
+}
+
+// 既にあるファイルは上書きしない（[skip] として残す）。書いたときだけ true
+fn write_file(
+    dir: &Path,
+    name: &str,
+    content: &str,
+    steps: &mut Vec<StepResult>,
+) -> Result<bool, ActionError> {
+    let path = dir.join(name);
+    if path.exists() {
+        steps.push(StepResult {
+            ok: true,
+            cmd: format!("[skip] {} already exists", name),
+            cwd: Some(path_to_string(dir)),
+            exit_code: 0,
+            stdout: "".into(),
+            stderr: "".into(),
+        });
+        return Ok(false);
+    }
+    match fs::write(&path, content) {
+        Ok(_) => {
+            steps.push(StepResult {
+                ok: true,
+                cmd: format!("write {} ({} bytes)", name, content.len()),
+                cwd: Some(path_to_string(dir)),
+                exit_code: 0,
+                stdout: "".into(),
+                stderr: "".into(),
+            });
+            Ok(true)
+        }
+        Err(e) => {
+            steps.push(step_error(format!("write {}", name), e.to_string()));
+            Err(scaffold_err(
+                "GIT-0402",
+                "failed to write scaffold file",
+                Some(name.into()),
+            ))
+        }
+    }
+}
+
+impl ScaffoldOptions {
+    pub(crate) fn with_push(mut self) -> Self {
+        self.push = true;
+        self
+    }
+
+    /// テンプレート名と push の前提を確認する。git init や GitHub の作成より前に呼ぶ
+    pub(crate) fn validate(&self, has_origin: bool) -> Result<(), ActionError> {
+        self.gitignore_text()?;
+        self.license_template()?;
+        if self.push && !has_origin {
+            return Err(scaffold_err(
+                "CFG-0001",
+                "push requires repoUrl (origin)",
+                None,
+            ));
+        }
+        Ok(())
+    }
+
+    // 選ばれた .gitignore テンプレートを順に連結する
+    fn gitignore_text(&self) -> Result<String, ActionError> {
+        let mut ignore = String::new();
+        for name in self
+            .gitignore
+            .iter()
+            .map(|n| n.trim())
+            .filter(|n| !n.is_empty())
+        {
+            let t = find_template(GITIGNORE_TEMPLATES, name).ok_or_else(|| {
+                scaffold_err("CFG-0001", "unknown gitignore template", Some(name.into()))
+            })?;
+            if !ignore.is_empty() {
+                ignore.push('\n');
+            }
+            ignore.push_str(t);
+        }
+        Ok(ignore)
+    }
+
+    fn license_template(&self) -> Result<Option<&'static str>, ActionError> {
+        match opt(&self.license) {
+            Some(name) => find_template(LICENSE_TEMPLATES, name)
+                .map(Some)
+                .ok_or_else(|| {
+                    scaffold_err("CFG-0001", "unknown license template", Some(name.into()))
+                }),
+            None => Ok(None),
+        }
+    }
+}
+
+/// 同梱テンプレートの一覧
+#[tauri::command(rename_all = "camelCase")]
+pub(crate) fn list_scaffold_templates() -> ScaffoldTemplates {
+    ScaffoldTemplates {
+        gitignore: GITIGNORE_TEMPLATES
+            .iter()
+            .map(|(n, _)| n.to_string())
+            .collect(),
+        licenses: LICENSE_TEMPLATES
+            .iter()
+            .map(|(n, _)| n.to_string())
+            .collect(),
+    }
+}
+
+/// git init 直後の dir にファイルを置いて最初の commit（と push）を行う。auth は push にだけ使う
+pub(crate) fn apply(
+    git: &Path,
+    dir: &Path,
+    branch: &str,
+    has_origin: bool,
+    opts: &ScaffoldOptions,
+    auth: &GitAuth,
+    steps: &mut Vec<StepResult>,
+) -> Result<(), ActionError> {
+    opts.validate(has_origin)?;
+    let ignore = opts.gitignore_text()?;
+    let license = opts.license_template()?;
+
+    let mut wrote = false;
+    if !ignore.is_empty() {
+        wrote |= write_file(dir, ".gitignore", &ignore, steps)?;
+    }
+    if opts.readme {
+        let title = opt(&opts.readme_title)
+            .map(str::to_string)
+            .or_else(|| dir.file_name().map(|n| n.to_string_lossy().to_string()))
+            .unwrap_or_else(|| "README".into());
+        wrote |= write_file(dir, "README.md", &format!("# {}\n", title), steps)?;
+    }
+    if let Some(t) = license {
+        let holder = opt(&opts.license_holder)
+            .or(opt(&opts.author_name))
+            .unwrap_or("the authors");
+        let (year, _, _) = today_utc();
+        let text = t
+            .replace("{year}", &year.to_string())
+            .replace("{holder}", holder);
+        wrote |= write_file(dir, "LICENSE", &text, steps)?;
+    }
+
+    if !opts.commit.unwrap_or(true) {
+        return Ok(());
+    }
+
+    let d = path_to_string(dir);
+    let add = run_capture(git, &["-C", &d, "add", "-A"], None);
+    let add_ok = add.ok;
+    steps.push(add);
+    if !add_ok {
+        return Err(scaffold_err("GIT-0499", "git add failed", None));
+    }
+
+    // identity は -c で渡す（config には書かない）
+    let mut args: Vec<String> = vec!["-C".into(), d.clone()];
+    if let Some(n) = opt(&opts.author_name) {
+        args.extend(["-c".into(), format!("user.name={}", n)]);
+    }
+    if let Some(e) = opt(&opts.author_email) {
+        args.extend(["-c".into(), format!("user.email={}", e)]);
+    }
+    let msg = opt(&opts.commit_message).unwrap_or("Initial commit");
+    args.push("commit".into());
+    // 雛形を 1 つも書かなかったとき（push だけ頼まれた等）に限り空 commit を許す
+    if !wrote {
+        args.push("--allow-empty".into());
+    }
+    args.extend(["-m".into(), msg.into()]);
+    let refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    let commit = run_capture(git, &refs, None);
+    let commit_ok = commit.ok;
+    let detail = commit.stderr.trim().to_string();
+    steps.push(commit);
+    if !commit_ok {
+        return Err(scaffold_err(
+            "GIT-0108",
+            "initial commit failed (set authorName / authorEmail?)",
+            Some(detail),
+        ));
+    }
+
+    if opts.push {
+        let target = if branch.is_empty() { "HEAD" } else { branch };
+        let mut args: Vec<&str> = vec!["-C", &d];
+        args.extend(auth.args().iter().map(|s| s.as_str()));
+        args.extend(["push", "-u", "origin", target]);
+        let push = run_capture_env(git, &args, None, None, &auth.envs());
+        let push_ok = push.ok;
+        let detail = push.stderr.trim().to_string();
+        steps.push(push);
+        if !push_ok {
+            return Err(scaffold_err("GIT-0499", "git push -u failed", Some(detail)));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn git_out(dir: &Path, args: &[&str]) -> String {
+        let mut full = vec!["-C", dir.to_str().unwrap()];
+        full.extend(args);
+        let s = run_capture(Path::new("git"), &full, None);
+        assert!(s.ok, "{}", s.stderr);
+        s.stdout.trim().to_string()
+    }
+
+    fn fresh_repo(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gitshlc-{}-{}", name, std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        git_out(&dir, &["init", "-q"]);
+        dir
+    }
+
+    #[test]
+    fn apply_writes_templates_and_commits_with_identity() {
+        let dir = fresh_repo("scaffold");
+        fs::write(dir.join("README.md"), "keep me\n").unwrap();
+        let opts = ScaffoldOptions {
+            gitignore: vec!["Rust".into(), " os ".into()],
+            readme: true,
+            license: Some("MIT".into()),
+            author_name: Some("Jane Tester".into()),
+            author_email: Some("jane@example.com".into()),
+            ..ScaffoldOptions::default()
+        };
+
+        let mut steps = Vec::new();
+        apply(
+            Path::new("git"),
+            &dir,
+            "main",
+            false,
+            &opts,
+            &GitAuth::default(),
+            &mut steps,
+        )
+        .unwrap();
+
+        let ignore = fs::read_to_string(dir.join(".gitignore")).unwrap();
+        assert_eq!(
+            ignore,
+            format!(
+                "{}\n{}",
+                find_template(GITIGNORE_TEMPLATES, "rust").unwrap(),
+                find_template(GITIGNORE_TEMPLATES, "os").unwrap()
+            )
+        );
+        // 既にある README は上書きしない
+        assert_eq!(
+            fs::read_to_string(dir.join("README.md")).unwrap(),
+            "keep me\n"
+        );
+        let license = fs::read_to_string(dir.join("LICENSE")).unwrap();
+        let (year, _, _) = today_utc();
+        assert!(
+            license.contains(&format!("{} Jane Tester", year)),
+            "{}",
+            license
+        );
+        assert!(!license.contains("{year}") && !license.contains("{holder}"));
+
+        assert_eq!(
+            git_out(&dir, &["log", "-1", "--format=%an <%ae>|%s"]),
+            "Jane Tester <jane@example.com>|Initial commit"
+        );
+        // identity は config に残さない
+        let local = run_capture(
+            Path::new("git"),
+            &[
+                "-C",
+                dir.to_str().unwrap(),
+                "config",
+                "--local",
+                "user.name",
+            ],
+            None,
+        );
+        assert!(!local.ok);
+
+        let cmds: Vec<&str> = steps.iter().map(|s| s.cmd.as_str()).collect();
+        assert!(steps.iter().all(|s| s.ok));
+        assert_eq!(cmds.len(), 5, "{:?}", cmds);
+        assert!(cmds[0].starts_with("write .gitignore ("));
+        assert_eq!(cmds[1], "[skip] README.md already exists");
+        assert!(cmds[2].starts_with("write LICENSE ("));
+        assert!(cmds[3].ends_with(" add -A"));
+        assert!(cmds[4].contains(
+            " -c user.name=Jane Tester -c user.email=jane@example.com commit -m Initial commit"
+        ));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn allow_empty_only_when_nothing_was_written() {
+        let dir = fresh_repo("scaffold-empty");
+        let opts = ScaffoldOptions {
+            author_name: Some("Jane Tester".into()),
+            author_email: Some("jane@example.com".into()),
+            ..ScaffoldOptions::default()
+        };
+        let mut steps = Vec::new();
+        apply(
+            Path::new("git"),
+            &dir,
+            "",
+            false,
+            &opts,
+            &GitAuth::default(),
+            &mut steps,
+        )
+        .unwrap();
+        assert!(steps
+            .last()
+            .unwrap()
+            .cmd
+            .contains(" commit --allow-empty -m "));
+        assert_eq!(git_out(&dir, &["rev-list", "--count", "HEAD"]), "1");
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn validate_rejects_unknown_templates_and_push_without_origin() {
+        let code = |o: ScaffoldOptions, origin: bool| o.validate(origin).unwrap_err().code;
+        let typo = ScaffoldOptions {
+            gitignore: vec!["rust".into(), "rsut".into()],
+            ..ScaffoldOptions::default()
+        };
+        assert_eq!(code(typo, true), "CFG-0001");
+        let license = ScaffoldOptions {
+            license: Some("GPL-9".into()),
+            ..ScaffoldOptions::default()
+        };
+        assert_eq!(code(license, true), "CFG-0001");
+        assert_eq!(
+            code(ScaffoldOptions::default().with_push(), false),
+            "CFG-0001"
+        );
+        assert!(ScaffoldOptions::default()
+            .with_push()
+            .validate(true)
+            .is_ok());
+    }
+}
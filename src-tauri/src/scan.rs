@@ -0,0 +1,594 @@
+// ローカル repo 検出: 並列 walk + 進捗を Tauri event で逐次通知（scanId でキャンセル可）
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::{
+    git_exe, git_remote_origin_url, gitdir, is_git_repo_dir, normalize_path_input, path_to_string,
+    run_capture,
+    scan_profile::{self, IgnoreRule, ScanProfile, ScanRules},
+    DetectedRepo,
+};
+
+pub(crate) const EVENT_REPO: &str = "scan:repo";
+pub(crate) const EVENT_DONE: &str = "scan:done";
+
+#[derive(Default)]
+pub(crate) struct ScanRegistry {
+    running: Mutex<HashMap<String, Arc<AtomicBool>>>,
+    seq: AtomicU64,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScanRepoEvent {
+    scan_id: String,
+    repo: DetectedRepo,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScanDoneEvent {
+    scan_id: String,
+    count: usize,
+    cancelled: bool,
+}
+
+#[derive(Default)]
+struct WorkQueue {
+    items: Vec<(PathBuf, u8, Arc<Vec<IgnoreRule>>)>,
+    in_flight: usize,
+}
+
+/// 前回 readdir した結果。mtime（.gitignore を見る場合はその mtime も）が同じなら再利用する
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DirMemoEntry {
+    mtime: u64,
+    children: Vec<PathBuf>,
+    repos: Vec<PathBuf>,
+}
+
+#[derive(Default)]
+pub(crate) struct DirMemo {
+    pub(crate) prev: HashMap<PathBuf, DirMemoEntry>,
+    pub(crate) next: Mutex<HashMap<PathBuf, DirMemoEntry>>,
+}
+
+pub(crate) fn mtime_nanos(p: &Path) -> Option<u64> {
+    let m = std::fs::metadata(p).ok()?.modified().ok()?;
+    m.duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_nanos() as u64)
+}
+
+fn dir_stamp(dir: &Path, rules: &ScanRules) -> Option<u64> {
+    let m = mtime_nanos(dir)?;
+    if rules.respect_gitignore {
+        Some(m.max(mtime_nanos(&dir.join(".gitignore")).unwrap_or(0)))
+    } else {
+        Some(m)
+    }
+}
+
+// symlink をたどるときは、どのディレクトリも実体で 1 回だけ訪れる
+// （ループ対策。直接と symlink 経由の両方から届く repo を二重に出さない）
+fn first_visit(p: &Path, rules: &ScanRules, visited: &Mutex<HashSet<PathBuf>>) -> bool {
+    if !rules.follow_symlinks {
+        return true;
+    }
+    match p.canonicalize() {
+        Ok(real) => visited.lock().unwrap().insert(real),
+        Err(_) => false,
+    }
+}
+
+/// root 配下を複数スレッドで走査し、repo を見つけるたびに on_repo を呼ぶ。
+/// symlink をたどる場合は全ディレクトリを canonicalize 済みパスで訪問済みにして、ループと二重検出を防ぐ。
+/// rules.max_repos に達したらそこで打ち切る。memo があれば変化のないディレクトリは readdir しない。
+/// 最後まで走査できたら true（キャンセル / 上限で打ち切ったら false）
+pub(crate) fn parallel_walk<F>(
+    root: &Path,
+    rules: &ScanRules,
+    cancel: &AtomicBool,
+    memo: Option<&DirMemo>,
+    on_repo: F,
+) -> bool
+where
+    F: Fn(PathBuf) + Sync,
+{
+    let queue = Mutex::new(WorkQueue {
+        items: vec![(root.to_path_buf(), 0, Arc::new(Vec::new()))],
+        in_flight: 0,
+    });
+    let cv = Condvar::new();
+    let visited = Mutex::new(HashSet::<PathBuf>::new());
+    if let Ok(c) = root.canonicalize() {
+        visited.lock().unwrap().insert(c);
+    }
+
+    let found = AtomicU64::new(0);
+    let limit_hit = AtomicBool::new(false);
+    let emit = |p: PathBuf| {
+        if !rules.is_included(&p) {
+            return;
+        }
+        let n = found.fetch_add(1, Ordering::Relaxed) as usize;
+        match rules.max_repos {
+            Some(max) if n >= max => limit_hit.store(true, Ordering::Relaxed),
+            Some(max) => {
+                on_repo(p);
+                if n + 1 >= max {
+                    limit_hit.store(true, Ordering::Relaxed);
+                }
+            }
+            None => on_repo(p),
+        }
+    };
+    let stopped = || cancel.load(Ordering::Relaxed) || limit_hit.load(Ordering::Relaxed);
+
+    let workers = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+        .clamp(2, 16);
+
+    thread::scope(|s| {
+        for _ in 0..workers {
+            s.spawn(|| loop {
+                let (dir, depth, ignores) = {
+                    let mut q = queue.lock().unwrap();
+                    loop {
+                        if stopped() {
+                            cv.notify_all();
+                            return;
+                        }
+                        if let Some(item) = q.items.pop() {
+                            q.in_flight += 1;
+                            break item;
+                        }
+                        if q.in_flight == 0 {
+                            cv.notify_all();
+                            return;
+                        }
+                        q = cv.wait(q).unwrap();
+                    }
+                };
+
+                let mut children: Vec<(PathBuf, u8, Arc<Vec<IgnoreRule>>)> = Vec::new();
+                if depth <= rules.max_depth {
+                    let ignores = if rules.respect_gitignore {
+                        let own = scan_profile::gitignore_rules(&dir);
+                        if own.is_empty() {
+                            ignores
+                        } else {
+                            Arc::new(ignores.iter().cloned().chain(own).collect())
+                        }
+                    } else {
+                        ignores
+                    };
+                    let stamp = memo.and_then(|_| dir_stamp(&dir, rules));
+                    let reuse = memo
+                        .and_then(|m| m.prev.get(&dir))
+                        .filter(|e| stamp == Some(e.mtime));
+                    let entry = match reuse {
+                        Some(e) => {
+                            let e = recheck_memo(&dir, e);
+                            for r in &e.repos {
+                                emit(r.clone());
+                            }
+                            for c in e
+                                .children
+                                .iter()
+                                .filter(|c| first_visit(c, rules, &visited))
+                            {
+                                children.push((c.clone(), depth + 1, ignores.clone()));
+                            }
+                            e
+                        }
+                        None => {
+                            let mut e = DirMemoEntry {
+                                mtime: stamp.unwrap_or(0),
+                                ..Default::default()
+                            };
+                            scan_dir(
+                                &dir,
+                                rules,
+                                &ignores,
+                                &visited,
+                                |p| {
+                                    e.repos.push(p.clone());
+                                    emit(p)
+                                },
+                                |p| {
+                                    e.children.push(p.clone());
+                                    children.push((p, depth + 1, ignores.clone()))
+                                },
+                            );
+                            e
+                        }
+                    };
+                    if let (Some(m), Some(_)) = (memo, stamp) {
+                        m.next.lock().unwrap().insert(dir.clone(), entry);
+                    }
+                }
+
+                let mut q = queue.lock().unwrap();
+                q.items.extend(children);
+                q.in_flight -= 1;
+                cv.notify_all();
+            });
+        }
+    });
+
+    !stopped()
+}
+
+fn is_repo(p: &Path) -> bool {
+    is_git_repo_dir(p) || gitdir::is_bare_repo_dir(p)
+}
+
+// 子ディレクトリでの git init や .git の削除では親の mtime が変わらないので、
+// 再利用する memo の repo / children はここで判定し直す
+fn recheck_memo(dir: &Path, e: &DirMemoEntry) -> DirMemoEntry {
+    let mut out = DirMemoEntry {
+        mtime: e.mtime,
+        ..Default::default()
+    };
+    for r in &e.repos {
+        if is_repo(r) {
+            out.repos.push(r.clone());
+        } else if r.parent() == Some(dir) && r.is_dir() {
+            // repo でなくなった直下のディレクトリは普通に降りる
+            out.children.push(r.clone());
+        }
+    }
+    for c in &e.children {
+        if is_repo(c) {
+            out.repos.extend(submodule_dirs(c));
+            out.repos.push(c.clone());
+        } else {
+            out.children.push(c.clone());
+        }
+    }
+    out
+}
+
+fn scan_dir<R, C>(
+    dir: &Path,
+    rules: &ScanRules,
+    ignores: &[IgnoreRule],
+    visited: &Mutex<HashSet<PathBuf>>,
+    mut on_repo: R,
+    mut push_child: C,
+) where
+    R: FnMut(PathBuf),
+    C: FnMut(PathBuf),
+{
+    let rd = match std::fs::read_dir(dir) {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    for entry in rd.flatten() {
+        let ft = match entry.file_type() {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+
+        let p = entry.path();
+        let is_dir = if ft.is_symlink() {
+            rules.follow_symlinks && p.is_dir()
+        } else {
+            ft.is_dir()
+        };
+        if !is_dir || entry.file_name() == ".git" {
+            continue;
+        }
+
+        if rules.is_excluded(&p)
+            || scan_profile::is_gitignored(ignores, &p)
+            || !rules.same_filesystem(&p)
+        {
+            continue;
+        }
+
+        if !first_visit(&p, rules, visited) {
+            continue;
+        }
+
+        if is_repo(&p) {
+            for sub in submodule_dirs(&p) {
+                on_repo(sub);
+            }
+            on_repo(p);
+            continue;
+        }
+
+        push_child(p);
+    }
+}
+
+/// root 自体が repo ならそれ（と submodule）だけ、そうでなければ parallel_walk で集める。
+/// 戻り値の bool は parallel_walk と同じ
+pub(crate) fn walk_repos<F>(
+    root: &Path,
+    rules: &ScanRules,
+    cancel: &AtomicBool,
+    memo: Option<&DirMemo>,
+    on_repo: F,
+) -> bool
+where
+    F: Fn(PathBuf) + Sync,
+{
+    if is_repo(root) {
+        for sub in submodule_dirs(root) {
+            on_repo(sub);
+        }
+        on_repo(root.to_path_buf());
+        return true;
+    }
+    parallel_walk(root, rules, cancel, memo, on_repo)
+}
+
+/// .gitmodules の path のうち、実際に checkout されている submodule
+pub(crate) fn submodule_dirs(repo_dir: &Path) -> Vec<PathBuf> {
+    let Ok(text) = std::fs::read_to_string(repo_dir.join(".gitmodules")) else {
+        return Vec::new();
+    };
+    gitdir::parse_config(&text)
+        .into_iter()
+        .filter(|e| e.section == "submodule" && e.key == "path" && !e.value.is_empty())
+        .map(|e| repo_dir.join(e.value))
+        .filter(|p| is_git_repo_dir(p))
+        .collect()
+}
+
+/// .git を直接読んで kind / remotes / HEAD を埋める（config が読めない特殊な構成のときだけ git にフォールバック）。
+/// 日付と dirty は git が必要
+pub(crate) fn detected_repo(repo_dir: &Path, git: Option<&Path>) -> DetectedRepo {
+    let name = repo_dir
+        .file_name()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_string());
+
+    let dirs = gitdir::resolve_git_dirs(repo_dir);
+    let config = dirs.as_ref().and_then(gitdir::read_config);
+    let (origin_url, remotes) = match &config {
+        Some(c) => (gitdir::remote_url(c, "origin"), gitdir::remotes(c)),
+        None => (
+            git.and_then(|g| git_remote_origin_url(g, repo_dir)),
+            Vec::new(),
+        ),
+    };
+    let (branch, head_sha) = dirs.as_ref().map(gitdir::read_head).unwrap_or_default();
+    let kind = dirs.as_ref().map(|d| d.kind).unwrap_or("normal");
+
+    let mut head_date = None;
+    if let (Some(g), Some(_)) = (git, &head_sha) {
+        let s = run_capture(g, &["log", "-1", "--format=%cI"], Some(repo_dir));
+        if s.ok && !s.stdout.trim().is_empty() {
+            head_date = Some(s.stdout.trim().to_string());
+        }
+    }
+    let dirty = repo_dirty(repo_dir, kind, git);
+
+    DetectedRepo {
+        path: path_to_string(repo_dir),
+        origin_url,
+        name,
+        kind: kind.to_string(),
+        remotes,
+        branch,
+        head_sha,
+        head_date,
+        dirty,
+        size_bytes: Some(gitdir::dir_size(repo_dir)),
+    }
+}
+
+/// 作業ツリーの変更は .git 側の mtime に出ないので、stamp とは別に毎回取る
+pub(crate) fn repo_dirty(repo_dir: &Path, kind: &str, git: Option<&Path>) -> Option<bool> {
+    let g = git.filter(|_| kind != "bare")?;
+    let s = run_capture(
+        g,
+        &[
+            "--no-optional-locks",
+            "status",
+            "--porcelain",
+            "--ignore-submodules",
+        ],
+        Some(repo_dir),
+    );
+    s.ok.then(|| !s.stdout.trim().is_empty())
+}
+
+pub(crate) fn resolve_scan_root(root_path: &str) -> Result<PathBuf, String> {
+    let p = normalize_path_input(root_path);
+    let p = p.trim().to_string();
+    if p.is_empty() {
+        return Err("root_path is empty".to_string());
+    }
+
+    let root = PathBuf::from(&p);
+    if !root.exists() {
+        return Err(format!(
+            "root_path does not exist: {}",
+            path_to_string(&root)
+        ));
+    }
+    if !root.is_dir() {
+        return Err(format!(
+            "root_path is not a directory: {}",
+            path_to_string(&root)
+        ));
+    }
+    Ok(root)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub(crate) fn start_local_scan(
+    app: AppHandle,
+    registry: State<'_, ScanRegistry>,
+    root_path: String,
+    max_depth: u8,
+    follow_symlinks: Option<bool>,
+    git_path: Option<String>,
+    profile: Option<ScanProfile>,
+) -> Result<String, String> {
+    let root = resolve_scan_root(&root_path)?;
+    let git = git_exe(git_path);
+    let mut profile = scan_profile::effective_profile(&app, profile, &root_path, None);
+    if let Some(f) = follow_symlinks {
+        profile.follow_symlinks = f;
+    }
+    let rules = ScanRules::compile(&root, &profile, max_depth)?;
+
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let scan_id = format!(
+        "scan-{}-{}",
+        millis,
+        registry.seq.fetch_add(1, Ordering::Relaxed)
+    );
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    registry
+        .running
+        .lock()
+        .unwrap()
+        .insert(scan_id.clone(), cancel.clone());
+
+    let id = scan_id.clone();
+    thread::spawn(move || {
+        let count = AtomicU64::new(0);
+        let emit_repo = |p: PathBuf| {
+            count.fetch_add(1, Ordering::Relaxed);
+            let _ = app.emit(
+                EVENT_REPO,
+                ScanRepoEvent {
+                    scan_id: id.clone(),
+                    repo: detected_repo(&p, git.as_deref()),
+                },
+            );
+        };
+
+        walk_repos(&root, &rules, &cancel, None, emit_repo);
+
+        let cancelled = cancel.load(Ordering::Relaxed);
+        app.state::<ScanRegistry>()
+            .running
+            .lock()
+            .unwrap()
+            .remove(&id);
+        let _ = app.emit(
+            EVENT_DONE,
+            ScanDoneEvent {
+                scan_id: id,
+                count: count.load(Ordering::Relaxed) as usize,
+                cancelled,
+            },
+        );
+    });
+
+    Ok(scan_id)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub(crate) fn cancel_scan(registry: State<'_, ScanRegistry>, scan_id: String) -> bool {
+    match registry.running.lock().unwrap().get(&scan_id) {
+        Some(flag) => {
+            flag.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan_profile::ScanProfile;
+
+    fn walk(root: &Path, memo: &DirMemo) -> Vec<PathBuf> {
+        let rules = ScanRules::compile(root, &ScanProfile::default(), 5).unwrap();
+        let found = Mutex::new(Vec::new());
+        parallel_walk(root, &rules, &AtomicBool::new(false), Some(memo), |p| {
+            found.lock().unwrap().push(p)
+        });
+        let mut v = found.into_inner().unwrap();
+        v.sort();
+        v
+    }
+
+    #[test]
+    fn memo_reuse_rechecks_repos_and_children() {
+        let root = std::env::temp_dir().join(format!("gitshlc-scan-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("a/x")).unwrap();
+        std::fs::create_dir_all(root.join("b/.git")).unwrap();
+        std::fs::create_dir_all(root.join("b/c/.git")).unwrap();
+
+        let memo = DirMemo::default();
+        assert_eq!(walk(&root, &memo), vec![root.join("b")]);
+
+        // 子の中での変更なので root の mtime（= memo の stamp）は変わらない
+        let before = mtime_nanos(&root);
+        std::fs::create_dir(root.join("a/.git")).unwrap();
+        std::fs::remove_dir(root.join("b/.git")).unwrap();
+        assert_eq!(mtime_nanos(&root), before);
+
+        let memo = DirMemo {
+            prev: memo.next.into_inner().unwrap(),
+            next: Mutex::new(HashMap::new()),
+        };
+        assert_eq!(walk(&root, &memo), vec![root.join("a"), root.join("b/c")]);
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn symlinked_sibling_is_walked_once() {
+        let root = std::env::temp_dir().join(format!("gitshlc-scan-link-{}", std::process::id()));
+        std::fs::remove_dir_all(&root).ok();
+        std::fs::create_dir_all(root.join("a/r/.git")).unwrap();
+        std::fs::create_dir_all(root.join("a/deep/s/.git")).unwrap();
+        std::os::unix::fs::symlink(root.join("a"), root.join("b")).unwrap();
+
+        let profile = ScanProfile {
+            follow_symlinks: true,
+            ..ScanProfile::default()
+        };
+        let rules = ScanRules::compile(&root, &profile, 5).unwrap();
+        let found = Mutex::new(Vec::new());
+        assert!(parallel_walk(
+            &root,
+            &rules,
+            &AtomicBool::new(false),
+            None,
+            |p| found.lock().unwrap().push(p)
+        ));
+        // a と b のどちらから届いても、実体ごとに 1 回だけ
+        let mut real: Vec<PathBuf> = found
+            .into_inner()
+            .unwrap()
+            .iter()
+            .map(|p| p.canonicalize().unwrap())
+            .collect();
+        real.sort();
+        let a = root.join("a").canonicalize().unwrap();
+        assert_eq!(real, vec![a.join("deep/s"), a.join("r")]);
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
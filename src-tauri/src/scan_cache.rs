@@ -0,0 +1,562 @@
+// 検出結果のキャッシュ（root ごと / SSH ホストごと）。
+// 再スキャンは変化したディレクトリ・repo だけを見直し、見つからなくなった repo は vanished にする
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{atomic::AtomicBool, Arc, Mutex},
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::{
+    git_exe, gitdir, path_to_string, remote_detect,
+    scan::{self, DirMemo, DirMemoEntry},
+    scan_profile::{self, ScanProfile, ScanRules},
+    ssh_exe, DetectedRepo, RemoteIncremental, SshConfig,
+};
+
+const CACHE_DIR: &str = "detect-cache";
+
+pub(crate) const EVENT_UPDATED: &str = "cache:updated";
+
+#[derive(Default)]
+pub(crate) struct CacheRegistry {
+    // バックグラウンド更新中のキー
+    refreshing: Mutex<HashSet<String>>,
+    // キーごとの更新ロック（load → スキャン → store を detect_* とバックグラウンド更新で重ねない）
+    locks: Mutex<HashMap<String, Arc<Mutex<()>>>>,
+}
+
+impl CacheRegistry {
+    fn lock_for(&self, key: &str) -> Arc<Mutex<()>> {
+        self.locks
+            .lock()
+            .unwrap()
+            .entry(key.to_string())
+            .or_default()
+            .clone()
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CachedRepo {
+    #[serde(flatten)]
+    repo: DetectedRepo,
+    // epoch millis
+    first_seen: u64,
+    last_seen: u64,
+    vanished: bool,
+    vanished_at: Option<u64>,
+    // .git 側の mtime。変わっていなければ dirty 以外のメタデータを取り直さない（ローカルのみ）
+    #[serde(default)]
+    stamp: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct RootCache {
+    key: String,
+    root_path: String,
+    // remote のときだけ user@host:port
+    host: Option<String>,
+    // プロファイルが変わったら dirs は捨てる
+    profile: String,
+    refreshed_at: Option<u64>,
+    repos: Vec<CachedRepo>,
+    dirs: HashMap<PathBuf, DirMemoEntry>,
+    // remote: 前回の完全走査で見たディレクトリと、そのとき remote の stamp に書いた token
+    remote_dirs: Vec<String>,
+    remote_token: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CachedRepos {
+    key: String,
+    root_path: String,
+    host: Option<String>,
+    refreshed_at: Option<u64>,
+    refreshing: bool,
+    repos: Vec<CachedRepo>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheUpdatedEvent {
+    key: String,
+    ok: bool,
+    error: Option<String>,
+    count: usize,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// キャッシュファイル / remote の stamp の名前。記号だけ違う root（my-app と my_app）を分けるためキー全体のハッシュにする
+fn file_name(key: &str) -> String {
+    hex::encode(&Sha256::digest(key.as_bytes())[..16])
+}
+
+fn cache_file(app: &AppHandle, key: &str) -> Result<PathBuf, String> {
+    let name = file_name(key);
+    app.path()
+        .app_cache_dir()
+        .map(|d| d.join(CACHE_DIR).join(format!("{}.json", name)))
+        .map_err(|e| e.to_string())
+}
+
+fn load(app: &AppHandle, key: &str) -> RootCache {
+    cache_file(app, key)
+        .ok()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|s| serde_json::from_str::<RootCache>(&s).ok())
+        .filter(|c| c.key == key)
+        .unwrap_or_default()
+}
+
+fn store(app: &AppHandle, cache: &RootCache) -> Result<(), String> {
+    let p = cache_file(app, &cache.key)?;
+    if let Some(dir) = p.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let text = serde_json::to_string(cache).map_err(|e| e.to_string())?;
+    write_atomic(&p, &text)
+}
+
+// 書きかけのファイルを読まれないように一時ファイルに書いてから rename する
+fn write_atomic(p: &Path, text: &str) -> Result<(), String> {
+    let tmp = p.with_extension(format!("json.{}.tmp", std::process::id()));
+    std::fs::write(&tmp, text)
+        .and_then(|_| std::fs::rename(&tmp, p))
+        .map_err(|e| {
+            let _ = std::fs::remove_file(&tmp);
+            format!("{}: {}", path_to_string(p), e)
+        })
+}
+
+fn profile_fingerprint(profile: &ScanProfile, max_depth: u8, max_repos: Option<u16>) -> String {
+    format!(
+        "{}|{}|{}",
+        serde_json::to_string(profile).unwrap_or_default(),
+        max_depth,
+        max_repos.map(|n| n.to_string()).unwrap_or_default()
+    )
+}
+
+/// .git 側で HEAD 移動・index 更新・fetch があれば変わる値
+fn repo_stamp(repo_dir: &Path) -> Option<u64> {
+    let dirs = gitdir::resolve_git_dirs(repo_dir)?;
+    ["", "HEAD", "index", "logs/HEAD", "FETCH_HEAD"]
+        .iter()
+        .filter_map(|f| scan::mtime_nanos(&dirs.git_dir.join(f)))
+        .max()
+}
+
+/// found: 今回メタデータを取り直した repo / unchanged: 変化なしと判定された既知の repo（と最新の dirty）。
+/// complete=false（上限で打ち切り）のときは見つからなかった repo を vanished にしない
+fn merge(
+    cache: &mut RootCache,
+    found: Vec<CachedRepo>,
+    unchanged: &[(String, Option<bool>)],
+    complete: bool,
+) {
+    let now = now_millis();
+    let mut found: HashMap<String, CachedRepo> = found
+        .into_iter()
+        .map(|r| (r.repo.path.clone(), r))
+        .collect();
+    let unchanged: HashMap<&str, Option<bool>> =
+        unchanged.iter().map(|(p, d)| (p.as_str(), *d)).collect();
+
+    for c in cache.repos.iter_mut() {
+        if let Some(f) = found.remove(&c.repo.path) {
+            c.repo = f.repo;
+            c.stamp = f.stamp;
+        } else if let Some(dirty) = unchanged.get(c.repo.path.as_str()) {
+            c.repo.dirty = *dirty;
+        } else {
+            if complete && !c.vanished {
+                c.vanished = true;
+                c.vanished_at = Some(now);
+            }
+            continue;
+        }
+        c.last_seen = now;
+        c.vanished = false;
+        c.vanished_at = None;
+    }
+
+    let mut added: Vec<CachedRepo> = found.into_values().collect();
+    for r in added.iter_mut() {
+        r.first_seen = now;
+        r.last_seen = now;
+    }
+    cache.repos.extend(added);
+    cache.repos.sort_by(|a, b| a.repo.path.cmp(&b.repo.path));
+    cache.refreshed_at = Some(now);
+}
+
+fn fresh(repo: DetectedRepo, stamp: Option<u64>) -> CachedRepo {
+    CachedRepo {
+        repo,
+        first_seen: 0,
+        last_seen: 0,
+        vanished: false,
+        vanished_at: None,
+        stamp,
+    }
+}
+
+fn present(cache: &RootCache) -> Vec<DetectedRepo> {
+    cache
+        .repos
+        .iter()
+        .filter(|r| !r.vanished)
+        .map(|r| r.repo.clone())
+        .collect()
+}
+
+/// ローカル root を差分スキャンしてキャッシュを更新し、現存する repo を返す
+pub(crate) fn refresh_local(
+    app: &AppHandle,
+    root_path: &str,
+    max_depth: u8,
+    git: Option<PathBuf>,
+    profile: Option<ScanProfile>,
+) -> Result<Vec<DetectedRepo>, String> {
+    let root = scan::resolve_scan_root(root_path)?;
+    let profile = scan_profile::effective_profile(app, profile, root_path, None);
+    let rules = ScanRules::compile(&root, &profile, max_depth)?;
+    let key = scan_profile::profile_key(root_path, None);
+
+    let lock = app.state::<CacheRegistry>().lock_for(&key);
+    let _guard = lock.lock().unwrap();
+    let mut cache = load(app, &key);
+    cache.key = key;
+    rescan_local(
+        &mut cache,
+        &root,
+        &rules,
+        profile_fingerprint(&profile, max_depth, None),
+        git.as_deref(),
+    );
+    store(app, &cache)?;
+    Ok(present(&cache))
+}
+
+// 変化のないディレクトリは readdir せず、.git 側の stamp が同じ repo はメタデータを取り直さない
+fn rescan_local(
+    cache: &mut RootCache,
+    root: &Path,
+    rules: &ScanRules,
+    fp: String,
+    git: Option<&Path>,
+) {
+    cache.root_path = path_to_string(root);
+    let memo = DirMemo {
+        prev: if cache.profile == fp {
+            std::mem::take(&mut cache.dirs)
+        } else {
+            HashMap::new()
+        },
+        next: Mutex::new(HashMap::new()),
+    };
+    cache.profile = fp;
+
+    let known: HashMap<&str, &CachedRepo> = cache
+        .repos
+        .iter()
+        .map(|r| (r.repo.path.as_str(), r))
+        .collect();
+    // メタデータ（git log / status / サイズ）は walk のワーカースレッド上でそのまま取る
+    let seen = Mutex::new(HashSet::<PathBuf>::new());
+    let refreshed = Mutex::new(Vec::<CachedRepo>::new());
+    let never = AtomicBool::new(false);
+    let complete = scan::walk_repos(root, rules, &never, Some(&memo), |p| {
+        if !seen.lock().unwrap().insert(p.clone()) {
+            return;
+        }
+        let stamp = repo_stamp(&p);
+        let repo = match known.get(path_to_string(&p).as_str()) {
+            Some(c) if stamp.is_some() && c.stamp == stamp => {
+                let mut c = (*c).clone();
+                c.repo.dirty = scan::repo_dirty(&p, &c.repo.kind, git);
+                c
+            }
+            _ => fresh(scan::detected_repo(&p, git), stamp),
+        };
+        refreshed.lock().unwrap().push(repo);
+    });
+    let refreshed = refreshed.into_inner().unwrap();
+
+    merge(cache, refreshed, &[], complete);
+    cache.dirs = memo.next.into_inner().unwrap();
+}
+
+/// remote root をスキャンしてキャッシュを更新する。前回から変化のない既知 repo は
+/// remote 側でメタデータ取得を省略し、mtime の変わっていないディレクトリは前回の一覧を使って
+/// readdir しない（remote の ~/.cache/gitshlc に stamp を置く）
+pub(crate) fn refresh_remote(
+    app: &AppHandle,
+    ssh_exe: &Path,
+    ssh: &SshConfig,
+    root_path: &str,
+    max_depth: u8,
+    max_repos: u16,
+    profile: Option<ScanProfile>,
+) -> Result<Vec<DetectedRepo>, String> {
+    let profile = scan_profile::effective_profile(app, profile, root_path, Some(ssh));
+    let key = scan_profile::profile_key(root_path, Some(ssh));
+
+    let lock = app.state::<CacheRegistry>().lock_for(&key);
+    let _guard = lock.lock().unwrap();
+    let mut cache = load(app, &key);
+    cache.key = key.clone();
+    cache.root_path = root_path.trim().to_string();
+    cache.host = Some(format!(
+        "{}@{}:{}",
+        ssh.user.trim(),
+        ssh.host.trim(),
+        ssh.port.unwrap_or(22)
+    ));
+    let fp = profile_fingerprint(&profile, max_depth, Some(max_repos));
+    let same_profile = cache.profile == fp;
+    let known: Vec<String> = if same_profile {
+        cache
+            .repos
+            .iter()
+            .filter(|r| !r.vanished)
+            .map(|r| r.repo.path.clone())
+            .collect()
+    } else {
+        Vec::new()
+    };
+    // .gitignore の除外は remote で毎回組み立てるので、そのときはディレクトリの一覧を使わない
+    let (dirs, memo_token) = match cache.remote_token.take() {
+        Some(t) if same_profile && !profile.respect_gitignore => {
+            (std::mem::take(&mut cache.remote_dirs), t)
+        }
+        _ => (Vec::new(), String::new()),
+    };
+    cache.profile = fp;
+    let token = now_millis().to_string();
+
+    let stamp_name = file_name(&key);
+    let res = remote_detect(
+        ssh_exe,
+        ssh,
+        root_path,
+        max_depth,
+        max_repos,
+        &profile,
+        Some(&RemoteIncremental {
+            stamp_name,
+            known,
+            dirs,
+            memo_token,
+            token: token.clone(),
+        }),
+    )?;
+
+    // 打ち切ったときの一覧は欠けているので次回はフルスキャンにする
+    if res.complete {
+        cache.remote_dirs = res.dirs;
+        cache.remote_token = Some(token);
+    } else {
+        cache.remote_dirs = Vec::new();
+        cache.remote_token = None;
+    }
+    let found = res.repos.into_iter().map(|r| fresh(r, None)).collect();
+    merge(&mut cache, found, &res.unchanged, res.complete);
+    store(app, &cache)?;
+    Ok(present(&cache))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_refresh(
+    app: AppHandle,
+    key: String,
+    root_path: String,
+    ssh: Option<SshConfig>,
+    ssh_path: Option<String>,
+    git_path: Option<String>,
+    max_depth: u8,
+    max_repos: u16,
+) {
+    thread::spawn(move || {
+        let res = match &ssh {
+            Some(cfg) => match ssh_exe(ssh_path) {
+                Some(exe) => {
+                    refresh_remote(&app, &exe, cfg, &root_path, max_depth, max_repos, None)
+                }
+                None => Err("ssh not found. Run preflight and set sshPath if needed.".into()),
+            },
+            None => refresh_local(&app, &root_path, max_depth, git_exe(git_path), None),
+        };
+
+        app.state::<CacheRegistry>()
+            .refreshing
+            .lock()
+            .unwrap()
+            .remove(&key);
+        let (ok, error, count) = match res {
+            Ok(v) => (true, None, v.len()),
+            Err(e) => (false, Some(e), 0),
+        };
+        let _ = app.emit(
+            EVENT_UPDATED,
+            CacheUpdatedEvent {
+                key,
+                ok,
+                error,
+                count,
+            },
+        );
+    });
+}
+
+/// キャッシュをすぐ返し、refresh（default true）ならバックグラウンドで差分スキャンを始める。
+/// 完了は cache:updated イベントで通知する
+#[allow(clippy::too_many_arguments)]
+#[tauri::command(rename_all = "camelCase")]
+pub(crate) fn get_cached_repos(
+    app: AppHandle,
+    registry: State<'_, CacheRegistry>,
+    root_path: String,
+    ssh: Option<SshConfig>,
+    ssh_path: Option<String>,
+    git_path: Option<String>,
+    max_depth: Option<u8>,
+    max_repos: Option<u16>,
+    refresh: Option<bool>,
+) -> CachedRepos {
+    let key = scan_profile::profile_key(&root_path, ssh.as_ref());
+    let cache = load(&app, &key);
+
+    let mut refreshing = registry.refreshing.lock().unwrap();
+    if refresh.unwrap_or(true) && refreshing.insert(key.clone()) {
+        spawn_refresh(
+            app.clone(),
+            key.clone(),
+            root_path.clone(),
+            ssh,
+            ssh_path,
+            git_path,
+            max_depth.unwrap_or(8),
+            max_repos.unwrap_or(50),
+        );
+    }
+
+    CachedRepos {
+        key: key.clone(),
+        root_path: if cache.root_path.is_empty() {
+            root_path
+        } else {
+            cache.root_path
+        },
+        host: cache.host,
+        refreshed_at: cache.refreshed_at,
+        refreshing: refreshing.contains(&key),
+        repos: cache.repos,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run_capture;
+
+    fn init(p: &Path) {
+        let s = run_capture(Path::new("git"), &["init", "-q", &path_to_string(p)], None);
+        assert!(s.ok, "{}", s.stderr);
+    }
+
+    fn rescan(cache: &mut RootCache, root: &Path) -> Vec<String> {
+        let profile = ScanProfile::default();
+        let rules = ScanRules::compile(root, &profile, 5).unwrap();
+        let fp = profile_fingerprint(&profile, 5, None);
+        rescan_local(cache, root, &rules, fp, Some(Path::new("git")));
+        present(cache).into_iter().map(|r| r.path).collect()
+    }
+
+    #[test]
+    fn incremental_local_refresh_reuses_unchanged_repos() {
+        let root = std::env::temp_dir().join(format!("gitshlc-cache-{}", std::process::id()));
+        std::fs::remove_dir_all(&root).ok();
+        std::fs::create_dir_all(root.join("x")).unwrap();
+        init(&root.join("a"));
+        init(&root.join("x/b"));
+        let p = |rel: &str| path_to_string(&root.join(rel));
+
+        let mut cache = RootCache::default();
+        assert_eq!(rescan(&mut cache, &root), [p("a"), p("x/b")]);
+        assert!(cache.dirs.contains_key(&root));
+        let first_seen = cache.repos[0].first_seen;
+
+        // stamp が変わらない repo はメタデータを取り直さない（dirty だけ更新する）
+        cache.repos[0].repo.name = Some("from-cache".into());
+        std::fs::write(root.join("a/file.txt"), "x").unwrap();
+        init(&root.join("x/c"));
+        std::fs::remove_dir_all(root.join("x/b")).unwrap();
+
+        assert_eq!(rescan(&mut cache, &root), [p("a"), p("x/c")]);
+        let a = &cache.repos[0];
+        assert_eq!(a.repo.name.as_deref(), Some("from-cache"));
+        assert_eq!(a.repo.dirty, Some(true));
+        assert_eq!(a.first_seen, first_seen);
+        let b = cache
+            .repos
+            .iter()
+            .find(|r| r.repo.path == p("x/b"))
+            .unwrap();
+        assert!(b.vanished && b.vanished_at.is_some());
+
+        // HEAD が動けば取り直す
+        let s = run_capture(
+            Path::new("git"),
+            &["-C", &p("a"), "symbolic-ref", "HEAD", "refs/heads/other"],
+            None,
+        );
+        assert!(s.ok, "{}", s.stderr);
+        rescan(&mut cache, &root);
+        let a = &cache.repos[0];
+        assert_eq!(a.repo.name.as_deref(), Some("a"));
+        assert_eq!(a.repo.branch.as_deref(), Some("other"));
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn file_names_keep_keys_apart() {
+        let key = |root: &str| scan_profile::profile_key(root, None);
+        let a = file_name(&key("/srv/my-app"));
+        assert_eq!(a, file_name(&key("/srv/my-app")));
+        assert_ne!(a, file_name(&key("/srv/my_app")));
+        assert_ne!(
+            file_name("ssh://deploy@host:22/srv"),
+            file_name("ssh://deploy@host_22/srv")
+        );
+        assert!(a.len() == 32 && a.bytes().all(|b| b.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn cache_file_is_replaced_atomically() {
+        let dir = std::env::temp_dir().join(format!("gitshlc-cache-w-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let p = dir.join("k.json");
+        write_atomic(&p, "{\"a\":1}").unwrap();
+        write_atomic(&p, "{\"a\":2}").unwrap();
+        assert_eq!(std::fs::read_to_string(&p).unwrap(), "{\"a\":2}");
+        let names: Vec<_> = std::fs::read_dir(&dir).unwrap().flatten().collect();
+        assert_eq!(names.len(), 1);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,450 @@
+// スキャンプロファイル: include / exclude glob・.gitignore・上限・symlink / filesystem の扱い。
+// ローカル walker と remote の find スクリプトに同じ規則を適用し、root ごとに保存する
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use glob::Pattern;
+use tauri::{AppHandle, Manager};
+
+use crate::{normalize_path_input, path_to_string, shell_escape_posix_single, SshConfig};
+
+const PROFILES_FILE: &str = "scan_profiles.json";
+
+// 以前の should_skip_dir と同じ（.git は walker 側で常に扱う）
+const DEFAULT_EXCLUDES: &[&str] = &[
+    "node_modules",
+    "target",
+    "dist",
+    "build",
+    ".venv",
+    ".idea",
+    ".vscode",
+];
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct ScanProfile {
+    // '/' を含まない pattern はディレクトリ名に、含む pattern は root からの相対パスに当てる
+    // （`*` は '/' にもマッチ = find -path と同じ）。空なら全 repo を報告する
+    pub(crate) include: Vec<String>,
+    // マッチしたディレクトリには入らない
+    pub(crate) exclude: Vec<String>,
+    // .gitignore に書かれたディレクトリには入らない（! による否定は未対応）
+    pub(crate) respect_gitignore: bool,
+    pub(crate) max_repos: Option<u32>,
+    // 指定があれば呼び出し側の maxDepth より優先
+    pub(crate) max_depth: Option<u8>,
+    pub(crate) follow_symlinks: bool,
+    pub(crate) cross_filesystems: bool,
+}
+
+impl Default for ScanProfile {
+    fn default() -> Self {
+        ScanProfile {
+            include: Vec::new(),
+            exclude: DEFAULT_EXCLUDES.iter().map(|s| s.to_string()).collect(),
+            respect_gitignore: false,
+            max_repos: None,
+            max_depth: None,
+            follow_symlinks: false,
+            cross_filesystems: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct GlobRule {
+    pat: Pattern,
+    // true: 相対パスに当てる / false: 名前に当てる
+    by_path: bool,
+}
+
+impl GlobRule {
+    fn parse(raw: &str) -> Result<Option<Self>, String> {
+        let t = raw.trim().trim_end_matches('/');
+        if t.is_empty() {
+            return Ok(None);
+        }
+        let anchored = t.starts_with('/');
+        let t = t.trim_start_matches('/');
+        let pat = Pattern::new(t).map_err(|e| format!("invalid glob {:?}: {}", raw, e))?;
+        Ok(Some(GlobRule {
+            pat,
+            by_path: anchored || t.contains('/'),
+        }))
+    }
+
+    fn matches(&self, name: &str, rel: &str) -> bool {
+        if self.by_path {
+            self.pat.matches(rel)
+        } else {
+            self.pat.matches(name)
+        }
+    }
+}
+
+fn parse_rules(raw: &[String]) -> Result<Vec<GlobRule>, String> {
+    let mut out = Vec::new();
+    for r in raw {
+        if let Some(rule) = GlobRule::parse(r)? {
+            out.push(rule);
+        }
+    }
+    Ok(out)
+}
+
+fn rel_path(base: &Path, p: &Path) -> String {
+    p.strip_prefix(base)
+        .map(|r| {
+            r.components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+        .unwrap_or_default()
+}
+
+fn dir_name(p: &Path) -> String {
+    p.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// ある .gitignore から読んだ 1 行分（base はその .gitignore のあるディレクトリ）
+#[derive(Debug, Clone)]
+pub(crate) struct IgnoreRule {
+    base: PathBuf,
+    rule: GlobRule,
+}
+
+pub(crate) fn gitignore_rules(dir: &Path) -> Vec<IgnoreRule> {
+    let Ok(text) = std::fs::read_to_string(dir.join(".gitignore")) else {
+        return Vec::new();
+    };
+    text.lines()
+        .map(|l| l.trim_end())
+        .filter(|l| !l.is_empty() && !l.starts_with('#') && !l.starts_with('!'))
+        .filter_map(|l| GlobRule::parse(l).ok().flatten())
+        .map(|rule| IgnoreRule {
+            base: dir.to_path_buf(),
+            rule,
+        })
+        .collect()
+}
+
+pub(crate) fn is_gitignored(rules: &[IgnoreRule], p: &Path) -> bool {
+    let name = dir_name(p);
+    rules
+        .iter()
+        .any(|r| p.starts_with(&r.base) && r.rule.matches(&name, &rel_path(&r.base, p)))
+}
+
+#[cfg(unix)]
+fn device_id(p: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(p).ok().map(|m| m.dev())
+}
+
+#[cfg(not(unix))]
+fn device_id(_p: &Path) -> Option<u64> {
+    None
+}
+
+/// walker 用にコンパイルしたプロファイル
+pub(crate) struct ScanRules {
+    root: PathBuf,
+    include: Vec<GlobRule>,
+    exclude: Vec<GlobRule>,
+    pub(crate) respect_gitignore: bool,
+    pub(crate) follow_symlinks: bool,
+    pub(crate) max_repos: Option<usize>,
+    pub(crate) max_depth: u8,
+    // cross_filesystems=false のときだけ
+    root_dev: Option<u64>,
+}
+
+impl ScanRules {
+    pub(crate) fn compile(
+        root: &Path,
+        profile: &ScanProfile,
+        max_depth: u8,
+    ) -> Result<Self, String> {
+        Ok(ScanRules {
+            root: root.to_path_buf(),
+            include: parse_rules(&profile.include)?,
+            exclude: parse_rules(&profile.exclude)?,
+            respect_gitignore: profile.respect_gitignore,
+            follow_symlinks: profile.follow_symlinks,
+            max_repos: profile.max_repos.map(|n| n.max(1) as usize),
+            max_depth: profile.max_depth.unwrap_or(max_depth).clamp(1, 50),
+            root_dev: if profile.cross_filesystems {
+                None
+            } else {
+                device_id(root)
+            },
+        })
+    }
+
+    pub(crate) fn is_excluded(&self, p: &Path) -> bool {
+        let name = dir_name(p);
+        let rel = rel_path(&self.root, p);
+        self.exclude.iter().any(|r| r.matches(&name, &rel))
+    }
+
+    pub(crate) fn is_included(&self, p: &Path) -> bool {
+        if self.include.is_empty() {
+            return true;
+        }
+        let name = dir_name(p);
+        let rel = rel_path(&self.root, p);
+        self.include.iter().any(|r| r.matches(&name, &rel))
+    }
+
+    pub(crate) fn same_filesystem(&self, p: &Path) -> bool {
+        match self.root_dev {
+            Some(dev) => device_id(p) == Some(dev),
+            None => true,
+        }
+    }
+}
+
+/// remote スクリプト用の定義。以下を設定する:
+///   FIND_PRE / FIND_XDEV  find のオプション（-L / -xdev）
+///   "$@"                   prune するディレクトリの find 式（.gitignore 分も含む）
+///   inc_match <rel>        include 判定
+/// ROOT / MAXD は先に定義しておくこと
+pub(crate) fn remote_script_prelude(profile: &ScanProfile) -> Result<String, String> {
+    let include = parse_rules(&profile.include)?;
+    let exclude = parse_rules(&profile.exclude)?;
+
+    let mut s = String::new();
+    s.push_str(if profile.follow_symlinks {
+        "FIND_PRE=-L\n"
+    } else {
+        "FIND_PRE=\n"
+    });
+    s.push_str(if profile.cross_filesystems {
+        "FIND_XDEV=\n"
+    } else {
+        "FIND_XDEV=-xdev\n"
+    });
+    s.push_str("RP=\"${ROOT%/}\"\n");
+
+    // 式を空にしないための seed（.git は find 本体で先に処理される）
+    s.push_str("set -- -name .git\n");
+    for r in &exclude {
+        let p = shell_escape_posix_single(r.pat.as_str());
+        if r.by_path {
+            s.push_str(&format!("set -- \"$@\" -o -path \"$RP/\"{}\n", p));
+        } else {
+            s.push_str(&format!("set -- \"$@\" -o -name {}\n", p));
+        }
+    }
+
+    if profile.respect_gitignore {
+        s.push_str(
+            r#"while IFS= read -r gi; do
+  [ -f "$gi" ] || continue
+  d="${gi%/.gitignore}"
+  while IFS= read -r pat || [ -n "$pat" ]; do
+    case "$pat" in ''|'#'*|'!'*) continue ;; esac
+    pat="${pat%/}"
+    case "$pat" in
+      /*) set -- "$@" -o -path "$d$pat" ;;
+      */*) set -- "$@" -o -path "$d/$pat" ;;
+      *) set -- "$@" -o -path "$d/$pat" -o -path "$d/*/$pat" ;;
+    esac
+  done < "$gi"
+done <<GITSHLC_GITIGNORE
+$(find $FIND_PRE "$ROOT" -maxdepth "$MAXD" $FIND_XDEV \( -name .git -prune \) -o \( -type d \( "$@" \) -prune \) -o \( -type f -name .gitignore -print \) 2>/dev/null)
+GITSHLC_GITIGNORE
+"#,
+        );
+    }
+
+    s.push_str("inc_match() {\n");
+    if include.is_empty() {
+        s.push_str("  return 0\n");
+    } else {
+        s.push_str("  rel=\"$1\"\n  base=\"${rel##*/}\"\n");
+        for r in &include {
+            // case の pattern に未クォートの変数を置くと glob として扱われる
+            s.push_str(&format!(
+                "  p={}\n  case \"{}\" in $p) return 0 ;; esac\n",
+                shell_escape_posix_single(r.pat.as_str()),
+                if r.by_path { "$rel" } else { "$base" }
+            ));
+        }
+        s.push_str("  return 1\n");
+    }
+    s.push_str("}\n");
+    Ok(s)
+}
+
+/// 保存キー: ローカルは正規化した root、remote は ssh://user@host:port/root
+pub(crate) fn profile_key(root_path: &str, ssh: Option<&SshConfig>) -> String {
+    match ssh {
+        Some(s) => {
+            let root = root_path.trim();
+            format!(
+                "ssh://{}@{}:{}/{}",
+                s.user.trim(),
+                s.host.trim(),
+                s.port.unwrap_or(22),
+                if root.is_empty() { "~" } else { root }.trim_start_matches('/')
+            )
+        }
+        None => {
+            let p = normalize_path_input(root_path);
+            let t = p.trim();
+            let trimmed = t.trim_end_matches(['/', '\\']);
+            if trimmed.is_empty() {
+                t.to_string()
+            } else {
+                trimmed.to_string()
+            }
+        }
+    }
+}
+
+fn profiles_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_config_dir()
+        .map(|d| d.join(PROFILES_FILE))
+        .map_err(|e| e.to_string())
+}
+
+fn load_profiles(app: &AppHandle) -> HashMap<String, ScanProfile> {
+    profiles_path(app)
+        .ok()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn store_profiles(app: &AppHandle, all: &HashMap<String, ScanProfile>) -> Result<(), String> {
+    let p = profiles_path(app)?;
+    if let Some(dir) = p.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let text = serde_json::to_string_pretty(all).map_err(|e| e.to_string())?;
+    std::fs::write(&p, text).map_err(|e| format!("{}: {}", path_to_string(&p), e))
+}
+
+/// 明示されたプロファイル > root ごとに保存されたもの > デフォルト
+pub(crate) fn effective_profile(
+    app: &AppHandle,
+    explicit: Option<ScanProfile>,
+    root_path: &str,
+    ssh: Option<&SshConfig>,
+) -> ScanProfile {
+    explicit.unwrap_or_else(|| {
+        load_profiles(app)
+            .remove(&profile_key(root_path, ssh))
+            .unwrap_or_default()
+    })
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub(crate) fn get_scan_profile(
+    app: AppHandle,
+    root_path: String,
+    ssh: Option<SshConfig>,
+) -> ScanProfile {
+    effective_profile(&app, None, &root_path, ssh.as_ref())
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub(crate) fn save_scan_profile(
+    app: AppHandle,
+    root_path: String,
+    ssh: Option<SshConfig>,
+    profile: ScanProfile,
+) -> Result<(), String> {
+    // 壊れた glob は保存しない
+    parse_rules(&profile.include)?;
+    parse_rules(&profile.exclude)?;
+
+    let mut all = load_profiles(&app);
+    all.insert(profile_key(&root_path, ssh.as_ref()), profile);
+    store_profiles(&app, &all)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub(crate) fn delete_scan_profile(
+    app: AppHandle,
+    root_path: String,
+    ssh: Option<SshConfig>,
+) -> Result<bool, String> {
+    let mut all = load_profiles(&app);
+    let removed = all.remove(&profile_key(&root_path, ssh.as_ref())).is_some();
+    if removed {
+        store_profiles(&app, &all)?;
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs_match_names_or_root_relative_paths() {
+        let root = Path::new("/r");
+        let profile = ScanProfile {
+            include: vec!["app-*".into(), "/work/*/svc".into()],
+            exclude: vec!["node_modules".into(), "vendor/".into(), "/tmp".into()],
+            ..ScanProfile::default()
+        };
+        let rules = ScanRules::compile(root, &profile, 5).unwrap();
+
+        // '/' を含まない pattern は深さに関係なく名前で当たる
+        assert!(rules.is_excluded(Path::new("/r/a/b/node_modules")));
+        assert!(rules.is_excluded(Path::new("/r/x/vendor")));
+        // 先頭 '/' は root 直下だけ
+        assert!(rules.is_excluded(Path::new("/r/tmp")));
+        assert!(!rules.is_excluded(Path::new("/r/a/tmp")));
+
+        assert!(rules.is_included(Path::new("/r/x/app-web")));
+        assert!(rules.is_included(Path::new("/r/work/a/svc")));
+        // `*` は '/' にもマッチする
+        assert!(rules.is_included(Path::new("/r/work/a/b/svc")));
+        assert!(!rules.is_included(Path::new("/r/other/svc")));
+
+        let all = ScanRules::compile(root, &ScanProfile::default(), 5).unwrap();
+        assert!(all.is_included(Path::new("/r/anything")));
+        assert!(all.is_excluded(Path::new("/r/a/target")));
+
+        let bad = ScanProfile {
+            exclude: vec!["[".into()],
+            ..ScanProfile::default()
+        };
+        assert!(ScanRules::compile(root, &bad, 5).is_err());
+    }
+
+    #[test]
+    fn gitignore_rules_apply_below_their_directory() {
+        let root = std::env::temp_dir().join(format!("gitshlc-ignore-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join(".gitignore"), "# c\nout/\n!keep\n/top\n").unwrap();
+        std::fs::write(root.join("sub/.gitignore"), "cache/*\n").unwrap();
+
+        let mut rules = gitignore_rules(&root);
+        // コメントと否定は読まない
+        assert_eq!(rules.len(), 2);
+        rules.extend(gitignore_rules(&root.join("sub")));
+
+        assert!(is_gitignored(&rules, &root.join("out")));
+        assert!(is_gitignored(&rules, &root.join("a/b/out")));
+        assert!(is_gitignored(&rules, &root.join("top")));
+        assert!(!is_gitignored(&rules, &root.join("a/top")));
+        assert!(!is_gitignored(&rules, &root.join("keep")));
+        assert!(is_gitignored(&rules, &root.join("sub/cache/x")));
+        assert!(!is_gitignored(&rules, &root.join("cache/x")));
+        assert!(gitignore_rules(&root.join("none")).is_empty());
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
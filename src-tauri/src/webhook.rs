@@ -0,0 +1,902 @@
+// push webhook（GitHub / Gitea）を受けて、repo + branch が一致する環境で pull を実行する組み込み HTTP listener
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::{remote_url, ActionError, RunActionRequest};
+
+pub(crate) const EVENT_DELIVERY: &str = "webhook:delivery";
+
+const DEFAULT_BIND: &str = "127.0.0.1:8787";
+const DEFAULT_PATH: &str = "/webhook";
+const MAX_HEADER_BYTES: usize = 64 * 1024;
+const MAX_BODY_BYTES: usize = 5 * 1024 * 1024;
+// ヘッダを少しずつ送ってくる接続を切るまでの時間
+const HEADER_DEADLINE: Duration = Duration::from_secs(10);
+// 同時に処理する接続（1 接続 1 スレッド）。超えた分はすぐ閉じる
+const MAX_CONNECTIONS: usize = 32;
+// delivery log に残す件数
+const LOG_CAP: usize = 200;
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WebhookTarget {
+    // log / event 用の名前（default: envKey）
+    #[serde(default)]
+    label: Option<String>,
+    // この repo への push だけ拾う（scp / https どちらの形でもよい）
+    repo_url: String,
+    // default: request.branch
+    #[serde(default)]
+    branch: Option<String>,
+    // run_action にそのまま渡す（action は pull に置き換える）
+    request: RunActionRequest,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WebhookConfig {
+    // default 127.0.0.1:8787（外から受けるなら 0.0.0.0:port）
+    #[serde(default)]
+    bind: Option<String>,
+    // default /webhook
+    #[serde(default)]
+    path: Option<String>,
+    // GitHub / Gitea の webhook に設定した secret（必須）
+    secret: String,
+    targets: Vec<WebhookTarget>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DeliveryResult {
+    target: String,
+    ok: bool,
+    error: Option<ActionError>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Delivery {
+    seq: u64,
+    // X-GitHub-Delivery / X-Gitea-Delivery
+    delivery_id: Option<String>,
+    received_at: u64,
+    // github | gitea | unknown
+    provider: String,
+    event: String,
+    repo: Option<String>,
+    branch: Option<String>,
+    sha: Option<String>,
+    // rejected | ignored | queued | done | failed
+    status: String,
+    http_status: u16,
+    message: String,
+    targets: Vec<String>,
+    results: Vec<DeliveryResult>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WebhookStatus {
+    running: bool,
+    bind: Option<String>,
+    path: Option<String>,
+    targets: usize,
+    error: Option<ActionError>,
+}
+
+struct Running {
+    bind: String,
+    path: String,
+    targets: usize,
+    stop: Arc<AtomicBool>,
+    listener: thread::JoinHandle<()>,
+}
+
+#[derive(Default)]
+pub(crate) struct WebhookRegistry {
+    running: Mutex<Option<Running>>,
+    log: Arc<Mutex<VecDeque<Delivery>>>,
+    seq: Arc<AtomicU64>,
+}
+
+struct Job {
+    seq: u64,
+    target: String,
+    req: RunActionRequest,
+}
+
+// listener スレッドが持つもの
+struct Ctx {
+    app: AppHandle,
+    path: String,
+    secret: String,
+    targets: Vec<WebhookTarget>,
+    log: Arc<Mutex<VecDeque<Delivery>>>,
+    seq: Arc<AtomicU64>,
+    queue: mpsc::Sender<Job>,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn hook_err(code: &str, message: &str, detail: Option<String>) -> ActionError {
+    ActionError {
+        code: code.into(),
+        severity: "ERROR".into(),
+        message: message.into(),
+        detail,
+    }
+}
+
+// HMAC-SHA256（RFC 2104）。hmac crate は入れずに sha2 だけで組む
+fn hmac_sha256(key: &[u8], msg: &[u8]) -> [u8; 32] {
+    const BLOCK: usize = 64;
+    let mut k = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        k[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        k[..key.len()].copy_from_slice(key);
+    }
+    let mut ipad = [0x36u8; BLOCK];
+    let mut opad = [0x5cu8; BLOCK];
+    for i in 0..BLOCK {
+        ipad[i] ^= k[i];
+        opad[i] ^= k[i];
+    }
+    let inner = Sha256::new()
+        .chain_update(ipad)
+        .chain_update(msg)
+        .finalize();
+    Sha256::new()
+        .chain_update(opad)
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+// 長さ以外で早く抜けない比較
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// GitHub: X-Hub-Signature-256: sha256=<hex>、Gitea: X-Gitea-Signature: <hex>
+fn verify_signature(secret: &str, headers: &HashMap<String, String>, body: &[u8]) -> bool {
+    let given = headers
+        .get("x-hub-signature-256")
+        .map(|s| s.trim().strip_prefix("sha256=").unwrap_or("").to_string())
+        .or_else(|| headers.get("x-gitea-signature").map(|s| s.trim().into()));
+    let Some(given) = given.and_then(|g| hex::decode(g).ok()) else {
+        return false;
+    };
+    constant_time_eq(&hmac_sha256(secret.as_bytes(), body), &given)
+}
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+// 最小限の HTTP/1.1（Content-Length 必須、chunked は受けない）
+fn read_request(stream: &mut TcpStream) -> Result<HttpRequest, (u16, &'static str)> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let started = Instant::now();
+    let header_end = loop {
+        if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i;
+        }
+        if buf.len() > MAX_HEADER_BYTES {
+            return Err((431, "headers too large"));
+        }
+        if started.elapsed() > HEADER_DEADLINE {
+            return Err((408, "request timeout"));
+        }
+        match stream.read(&mut chunk) {
+            Ok(0) | Err(_) => return Err((400, "incomplete request")),
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let mut lines = head.split("\r\n");
+    let mut first = lines.next().unwrap_or("").split_whitespace();
+    let method = first.next().unwrap_or("").to_string();
+    let path = first.next().unwrap_or("").to_string();
+    let headers: HashMap<String, String> = lines
+        .filter_map(|l| l.split_once(':'))
+        .map(|(k, v)| (k.trim().to_ascii_lowercase(), v.trim().to_string()))
+        .collect();
+
+    if headers
+        .get("transfer-encoding")
+        .is_some_and(|t| t.to_ascii_lowercase().contains("chunked"))
+    {
+        return Err((411, "chunked body is not supported"));
+    }
+    let len = match headers.get("content-length") {
+        Some(v) => v
+            .parse::<usize>()
+            .map_err(|_| (400, "bad content-length"))?,
+        None if method == "POST" => return Err((411, "content-length required")),
+        None => 0,
+    };
+    if len > MAX_BODY_BYTES {
+        return Err((413, "payload too large"));
+    }
+
+    let mut body = buf[header_end + 4..].to_vec();
+    while body.len() < len {
+        match stream.read(&mut chunk) {
+            Ok(0) | Err(_) => return Err((400, "incomplete body")),
+            Ok(n) => body.extend_from_slice(&chunk[..n]),
+        }
+    }
+    body.truncate(len);
+
+    Ok(HttpRequest {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+fn respond(stream: &mut TcpStream, status: u16, message: &str) {
+    let reason = match status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        411 => "Length Required",
+        408 => "Request Timeout",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        _ => "Error",
+    };
+    let body = format!("{}\n", message);
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    );
+    let _ = stream.flush();
+}
+
+impl Delivery {
+    fn new(seq: u64) -> Self {
+        Delivery {
+            seq,
+            delivery_id: None,
+            received_at: now_millis(),
+            provider: "unknown".into(),
+            event: "".into(),
+            repo: None,
+            branch: None,
+            sha: None,
+            status: "rejected".into(),
+            http_status: 400,
+            message: "".into(),
+            targets: Vec::new(),
+            results: Vec::new(),
+        }
+    }
+
+    fn finish(&mut self, code: u16, status: &str, msg: &str) {
+        self.http_status = code;
+        self.status = status.into();
+        self.message = msg.into();
+    }
+}
+
+fn same_repo(a: &str, b: &str) -> bool {
+    match (remote_url::parse(a), remote_url::parse(b)) {
+        (Some(x), Some(y)) => x.normalized() == y.normalized(),
+        _ => false,
+    }
+}
+
+impl Ctx {
+    fn record(&self, d: Delivery) {
+        let _ = self.app.emit(EVENT_DELIVERY, d.clone());
+        let mut log = self.log.lock().unwrap();
+        log.push_back(d);
+        while log.len() > LOG_CAP {
+            log.pop_front();
+        }
+    }
+
+    fn handle(&self, stream: &mut TcpStream) {
+        let seq = self.seq.fetch_add(1, Ordering::Relaxed);
+        let rejected = |code: u16, msg: &str| {
+            let mut d = Delivery::new(seq);
+            d.finish(code, "rejected", msg);
+            d
+        };
+        let req = match read_request(stream) {
+            Ok(r) => r,
+            Err((code, msg)) => {
+                respond(stream, code, msg);
+                return self.record(rejected(code, msg));
+            }
+        };
+        if req.path.split('?').next() != Some(self.path.as_str()) {
+            // 関係ないパスは log に残さない
+            respond(stream, 404, "not found");
+            return;
+        }
+        if req.method != "POST" {
+            respond(stream, 405, "POST only");
+            return self.record(rejected(405, "POST only"));
+        }
+
+        let (d, jobs) = process(seq, &self.secret, &req.headers, &req.body, &self.targets);
+        respond(stream, d.http_status, &d.message);
+        self.record(d);
+        for j in jobs {
+            let _ = self.queue.send(j);
+        }
+    }
+}
+
+/// 署名 → イベント → 対象環境の順に判定する（HTTP の読み書きはしない）。jobs が空なら何も実行しない
+fn process(
+    seq: u64,
+    secret: &str,
+    h: &HashMap<String, String>,
+    body: &[u8],
+    targets: &[WebhookTarget],
+) -> (Delivery, Vec<Job>) {
+    let mut d = Delivery::new(seq);
+    let done = |mut d: Delivery, code: u16, status: &str, msg: &str| {
+        d.finish(code, status, msg);
+        (d, Vec::new())
+    };
+
+    // Gitea は X-GitHub-Event も付けてくるので先に見る
+    if let Some(e) = h.get("x-gitea-event") {
+        d.provider = "gitea".into();
+        d.event = e.clone();
+        d.delivery_id = h.get("x-gitea-delivery").cloned();
+    } else if let Some(e) = h.get("x-github-event") {
+        d.provider = "github".into();
+        d.event = e.clone();
+        d.delivery_id = h.get("x-github-delivery").cloned();
+    }
+
+    if !verify_signature(secret, h, body) {
+        return done(d, 401, "rejected", "signature mismatch");
+    }
+    if d.event == "ping" {
+        return done(d, 200, "ignored", "pong");
+    }
+    if d.event != "push" {
+        return done(d, 202, "ignored", "not a push event");
+    }
+
+    let Ok(v) = serde_json::from_slice::<serde_json::Value>(body) else {
+        return done(d, 400, "rejected", "invalid json");
+    };
+    let s = |p: &str| v.pointer(p).and_then(|x| x.as_str()).map(String::from);
+    d.repo = s("/repository/full_name");
+    d.sha = s("/after");
+    let Some(branch) = s("/ref").and_then(|r| r.strip_prefix("refs/heads/").map(String::from))
+    else {
+        return done(d, 202, "ignored", "not a branch push");
+    };
+    d.branch = Some(branch.clone());
+    if v.get("deleted").and_then(|x| x.as_bool()) == Some(true) {
+        return done(d, 202, "ignored", "branch deleted");
+    }
+
+    let urls: Vec<String> = [
+        "/repository/clone_url",
+        "/repository/ssh_url",
+        "/repository/html_url",
+    ]
+    .iter()
+    .filter_map(|p| s(p))
+    .collect();
+    let mut jobs = Vec::new();
+    for t in targets {
+        let want = t
+            .branch
+            .as_deref()
+            .map(str::trim)
+            .filter(|b| !b.is_empty())
+            .unwrap_or(t.request.branch.trim());
+        if want != branch || !urls.iter().any(|u| same_repo(u, &t.repo_url)) {
+            continue;
+        }
+        let label = t
+            .label
+            .clone()
+            .filter(|l| !l.trim().is_empty())
+            .unwrap_or_else(|| t.request.env_key.clone());
+        let mut req = t.request.clone();
+        req.action = "pull".into();
+        d.targets.push(label.clone());
+        jobs.push(Job {
+            seq: d.seq,
+            target: label,
+            req,
+        });
+    }
+
+    if jobs.is_empty() {
+        return done(d, 202, "ignored", "no matching environment");
+    }
+    let msg = format!("queued pull for {}", d.targets.join(", "));
+    d.finish(202, "queued", &msg);
+    (d, jobs)
+}
+
+// 1 本ずつ順に pull する（同じ環境に並行して git を走らせない）
+fn worker(app: AppHandle, log: Arc<Mutex<VecDeque<Delivery>>>, rx: mpsc::Receiver<Job>) {
+    for job in rx {
+        let out = crate::run_action(job.req);
+        let result = DeliveryResult {
+            target: job.target,
+            ok: out.ok,
+            error: out.error,
+        };
+        let updated = {
+            let mut log = log.lock().unwrap();
+            log.iter_mut().find(|d| d.seq == job.seq).map(|d| {
+                d.results.push(result);
+                if d.results.len() >= d.targets.len() {
+                    d.status = if d.results.iter().all(|r| r.ok) {
+                        "done"
+                    } else {
+                        "failed"
+                    }
+                    .into();
+                }
+                d.clone()
+            })
+        };
+        if let Some(d) = updated {
+            let _ = app.emit(EVENT_DELIVERY, d);
+        }
+    }
+}
+
+// stop が立つまで accept する。遅い接続で accept が止まらないよう、1 接続ずつ別スレッドで読む
+fn serve(
+    listener: TcpListener,
+    stop: Arc<AtomicBool>,
+    handle: impl Fn(&mut TcpStream) + Send + Sync + 'static,
+) {
+    let handle = Arc::new(handle);
+    let active = Arc::new(AtomicUsize::new(0));
+    while !stop.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((mut stream, _)) => {
+                if active.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                    active.fetch_sub(1, Ordering::SeqCst);
+                    continue;
+                }
+                let _ = stream.set_nonblocking(false);
+                let _ = stream.set_read_timeout(Some(Duration::from_secs(10)));
+                let _ = stream.set_write_timeout(Some(Duration::from_secs(10)));
+                let handle = handle.clone();
+                let active = active.clone();
+                thread::spawn(move || {
+                    handle(&mut stream);
+                    active.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(100));
+            }
+            Err(_) => thread::sleep(Duration::from_millis(100)),
+        }
+    }
+}
+
+fn stop_running(registry: &WebhookRegistry) {
+    let running = registry.running.lock().unwrap().take();
+    if let Some(r) = running {
+        r.stop.store(true, Ordering::Relaxed);
+        let _ = r.listener.join();
+    }
+}
+
+fn status_of(registry: &WebhookRegistry, error: Option<ActionError>) -> WebhookStatus {
+    let running = registry.running.lock().unwrap();
+    WebhookStatus {
+        running: running.is_some(),
+        bind: running.as_ref().map(|r| r.bind.clone()),
+        path: running.as_ref().map(|r| r.path.clone()),
+        targets: running.as_ref().map(|r| r.targets).unwrap_or(0),
+        error,
+    }
+}
+
+/// listener を起動する（起動中なら新しい方を bind できてから入れ替える）
+#[tauri::command(rename_all = "camelCase")]
+pub(crate) fn webhook_start(
+    app: AppHandle,
+    registry: State<'_, WebhookRegistry>,
+    config: WebhookConfig,
+) -> WebhookStatus {
+    if config.secret.trim().is_empty() {
+        return status_of(
+            &registry,
+            Some(hook_err("CFG-0001", "webhook secret is required", None)),
+        );
+    }
+    let path = config
+        .path
+        .as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .unwrap_or(DEFAULT_PATH);
+    let path = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/{}", path)
+    };
+    let bind = config
+        .bind
+        .as_deref()
+        .map(str::trim)
+        .filter(|b| !b.is_empty())
+        .unwrap_or(DEFAULT_BIND)
+        .to_string();
+
+    // 先に bind して、失敗したら動いている listener はそのまま残す。
+    // 同じアドレスで再起動するときだけ、古い方を止めてから bind し直す
+    let same_bind = registry
+        .running
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|r| r.bind == bind);
+    let bound = match TcpListener::bind(&bind) {
+        Err(e) if same_bind && e.kind() == std::io::ErrorKind::AddrInUse => {
+            stop_running(&registry);
+            TcpListener::bind(&bind)
+        }
+        r => r,
+    };
+    let listener = match bound {
+        Ok(l) => l,
+        Err(e) => {
+            return status_of(
+                &registry,
+                Some(hook_err(
+                    "HOOK-0100",
+                    "failed to bind webhook listener",
+                    Some(format!("{}: {}", bind, e)),
+                )),
+            )
+        }
+    };
+    // stop を見られるように accept はノンブロッキングで回す
+    if let Err(e) = listener.set_nonblocking(true) {
+        return status_of(
+            &registry,
+            Some(hook_err(
+                "HOOK-0100",
+                "failed to bind webhook listener",
+                Some(e.to_string()),
+            )),
+        );
+    }
+    // 新しい listener の準備ができてから古い方を止めて入れ替える
+    stop_running(&registry);
+
+    let (tx, rx) = mpsc::channel::<Job>();
+    {
+        let app = app.clone();
+        let log = registry.log.clone();
+        thread::spawn(move || worker(app, log, rx));
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let targets = config.targets.len();
+    let ctx = Ctx {
+        app,
+        path: path.clone(),
+        secret: config.secret,
+        targets: config.targets,
+        log: registry.log.clone(),
+        seq: registry.seq.clone(),
+        queue: tx,
+    };
+    let handle = thread::spawn({
+        let stop = stop.clone();
+        // ctx（= queue の送信側）は処理中の接続が終わると drop され、worker は残りを片付けて終わる
+        move || serve(listener, stop, move |stream| ctx.handle(stream))
+    });
+
+    *registry.running.lock().unwrap() = Some(Running {
+        bind,
+        path,
+        targets,
+        stop,
+        listener: handle,
+    });
+    status_of(&registry, None)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub(crate) fn webhook_stop(registry: State<'_, WebhookRegistry>) -> WebhookStatus {
+    stop_running(&registry);
+    status_of(&registry, None)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub(crate) fn webhook_status(registry: State<'_, WebhookRegistry>) -> WebhookStatus {
+    status_of(&registry, None)
+}
+
+/// 新しい順に最大 limit 件（default 50）
+#[tauri::command(rename_all = "camelCase")]
+pub(crate) fn webhook_deliveries(
+    registry: State<'_, WebhookRegistry>,
+    limit: Option<usize>,
+) -> Vec<Delivery> {
+    let log = registry.log.lock().unwrap();
+    log.iter()
+        .rev()
+        .take(limit.unwrap_or(50))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "s3cret";
+
+    fn target(label: Option<&str>, repo_url: &str, branch: &str) -> WebhookTarget {
+        serde_json::from_value(serde_json::json!({
+            "label": label,
+            "repoUrl": repo_url,
+            "request": {
+                "mode": "ssh",
+                "envKey": "prod",
+                "action": "merge",
+                "localPath": "",
+                "remotePath": "/srv/app",
+                "branch": branch,
+                "gitPath": "",
+                "sshPath": "",
+                "ssh": {"host": "h", "user": "u", "port": null, "keyPath": null},
+                "mergeFromBranch": null,
+                "commitMessage": null,
+            },
+        }))
+        .unwrap()
+    }
+
+    fn push(branch: &str, deleted: bool) -> Vec<u8> {
+        serde_json::json!({
+            "ref": format!("refs/heads/{}", branch),
+            "after": "0123456789abcdef0123456789abcdef01234567",
+            "deleted": deleted,
+            "repository": {
+                "full_name": "Octo/App",
+                "clone_url": "https://github.com/Octo/App.git",
+                "ssh_url": "git@github.com:Octo/App.git",
+            },
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    fn github(event: &str, body: &[u8], secret: &str) -> HashMap<String, String> {
+        HashMap::from([
+            ("x-github-event".to_string(), event.to_string()),
+            ("x-github-delivery".to_string(), "d-1".to_string()),
+            (
+                "x-hub-signature-256".to_string(),
+                format!(
+                    "sha256={}",
+                    hex::encode(hmac_sha256(secret.as_bytes(), body))
+                ),
+            ),
+        ])
+    }
+
+    fn gitea(event: &str, body: &[u8], secret: &str) -> HashMap<String, String> {
+        HashMap::from([
+            ("x-gitea-event".to_string(), event.to_string()),
+            // Gitea は GitHub のヘッダも付ける
+            ("x-github-event".to_string(), event.to_string()),
+            ("x-gitea-delivery".to_string(), "g-1".to_string()),
+            (
+                "x-gitea-signature".to_string(),
+                hex::encode(hmac_sha256(secret.as_bytes(), body)),
+            ),
+        ])
+    }
+
+    #[test]
+    fn hmac_sha256_matches_rfc4231() {
+        let cases: [(Vec<u8>, Vec<u8>, &str); 4] = [
+            (
+                vec![0x0b; 20],
+                b"Hi There".to_vec(),
+                "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+            ),
+            (
+                b"Jefe".to_vec(),
+                b"what do ya want for nothing?".to_vec(),
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            ),
+            (
+                vec![0xaa; 20],
+                vec![0xdd; 50],
+                "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe",
+            ),
+            // block より長い key は先に hash する
+            (
+                vec![0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First".to_vec(),
+                "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+            ),
+        ];
+        for (key, msg, want) in cases {
+            assert_eq!(hex::encode(hmac_sha256(&key, &msg)), want);
+        }
+    }
+
+    #[test]
+    fn signatures_are_checked() {
+        let body = push("main", false);
+        let targets = [target(None, "git@github.com:octo/app.git", "main")];
+
+        let (d, jobs) = process(1, SECRET, &github("push", &body, SECRET), &body, &targets);
+        assert_eq!(
+            (d.provider.as_str(), d.status.as_str()),
+            ("github", "queued")
+        );
+        assert_eq!(d.delivery_id.as_deref(), Some("d-1"));
+        assert_eq!(jobs.len(), 1);
+
+        let (d, jobs) = process(2, SECRET, &gitea("push", &body, SECRET), &body, &targets);
+        assert_eq!(
+            (d.provider.as_str(), d.status.as_str()),
+            ("gitea", "queued")
+        );
+        assert_eq!(d.delivery_id.as_deref(), Some("g-1"));
+        assert_eq!(jobs.len(), 1);
+
+        for h in [
+            github("push", &body, "wrong"),
+            gitea("push", &body, "wrong"),
+            HashMap::from([("x-github-event".to_string(), "push".to_string())]),
+        ] {
+            let (d, jobs) = process(3, SECRET, &h, &body, &targets);
+            assert_eq!((d.http_status, d.status.as_str()), (401, "rejected"));
+            assert!(jobs.is_empty());
+        }
+
+        // 署名した後に body を変えたら通さない
+        let mut tampered = body.clone();
+        tampered.push(b' ');
+        let (d, _) = process(
+            4,
+            SECRET,
+            &github("push", &body, SECRET),
+            &tampered,
+            &targets,
+        );
+        assert_eq!(d.http_status, 401);
+    }
+
+    #[test]
+    fn non_push_events_are_ignored() {
+        let targets = [target(None, "https://github.com/octo/app", "main")];
+        let run = |event: &str, body: &[u8]| {
+            let (d, jobs) = process(1, SECRET, &github(event, body, SECRET), body, &targets);
+            assert!(jobs.is_empty(), "{}", event);
+            (d.http_status, d.status, d.message)
+        };
+
+        assert_eq!(run("ping", b"{}"), (200, "ignored".into(), "pong".into()));
+        assert_eq!(
+            run("issues", b"{}"),
+            (202, "ignored".into(), "not a push event".into())
+        );
+        assert_eq!(
+            run("push", &push("main", true)),
+            (202, "ignored".into(), "branch deleted".into())
+        );
+        let tag = serde_json::json!({"ref": "refs/tags/v1.0"}).to_string();
+        assert_eq!(
+            run("push", tag.as_bytes()),
+            (202, "ignored".into(), "not a branch push".into())
+        );
+        assert_eq!(
+            run("push", b"not json"),
+            (400, "rejected".into(), "invalid json".into())
+        );
+    }
+
+    #[test]
+    fn pushes_match_repo_and_branch() {
+        let mut staging = target(Some("staging"), "git@github.com:octo/app.git", "develop");
+        staging.branch = Some("main".into());
+        let targets = [
+            target(None, "https://github.com/octo/app.git", "main"),
+            staging,
+            target(Some("other"), "git@github.com:octo/other.git", "main"),
+            target(Some("dev"), "git@github.com:octo/app.git", "develop"),
+        ];
+
+        let body = push("main", false);
+        let (d, jobs) = process(7, SECRET, &github("push", &body, SECRET), &body, &targets);
+        assert_eq!(d.targets, ["prod", "staging"]);
+        assert_eq!(d.branch.as_deref(), Some("main"));
+        assert_eq!(d.repo.as_deref(), Some("Octo/App"));
+        assert_eq!(d.message, "queued pull for prod, staging");
+        assert!(jobs.iter().all(|j| j.seq == 7 && j.req.action == "pull"));
+
+        let body = push("feature", false);
+        let (d, jobs) = process(8, SECRET, &github("push", &body, SECRET), &body, &targets);
+        assert!(jobs.is_empty());
+        assert_eq!(d.message, "no matching environment");
+    }
+
+    #[test]
+    fn slow_connection_does_not_block_others() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let stop = Arc::new(AtomicBool::new(false));
+        let server = thread::spawn({
+            let stop = stop.clone();
+            move || {
+                serve(listener, stop, |stream| match read_request(stream) {
+                    Ok(r) => respond(stream, 200, &r.path),
+                    Err((code, msg)) => respond(stream, code, msg),
+                })
+            }
+        });
+
+        // ヘッダを送り終えない接続
+        let mut slow = TcpStream::connect(addr).unwrap();
+        slow.write_all(b"POST /webhook HTTP/1.1\r\n").unwrap();
+        thread::sleep(Duration::from_millis(300));
+
+        let mut fast = TcpStream::connect(addr).unwrap();
+        fast.set_read_timeout(Some(Duration::from_secs(3))).unwrap();
+        fast.write_all(b"GET /ping HTTP/1.1\r\n\r\n").unwrap();
+        let mut res = String::new();
+        fast.read_to_string(&mut res).unwrap();
+        assert!(res.starts_with("HTTP/1.1 200"), "{}", res);
+        assert!(res.ends_with("/ping\n"), "{}", res);
+
+        stop.store(true, Ordering::Relaxed);
+        server.join().unwrap();
+        drop(slow);
+    }
+}
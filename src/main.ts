@@ -30,6 +30,7 @@ type SshConfig = {
 type GitHubConfig = {
   username: string;
   token: string; // PAT
+  apiBaseUrl: string; // 空なら api.github.com（GHE は https://<host>/api/v3）
 };
 
 type ProjectEnv = {
@@ -94,12 +95,29 @@ type BranchListWire = {
   ok: boolean;
   branches: string[];
   stderr?: string | null;
+  defaultBranch?: string | null;
 };
 
 type DetectRepoWire = {
   path: string;
   originUrl?: string | null;
   name?: string | null;
+  remotes?: { name: string; fetchUrl?: string | null; pushUrl?: string | null }[];
+  // remote URL で対応付いた既存プロジェクト（フロント側で付与）
+  projectId?: string | null;
+};
+
+type RepoProjectMatchWire = {
+  repoPath: string;
+  projectId: string;
+  envKeys: string[];
+  remoteName?: string | null;
+  remoteUrl: string;
+};
+
+type MatchReposResultWire = {
+  matches: RepoProjectMatchWire[];
+  unmatchedRepos: string[];
 };
 
 type StepResultWire = {
@@ -138,15 +156,11 @@ type GitHubRepo = {
   isPrivate: boolean;
 };
 
-type DetectRepoResultWire = {
-  path: string;
-  name?: string | null;
-  hasRemote?: boolean;
-  remoteUrl?: string | null;
-};
-
-type DetectReposResultWire = {
-  repos: DetectRepoResultWire[];
+type GitHubRepoListWire = {
+  ok: boolean;
+  repos?: GitHubRepo[] | null;
+  truncated?: boolean;
+  error?: ActionErrorWire | null;
 };
 
 type GitHubTarget =
@@ -199,6 +213,8 @@ type AppState = {
   githubOpen: boolean;
   githubLoading: boolean;
   githubRepos: GitHubRepo[];
+  githubOrg: string;
+  githubQuery: string;
   githubTarget: GitHubTarget | null;
   githubError?: string;
 
@@ -248,6 +264,8 @@ const state: AppState = {
   githubOpen: false,
   githubLoading: false,
   githubRepos: [],
+  githubOrg: "",
+  githubQuery: "",
   githubTarget: null,
   githubError: undefined,
 
@@ -301,7 +319,7 @@ function defaultConfig(): AppConfig {
   return {
     toolPaths: { gitPath: "", sshPath: "" },
     ssh: { host: "", user: "", port: 22, keyPath: "" },
-    github: { username: "", token: "" },
+    github: { username: "", token: "", apiBaseUrl: "" },
     projects: [],
   };
 }
@@ -374,6 +392,7 @@ function migrateConfig(x: any): AppConfig {
   if (x?.github) {
     cfg.github.username = String(x.github.username ?? "");
     cfg.github.token = String(x.github.token ?? "");
+    cfg.github.apiBaseUrl = String(x.github.apiBaseUrl ?? "");
   }
 
   const projectsRaw = Array.isArray(x?.projects) ? x.projects : [];
@@ -735,6 +754,11 @@ function renderSettingsPanel(): string {
             <input class="input" id="inpGitHubToken" value="${escapeAttr(state.config.github.token)}" placeholder="github_pat_... (保存はlocalStorage)"/>
           </label>
 
+          <label class="field">
+            <div class="label">API base URL</div>
+            <input class="input" id="inpGitHubApiBaseUrl" value="${escapeAttr(state.config.github.apiBaseUrl)}" placeholder="https://api.github.com（GHE: https://host/api/v3）"/>
+          </label>
+
           <div class="muted" style="margin-top:8px; font-size:12px;">
             ※ repo一覧閲覧用です。clone/SSH認証はまだ範囲外（見る→repoUrlへ流し込み）まで。
           </div>
@@ -895,7 +919,9 @@ function renderDetectModal(): string {
       .map((r) => {
         const key = r.path;
         const checked = !!state.detectSelected[key];
-        const label = r.originUrl ? `${r.path} (${r.originUrl})` : r.path;
+        const linked = r.projectId ? state.config.projects.find((p) => p.id === r.projectId) : undefined;
+        const base = r.originUrl ? `${r.path} (${r.originUrl})` : r.path;
+        const label = linked ? `${base} → ${linked.name}` : base;
         return `
             <label class="detectRow">
               <input type="checkbox" data-detect-check="${escapeAttr(key)}"${checked ? " checked" : ""}/>
@@ -988,6 +1014,8 @@ function renderGitHubModal(): string {
         </div>
 
         <div class="row" style="margin-top:12px;">
+          <input class="input" id="inpGithubOrg" value="${escapeAttr(state.githubOrg)}" placeholder="org（空なら自分の repo）"/>
+          <input class="input" id="inpGithubQuery" value="${escapeAttr(state.githubQuery)}" placeholder="search"/>
           <button class="btn" id="btnGithubLoad"${state.githubLoading ? " disabled" : ""}>
             ${state.githubLoading ? "Loading..." : "Load my repos"}
           </button>
@@ -1151,6 +1179,7 @@ function bindHandlers(): void {
 
     state.config.github.username = (byId<HTMLInputElement>("inpGitHubUsername")?.value ?? "").trim();
     state.config.github.token = (byId<HTMLInputElement>("inpGitHubToken")?.value ?? "").trim();
+    state.config.github.apiBaseUrl = (byId<HTMLInputElement>("inpGitHubApiBaseUrl")?.value ?? "").trim();
 
     saveConfig();
     toast("Saved");
@@ -1605,7 +1634,7 @@ async function runSshConnect(): Promise<void> {
           maxRepos: 50,
         });
 
-        const list = Array.isArray(repos) ? repos : [];
+        const list = await linkDetectedRepos(Array.isArray(repos) ? repos : []);
         if (!list.length) {
           toast("remote repos not found (0)");
         } else {
@@ -1622,7 +1651,7 @@ async function runSshConnect(): Promise<void> {
           for (const r of list) {
             const rp = r?.path?.trim();
             if (!rp) continue;
-            if (existingRemotePaths.has(rp)) continue;
+            if (r.projectId || existingRemotePaths.has(rp)) continue;
 
             const proj = defaultProject();
             proj.name = r.name && r.name.trim() ? r.name.trim() : basename(rp);
@@ -1719,22 +1748,16 @@ async function runDetectRepos(): Promise<void> {
 
     try {
       const gitPath = state.config.toolPaths.gitPath || "";
-      const res = (await invoke("detect_local_repos", {
+      const repos = await invoke<DetectRepoWire[]>("detect_local_repos", {
         rootPath,
         maxDepth,
         gitPath,
-      })) as DetectReposResultWire;
+      });
 
-      state.detectResults = res.repos.map((r) => ({
-        path: r.path,
-        name: r.name,
-        envKey: "local",
-        hasRemote: r.hasRemote,
-        remoteUrl: r.remoteUrl ?? null,
-      }));
+      state.detectResults = await linkDetectedRepos(Array.isArray(repos) ? repos : []);
 
       state.detectSelected = {};
-      for (const r of state.detectResults) state.detectSelected[r.path] = true;
+      for (const r of state.detectResults) state.detectSelected[r.path] = !r.projectId;
 
       if (state.detectResults.length === 0) {
         state.detectError = "No git repositories found.";
@@ -1773,24 +1796,18 @@ async function runDetectRepos(): Promise<void> {
     const ssh = state.config.ssh;
     const gitPath = state.config.toolPaths.gitPath || "";
     const sshPath = state.config.toolPaths.sshPath || "";
-    const res = (await invoke("detect_remote_repos", {
+    const repos = await invoke<DetectRepoWire[]>("detect_remote_repos", {
       rootPath,
       maxDepth,
       gitPath,
       sshPath,
       ssh,
-    })) as DetectReposResultWire;
-
-    state.detectResults = res.repos.map((r) => ({
-      path: r.path,
-      name: r.name,
-      envKey: "ssh",
-      hasRemote: r.hasRemote,
-      remoteUrl: r.remoteUrl ?? null,
-    }));
+    });
+
+    state.detectResults = await linkDetectedRepos(Array.isArray(repos) ? repos : []);
 
     state.detectSelected = {};
-    for (const r of state.detectResults) state.detectSelected[r.path] = true;
+    for (const r of state.detectResults) state.detectSelected[r.path] = !r.projectId;
 
     if (state.detectResults.length === 0) {
       state.detectError = "No git repositories found on remote.";
@@ -1809,6 +1826,47 @@ async function runDetectRepos(): Promise<void> {
 
 
 
+// 検出した repo を remote URL で既存プロジェクトに対応付け、空いている localPath / remotePath に紐づける
+async function linkDetectedRepos(repos: DetectRepoWire[]): Promise<DetectRepoWire[]> {
+  if (!repos.length || !state.config.projects.length) return repos;
+
+  let res: MatchReposResultWire;
+  try {
+    res = await invoke<MatchReposResultWire>("match_repos_to_projects", {
+      repos,
+      projects: state.config.projects,
+    });
+  } catch {
+    return repos;
+  }
+
+  const projectByPath = new Map<string, string>();
+  let linked = 0;
+  for (const m of res.matches) {
+    if (!projectByPath.has(m.repoPath)) projectByPath.set(m.repoPath, m.projectId);
+    const proj = state.config.projects.find((p) => p.id === m.projectId);
+    if (!proj) continue;
+    for (const k of m.envKeys.filter((x): x is EnvKey => x === "test" || x === "deploy")) {
+      const env = proj[k];
+      if (state.ui.mode === "local") {
+        if (!env.localPath.trim()) {
+          env.localPath = m.repoPath;
+          linked++;
+        }
+      } else if (!env.remotePath.trim()) {
+        env.remotePath = m.repoPath;
+        linked++;
+      }
+    }
+  }
+  if (linked) {
+    saveConfig();
+    toast(`既存プロジェクトに紐づけました: ${linked}`);
+  }
+
+  return repos.map((r) => ({ ...r, projectId: projectByPath.get(r.path) ?? null }));
+}
+
 function addDetectedReposToProjects(): void {
   const selected = state.detectResults.filter((r) => !!state.detectSelected[r.path]);
   if (!selected.length) {
@@ -1824,7 +1882,7 @@ function addDetectedReposToProjects(): void {
 
   const newProjects: Project[] = [];
   for (const r of selected) {
-    if (existLocalPaths.has(r.path)) continue;
+    if (r.projectId || existLocalPaths.has(r.path)) continue;
 
     const proj = defaultProject();
     proj.name = (r.name && r.name.trim()) ? r.name.trim() : basename(r.path);
@@ -1889,40 +1947,48 @@ function closeGitHubModal(): void {
 
 async function loadMyGitHubRepos(): Promise<void> {
   const token = state.config.github.token.trim();
-  if (!token) {
+  state.githubOrg = (byId<HTMLInputElement>("inpGithubOrg")?.value ?? "").trim();
+  state.githubQuery = (byId<HTMLInputElement>("inpGithubQuery")?.value ?? "").trim();
+  if (!token && !state.githubOrg) {
     state.githubError = "PAT(token) が未設定です（Settingsで入力してください）";
     render();
     return;
   }
 
+  if (!isTauri()) {
+    state.githubError = "Tauri環境ではないため取得できません";
+    render();
+    return;
+  }
+
   state.githubLoading = true;
   state.githubError = undefined;
   render();
 
   try {
-    const res = await fetch("https://api.github.com/user/repos?per_page=100&sort=updated", {
-      headers: {
-        Authorization: `Bearer ${token}`,
-        Accept: "application/vnd.github+json",
-      },
+    const res = await invoke<GitHubRepoListWire>("github_list_repos", {
+      token: token || null,
+      apiBaseUrl: state.config.github.apiBaseUrl.trim() || null,
+      org: state.githubOrg || null,
+      query: state.githubQuery || null,
     });
 
-    if (!res.ok) {
-      const text = await res.text().catch(() => "");
-      throw new Error(`GitHub API failed: ${res.status} ${res.statusText} ${text}`);
+    if (!res?.ok) {
+      const err = res?.error;
+      throw new Error(err ? `[${err.code}] ${err.message}${err.detail ? ` ${err.detail}` : ""}` : "GitHub API failed");
     }
 
-    const json = (await res.json()) as any[];
-    state.githubRepos = (json || []).map((r) => ({
+    state.githubRepos = (res.repos || []).map((r) => ({
       id: Number(r.id),
-      fullName: String(r.full_name ?? ""),
-      cloneUrl: String(r.clone_url ?? ""),
-      sshUrl: String(r.ssh_url ?? ""),
-      defaultBranch: String(r.default_branch ?? ""),
-      updatedAt: String(r.updated_at ?? ""),
-      stargazersCount: Number(r.stargazers_count ?? 0),
-      isPrivate: Boolean(r.private ?? false),
+      fullName: String(r.fullName ?? ""),
+      cloneUrl: String(r.cloneUrl ?? ""),
+      sshUrl: String(r.sshUrl ?? ""),
+      defaultBranch: String(r.defaultBranch ?? ""),
+      updatedAt: String(r.updatedAt ?? ""),
+      stargazersCount: Number(r.stargazersCount ?? 0),
+      isPrivate: Boolean(r.isPrivate ?? false),
     }));
+    if (res.truncated) toast("件数が多いため途中までです");
 
     state.githubLoading = false;
     render();
@@ -1977,16 +2043,19 @@ async function fetchBranches(kind: "add" | "edit", envKey: EnvKey): Promise<void
 
   try {
     const gitPath = state.config.toolPaths.gitPath.trim() || null;
-    const res = await invoke<BranchListWire>("list_branches", { repoUrl, gitPath });
+    // private repo 用: GitHub の URL にだけ保存済み PAT を渡す
+    const token = /github\.com[:/]/i.test(repoUrl) ? state.config.github.token.trim() || null : null;
+    const res = await invoke<BranchListWire>("list_branches", { repoUrl, gitPath, token });
 
     const branches = Array.isArray(res?.branches) ? res.branches : [];
     state.editingProject.branchOptions[envKey] = branches;
+    const fallback = res?.defaultBranch ?? branches[0] ?? "main";
 
     // current branch が空なら default に寄せる（無ければ main）
     if (envKey === "test") {
-      if (!state.editingProject.draft.test.branch.trim()) state.editingProject.draft.test.branch = branches[0] ?? "main";
+      if (!state.editingProject.draft.test.branch.trim()) state.editingProject.draft.test.branch = fallback;
     } else {
-      if (!state.editingProject.draft.deploy.branch.trim()) state.editingProject.draft.deploy.branch = branches[0] ?? "main";
+      if (!state.editingProject.draft.deploy.branch.trim()) state.editingProject.draft.deploy.branch = fallback;
     }
 
     state.editingProject.branchLoading[envKey] = false;